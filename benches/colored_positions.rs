@@ -0,0 +1,77 @@
+//! Compares the specialized reader for scaled integer coordinates with 8-bit colors against the generic readers.
+//!
+//! Run with `cargo bench --bench colored_positions`.
+
+use e57::{E57Writer, PointCloud, Reader, Record, RecordDataType, RecordName, RecordValue};
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+const POINTS: i64 = 2_000_000;
+const RUNS: usize = 5;
+
+/// Writes a file with scaled integer coordinates and 8-bit colors.
+///
+/// The additional constant invalid state record has no data,
+/// but the point cloud no longer matches the layout of the fast path.
+fn write(with_invalid_state: bool) -> Vec<u8> {
+	let xyz = RecordDataType::ScaledInteger { min: -100_000, max: 100_000, scale: 0.001 };
+	let rgb = RecordDataType::Integer { min: 0, max: 255 };
+	let mut prototype = vec![
+		Record { name: RecordName::CartesianX, data_type: xyz },
+		Record { name: RecordName::CartesianY, data_type: xyz },
+		Record { name: RecordName::CartesianZ, data_type: xyz },
+		Record { name: RecordName::ColorRed, data_type: rgb },
+		Record { name: RecordName::ColorGreen, data_type: rgb },
+		Record { name: RecordName::ColorBlue, data_type: rgb },
+	];
+	if with_invalid_state {
+		prototype.push(Record {
+			name:      RecordName::CartesianInvalidState,
+			data_type: RecordDataType::Integer { min: 0, max: 0 },
+		});
+	}
+	let points = (0..POINTS).map(move |i| {
+		let mut values = vec![
+			RecordValue::ScaledInteger(i % 100_000),
+			RecordValue::ScaledInteger(-(i % 777)),
+			RecordValue::ScaledInteger(i % 5),
+			RecordValue::Integer(i % 256),
+			RecordValue::Integer(i % 13),
+			RecordValue::Integer(i % 200),
+		];
+		if with_invalid_state {
+			values.push(RecordValue::Integer(0));
+		}
+		values
+	});
+	let pc = PointCloud::builder("{bench}")
+		.prototype(prototype)
+		.build()
+		.expect("Invalid point cloud");
+	let mut writer = E57Writer::new(Cursor::new(Vec::new())).expect("Failed to create writer");
+	writer.add_pointcloud(pc, points).expect("Failed to write point cloud");
+	writer.finalize().expect("Failed to finalize file").into_inner()
+}
+
+/// Returns the fastest of multiple runs.
+fn measure(bytes: &[u8]) -> Duration {
+	let mut reader = Reader::from_bytes(bytes).expect("Failed to open file");
+	let pc = reader.pointclouds()[0].clone();
+	(0..RUNS)
+		.map(|_| {
+			let start = Instant::now();
+			let points = reader.colored_positions(&pc).expect("Failed to read points");
+			assert_eq!(points.len(), POINTS as usize);
+			start.elapsed()
+		})
+		.min()
+		.expect("No runs")
+}
+
+fn main() {
+	let fast = measure(&write(false));
+	let generic = measure(&write(true));
+	println!("colored_positions with {POINTS} points");
+	println!("fast path:       {fast:?}");
+	println!("generic readers: {generic:?}");
+}
@@ -0,0 +1,142 @@
+use crate::pointcloud::pointclouds_from_document;
+use crate::reader::{parse_document, xml_range, xml_to_string};
+use crate::root::root_from_document;
+use crate::E57Writer;
+use crate::Error;
+use crate::Header;
+use crate::Point;
+use crate::PointCloud;
+use crate::RawValues;
+use crate::Record;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Adds point clouds to an existing E57 file.
+///
+/// The new binary sections are written after the end of the file.
+/// When finalizing, the new point clouds are inserted into the `data3D` vector of the existing XML section,
+/// the updated XML section is written after the new binary sections and the header is rewritten to point to it.
+///
+/// Limitations:
+/// - The previous XML section is not removed and stays in the file as unused data.
+/// - The rest of the existing XML section is kept as is, only missing extension namespaces are declared.
+/// - The existing file stays readable until the header is rewritten while finalizing.
+///   Dropping the appender without finalizing leaves the new binary sections as unused data.
+/// - The length of the existing file must be a multiple of the page size.
+pub struct E57Appender<T: Read + Write + Seek> {
+	writer: E57Writer<T>,
+	xml:    String,
+	guids:  Vec<String>,
+}
+
+impl E57Appender<File> {
+	/// Opens an existing E57 file to append point clouds.
+	pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+		let file = File::options().read(true).write(true).open(path)?;
+		Self::new(file)
+	}
+}
+
+impl<T: Read + Write + Seek> E57Appender<T> {
+	/// Reads the header and the XML section of an existing E57 file to append point clouds.
+	pub fn new(mut inner: T) -> Result<Self, Error> {
+		let file_length = inner.seek(SeekFrom::End(0))?;
+		inner.seek(SeekFrom::Start(0))?;
+		let header = Header::read(&mut inner)?;
+		let (xml_offset, xml_end) = xml_range(&header, file_length as usize)?;
+		let mut physical = vec![0_u8; xml_end - xml_offset];
+		inner.seek(SeekFrom::Start(xml_offset as u64))?;
+		inner.read_exact(&mut physical)?;
+		let mut xml_raw = physical
+			.iter()
+			.enumerate()
+			.filter(|(i, _)| (xml_offset + i) % 1024 < 1020)
+			.map(|(_, b)| *b)
+			.collect::<Vec<u8>>();
+		xml_raw.truncate(header.xml_length as usize);
+		let xml = xml_to_string(xml_raw)?;
+
+		let document = parse_document(&xml)?;
+		let root = root_from_document(&document)?;
+		let guids = pointclouds_from_document(&document)?
+			.into_iter()
+			.map(|pc| pc.guid)
+			.collect();
+		Ok(Self {
+			writer: E57Writer::resume(inner, root.guid, file_length)?,
+			xml,
+			guids,
+		})
+	}
+
+	/// Appends a point cloud with the raw values of all points in prototype order.
+	///
+	/// Fails like [`E57Writer::add_pointcloud`] or if the GUID is used by an existing point cloud.
+	pub fn add_pointcloud(&mut self, pc: PointCloud, points: impl Iterator<Item = RawValues>) -> Result<(), Error> {
+		if self.guids.contains(&pc.guid) {
+			return Error::Invalid(format!("Point cloud GUID '{}' is not unique", pc.guid)).throw();
+		}
+		self.writer.add_pointcloud(pc, points)
+	}
+
+	/// Appends a point cloud with a random GUID from points of the built-in point type.
+	///
+	/// See [`E57Writer::write_points`].
+	pub fn write_points(&mut self, prototype: &[Record], points: impl Iterator<Item = Point>) -> Result<(), Error> {
+		self.writer.write_points(prototype, points)
+	}
+
+	/// Writes the updated XML section and the header and returns the underlying writer.
+	pub fn finalize(self) -> Result<T, Error> {
+		let xml = self.updated_xml()?;
+		self.writer.finalize_with_xml(&xml)
+	}
+
+	/// Inserts the new point clouds into the existing XML section and declares missing namespaces.
+	fn updated_xml(&self) -> Result<String, Error> {
+		let document = parse_document(&self.xml)?;
+		let root = document
+			.descendants()
+			.find(|n| n.has_tag_name("e57Root"))
+			.ok_or(Error::Invalid("Unable to find e57Root tag in XML document".into()))?;
+		let data3d = root
+			.children()
+			.find(|n| n.has_tag_name("data3D"))
+			.ok_or(Error::Invalid("Cannot find 'data3D' tag in XML document".into()))?;
+
+		// The tag names are used as written, including any namespace prefix
+		let qualified_name = |range: std::ops::Range<usize>| {
+			let tag = &self.xml[range.start + 1..range.end];
+			let end = tag
+				.find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+				.unwrap_or(tag.len());
+			&tag[..end]
+		};
+		let range = data3d.range();
+		let element = &self.xml[range.clone()];
+		let children = self.writer.data3d_xml();
+		let data3d_xml = if let Some(start) = element.strip_suffix("/>") {
+			format!("{}>{children}</{}>", start.trim_end(), qualified_name(range.clone()))
+		} else {
+			let end = element.rfind("</").unwrap_or(element.len());
+			format!("{}{children}{}", &element[..end], &element[end..])
+		};
+
+		let root_name_end = root.range().start + 1 + qualified_name(root.range()).len();
+		let mut declarations = String::new();
+		for (prefix, uri) in self.writer.namespaces() {
+			if root.lookup_namespace_uri(Some(prefix)).is_none() {
+				declarations += &format!(" xmlns:{prefix}=\"{uri}\"");
+			}
+		}
+
+		let mut xml = String::with_capacity(self.xml.len() + declarations.len() + children.len());
+		xml += &self.xml[..root_name_end];
+		xml += &declarations;
+		xml += &self.xml[root_name_end..range.start];
+		xml += &data3d_xml;
+		xml += &self.xml[range.end..];
+		Ok(xml)
+	}
+}
@@ -0,0 +1,66 @@
+use crate::Error;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// File systems where memory mapping is usually slower than reading the file at once.
+const NETWORK_FILE_SYSTEMS: [&str; 8] = ["nfs", "nfs4", "cifs", "smb3", "smbfs", "afs", "9p", "fuse.sshfs"];
+
+/// Strategy for accessing the contents of an E57 file, see [`crate::Reader::open_with_backing`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backing {
+	/// Memory maps the file and lets the operating system load pages on demand.
+	Mmap,
+	/// Reads the whole file into memory when opening it.
+	FullyBuffered,
+	/// Uses a memory map for local files and reads files on network file systems into memory.
+	#[default]
+	Auto,
+}
+
+impl Backing {
+	/// Opens the file with the selected strategy.
+	pub(crate) fn open(self, path: &Path) -> Result<Mmap, Error> {
+		let mut file = File::open(path)?;
+		let buffered = match self {
+			Backing::Mmap => false,
+			Backing::FullyBuffered => true,
+			Backing::Auto => is_network_path(path),
+		};
+		if !buffered {
+			return Ok(unsafe { memmap2::MmapOptions::new().map(&file)? });
+		}
+		let length = file.metadata()?.len() as usize;
+		if length == 0 {
+			return Error::Invalid("Cannot read E57 file without any data".into()).throw();
+		}
+		let mut mmap = memmap2::MmapMut::map_anon(length)?;
+		file.read_exact(&mut mmap)?;
+		Ok(mmap.make_read_only()?)
+	}
+}
+
+/// Checks the mount table for the file system type of the path.
+///
+/// Always returns false on platforms without a `/proc/self/mounts` file.
+fn is_network_path(path: &Path) -> bool {
+	let Ok(path) = path.canonicalize() else {
+		return false;
+	};
+	let Ok(mounts) = std::fs::read_to_string("/proc/self/mounts") else {
+		return false;
+	};
+	// The mount point with the longest matching prefix contains the file
+	mounts
+		.lines()
+		.filter_map(|line| {
+			let mut columns = line.split_whitespace();
+			let mount_point = columns.nth(1)?;
+			let fs_type = columns.next()?;
+			Some((mount_point, fs_type))
+		})
+		.filter(|(mount_point, _)| path.starts_with(mount_point))
+		.max_by_key(|(mount_point, _)| mount_point.len())
+		.is_some_and(|(_, fs_type)| NETWORK_FILE_SYSTEMS.contains(&fs_type))
+}
@@ -0,0 +1,103 @@
+use crate::error::INTERNAL_ERROR;
+use crate::mmap_paged;
+use crate::mmap_paged::{to_logical, to_physical};
+use crate::xml::location;
+use crate::Error;
+use roxmltree::Node;
+use std::io::Write;
+
+const BLOB_SECTION_HEADER_SIZE: usize = 16;
+const COPY_BUFFER_SIZE: usize = 1020 * 64;
+
+/// Binary data stored in its own section of the file, for example the encoded pixels of an image.
+///
+/// The bytes are extracted with [`crate::Reader::blob`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Blob {
+	/// Physical file offset of the blob section.
+	pub offset: u64,
+	/// Number of bytes in the blob.
+	pub length: u64,
+}
+
+impl Blob {
+	pub(crate) fn from_node(node: &Node) -> Result<Self, Error> {
+		let attribute = |name: &str| {
+			node.attribute(name)
+				.ok_or_else(|| {
+					Error::Invalid(format!(
+						"Cannot find '{name}' attribute in '{}' tag at {}",
+						node.tag_name().name(),
+						location(node)
+					))
+				})?
+				.parse::<u64>()
+				.map_err(Error::from)
+		};
+		Ok(Self {
+			offset: attribute("fileOffset")?,
+			length: attribute("length")?,
+		})
+	}
+
+	/// Checks the blob section header and copies the bytes of the blob into the writer.
+	pub(crate) fn copy(&self, mmap: &memmap2::Mmap, out: &mut dyn Write) -> Result<u64, Error> {
+		let data_start = self.data_start(mmap)?;
+		let mut buffer = vec![0_u8; COPY_BUFFER_SIZE.min(self.length as usize)];
+		let mut copied = 0;
+		while copied < self.length as usize {
+			let size = (self.length as usize - copied).min(buffer.len());
+			mmap_paged::read(&mut buffer[..size], to_physical(data_start + copied), mmap);
+			out.write_all(&buffer[..size])?;
+			copied += size;
+		}
+		Ok(copied as u64)
+	}
+
+	/// Checks the blob section header and returns the physical range from the section header to the end of the blob.
+	pub(crate) fn physical_range(&self, mmap: &memmap2::Mmap) -> Result<(usize, usize), Error> {
+		let data_start = self.data_start(mmap)?;
+		Ok((self.offset as usize, to_physical(data_start + self.length as usize)))
+	}
+
+	/// Checks the blob section header and returns the logical offset of the first byte of the blob.
+	fn data_start(&self, mmap: &memmap2::Mmap) -> Result<usize, Error> {
+		let start = to_logical(self.offset as usize);
+		if !mmap_paged::is_data_offset(self.offset as usize)
+			|| to_physical(start + BLOB_SECTION_HEADER_SIZE) > mmap.len()
+		{
+			return Error::Invalid(format!(
+				"Blob section at offset {} is located outside of the file",
+				self.offset
+			))
+			.throw();
+		}
+		let mut header = [0_u8; BLOB_SECTION_HEADER_SIZE];
+		mmap_paged::read(&mut header, self.offset as usize, mmap);
+		if header[0] != 0 {
+			return Error::Invalid(format!(
+				"Section ID of the blob section header is {} instead of 0",
+				header[0]
+			))
+			.throw();
+		}
+		let section_length = u64::from_le_bytes(header[8..16].try_into().expect(INTERNAL_ERROR));
+		if section_length < BLOB_SECTION_HEADER_SIZE as u64 + self.length {
+			return Error::Invalid(format!(
+				"Blob section with length {section_length} cannot contain a blob with {} bytes",
+				self.length
+			))
+			.throw();
+		}
+		let data_start = start + BLOB_SECTION_HEADER_SIZE;
+		if to_physical(data_start + self.length as usize) > mmap.len() {
+			return Error::Invalid(format!(
+				"Blob section at offset {} extends past the end of the file, the file is truncated",
+				self.offset
+			))
+			.throw();
+		}
+		Ok(data_start)
+	}
+}
@@ -1,10 +1,15 @@
-use crate::xml::optional_double;
 use crate::xml::optional_integer;
+use crate::xml::optional_real;
 use crate::Error;
 use roxmltree::Node;
 
 /// Optional minimum and maximum values for Cartesian X, Y and Z coordinates.
-#[derive(Clone, Debug, Default)]
+///
+/// Some files contain only some of the limits.
+/// Clamping and the bounds check of the point cloud reader use all available limits and ignore the missing ones,
+/// [`CartesianBounds::is_complete`] checks if all six limits are available.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CartesianBounds {
 	pub x_min: Option<f64>,
 	pub x_max: Option<f64>,
@@ -16,18 +21,49 @@ pub struct CartesianBounds {
 
 impl CartesianBounds {
 	pub(crate) fn from_node(node: &Node) -> Result<Self, Error> {
-		let x_min = optional_double(node, "xMinimum")?;
-		let x_max = optional_double(node, "xMaximum")?;
-		let y_min = optional_double(node, "yMinimum")?;
-		let y_max = optional_double(node, "yMaximum")?;
-		let z_min = optional_double(node, "zMinimum")?;
-		let z_max = optional_double(node, "zMaximum")?;
+		let x_min = optional_real(node, "xMinimum")?;
+		let x_max = optional_real(node, "xMaximum")?;
+		let y_min = optional_real(node, "yMinimum")?;
+		let y_max = optional_real(node, "yMaximum")?;
+		let z_min = optional_real(node, "zMinimum")?;
+		let z_max = optional_real(node, "zMaximum")?;
 		Ok(Self { x_min, x_max, y_min, y_max, z_min, z_max })
 	}
+
+	/// Returns true if the minimum and maximum of all three axes are available.
+	pub fn is_complete(&self) -> bool {
+		[self.x_min, self.x_max, self.y_min, self.y_max, self.z_min, self.z_max]
+			.iter()
+			.all(Option::is_some)
+	}
+
+	/// Moves the position into the bounds, missing limits are ignored.
+	pub fn clamp(&self, position: [f64; 3]) -> [f64; 3] {
+		let clamp = |value: f64, min: Option<f64>, max: Option<f64>| {
+			let value = min.map_or(value, |min| value.max(min));
+			max.map_or(value, |max| value.min(max))
+		};
+		[
+			clamp(position[0], self.x_min, self.x_max),
+			clamp(position[1], self.y_min, self.y_max),
+			clamp(position[2], self.z_min, self.z_max),
+		]
+	}
+
+	/// Returns true if the position is inside the bounds, missing limits are ignored.
+	pub fn contains(&self, position: [f64; 3]) -> bool {
+		let inside = |value: f64, min: Option<f64>, max: Option<f64>| {
+			min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+		};
+		inside(position[0], self.x_min, self.x_max)
+			&& inside(position[1], self.y_min, self.y_max)
+			&& inside(position[2], self.z_min, self.z_max)
+	}
 }
 
 /// Optional minimum and maximum values for spherical coordinates.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SphericalBounds {
 	pub range_min:     Option<f64>,
 	pub range_max:     Option<f64>,
@@ -39,12 +75,12 @@ pub struct SphericalBounds {
 
 impl SphericalBounds {
 	pub(crate) fn from_node(node: &Node) -> Result<Self, Error> {
-		let range_min = optional_double(node, "rangeMinimum")?;
-		let range_max = optional_double(node, "rangeMaximum")?;
-		let elevation_min = optional_double(node, "elevationMinimum")?;
-		let elevation_max = optional_double(node, "elevationMaximum")?;
-		let azimuth_start = optional_double(node, "azimuthStart")?;
-		let azimuth_end = optional_double(node, "azimuthEnd")?;
+		let range_min = optional_real(node, "rangeMinimum")?;
+		let range_max = optional_real(node, "rangeMaximum")?;
+		let elevation_min = optional_real(node, "elevationMinimum")?;
+		let elevation_max = optional_real(node, "elevationMaximum")?;
+		let azimuth_start = optional_real(node, "azimuthStart")?;
+		let azimuth_end = optional_real(node, "azimuthEnd")?;
 		Ok(Self {
 			range_min,
 			range_max,
@@ -54,10 +90,24 @@ impl SphericalBounds {
 			azimuth_end,
 		})
 	}
+
+	/// Returns the angular span in radians between the azimuth start and end.
+	///
+	/// Scans that wrap around the -PI/PI boundary have an end smaller than the start,
+	/// their span is measured across the boundary.
+	pub fn azimuth_span(&self) -> Option<f64> {
+		let span = self.azimuth_end? - self.azimuth_start?;
+		if span < 0.0 {
+			Some(span + std::f64::consts::TAU)
+		} else {
+			Some(span)
+		}
+	}
 }
 
 /// Optional minimum and maximum values for the row, column and return indices.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IndexBounds {
 	pub row_min:    Option<i64>,
 	pub row_max:    Option<i64>,
@@ -85,3 +135,16 @@ impl IndexBounds {
 		})
 	}
 }
+
+/// Statistics about the Cartesian coordinates of all valid points of a point cloud.
+#[derive(Clone, Debug)]
+pub struct GeometryStats {
+	/// Number of valid points.
+	pub points:   u64,
+	/// Mean of all valid points.
+	pub centroid: [f64; 3],
+	/// Minimum X, Y and Z coordinates of the axis-aligned bounding box.
+	pub min:      [f64; 3],
+	/// Maximum X, Y and Z coordinates of the axis-aligned bounding box.
+	pub max:      [f64; 3],
+}
@@ -0,0 +1,30 @@
+const POLYNOMIAL: u32 = 0x82F6_3B78;
+
+const TABLE: [u32; 256] = {
+	let mut table = [0_u32; 256];
+	let mut i = 0;
+	while i < 256 {
+		let mut crc = i as u32;
+		let mut bit = 0;
+		while bit < 8 {
+			crc = if crc & 1 != 0 {
+				(crc >> 1) ^ POLYNOMIAL
+			} else {
+				crc >> 1
+			};
+			bit += 1;
+		}
+		table[i] = crc;
+		i += 1;
+	}
+	table
+};
+
+/// Calculates the CRC-32C (Castagnoli) checksum used for the pages of E57 files.
+pub fn crc32c(data: &[u8]) -> u32 {
+	let mut crc = !0_u32;
+	for byte in data {
+		crc = TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+	}
+	!crc
+}
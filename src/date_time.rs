@@ -0,0 +1,75 @@
+use crate::xml::{optional_integer, required_double};
+use crate::Error;
+use roxmltree::Node;
+
+/// Seconds between the Unix epoch and the GPS epoch at 1980-01-06 00:00:00 UTC.
+const GPS_EPOCH_UNIX_SECONDS: f64 = 315_964_800.0;
+
+/// Unix timestamps of all UTC leap seconds inserted after the GPS epoch.
+const LEAP_SECONDS_UNIX: [f64; 18] = [
+	362_793_600.0,   // 1981-07-01
+	394_329_600.0,   // 1982-07-01
+	425_865_600.0,   // 1983-07-01
+	489_024_000.0,   // 1985-07-01
+	567_993_600.0,   // 1988-01-01
+	631_152_000.0,   // 1990-01-01
+	662_688_000.0,   // 1991-01-01
+	709_948_800.0,   // 1992-07-01
+	741_484_800.0,   // 1993-07-01
+	773_020_800.0,   // 1994-07-01
+	820_454_400.0,   // 1996-01-01
+	867_715_200.0,   // 1997-07-01
+	915_148_800.0,   // 1999-01-01
+	1_136_073_600.0, // 2006-01-01
+	1_230_768_000.0, // 2009-01-01
+	1_341_100_800.0, // 2012-07-01
+	1_435_708_800.0, // 2015-07-01
+	1_483_228_800.0, // 2017-01-01
+];
+
+/// Date and time of an event, for example the creation of the file or the start of an acquisition.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DateTime {
+	/// Seconds since the GPS epoch at 1980-01-06 00:00:00 UTC, GPS time contains no leap seconds.
+	pub gps_time:         f64,
+	/// The time was obtained from an atomic clock time source like a GPS receiver.
+	pub atomic_reference: bool,
+}
+
+impl DateTime {
+	pub(crate) fn from_node(node: &Node) -> Result<Self, Error> {
+		let gps_time = required_double(node, "dateTimeValue")?;
+		let atomic_reference = optional_integer::<i64>(node, "isAtomicClockReferenced")?.unwrap_or(0) != 0;
+		Ok(Self { gps_time, atomic_reference })
+	}
+
+	/// Returns the seconds since the Unix epoch at 1970-01-01 00:00:00 UTC.
+	///
+	/// The leap seconds inserted into UTC since the GPS epoch are subtracted,
+	/// leap seconds announced after 2017 are not known.
+	/// Times without an atomic clock reference are converted the same way,
+	/// but may be less accurate or relative to an arbitrary start.
+	pub fn to_unix_seconds(&self) -> f64 {
+		let leap_seconds = LEAP_SECONDS_UNIX
+			.iter()
+			.enumerate()
+			.filter(|(index, unix)| self.gps_time >= **unix - GPS_EPOCH_UNIX_SECONDS + (*index + 1) as f64)
+			.count();
+		self.gps_time + GPS_EPOCH_UNIX_SECONDS - leap_seconds as f64
+	}
+
+	/// Returns the time as UTC date and time, see [`DateTime::to_unix_seconds`] for the conversion.
+	///
+	/// Returns `None` if the time is not finite or outside of the supported range.
+	#[cfg(feature = "chrono")]
+	pub fn to_chrono(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+		let unix = self.to_unix_seconds();
+		if !unix.is_finite() {
+			return None;
+		}
+		let seconds = unix.floor();
+		let nanos = ((unix - seconds) * 1e9).round().min(999_999_999.0) as u32;
+		chrono::DateTime::from_timestamp(seconds as i64, nanos)
+	}
+}
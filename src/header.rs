@@ -9,7 +9,8 @@ const MINOR_VERSION: u32 = 0;
 const PAGE_SIZE: u64 = 1024;
 
 /// Represents the file structure from the start of an E57 file.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub struct Header {
 	/// File header signature that must be always "ASTM-E57".
@@ -65,4 +66,30 @@ impl Header {
 
 		Ok(header)
 	}
+
+	/// Creates the header for a file written by this library.
+	pub(crate) fn new(phys_length: u64, phys_xml_offset: u64, xml_length: u64) -> Self {
+		Header {
+			signature: *SIGNATURE,
+			major: MAJOR_VERSION,
+			minor: MINOR_VERSION,
+			phys_length,
+			phys_xml_offset,
+			xml_length,
+			page_size: PAGE_SIZE,
+		}
+	}
+
+	/// Serializes the header into its binary representation.
+	pub(crate) fn to_bytes(&self) -> [u8; 48] {
+		let mut data = [0_u8; 48];
+		data[0..8].copy_from_slice(&self.signature);
+		data[8..12].copy_from_slice(&self.major.to_le_bytes());
+		data[12..16].copy_from_slice(&self.minor.to_le_bytes());
+		data[16..24].copy_from_slice(&self.phys_length.to_le_bytes());
+		data[24..32].copy_from_slice(&self.phys_xml_offset.to_le_bytes());
+		data[32..40].copy_from_slice(&self.xml_length.to_le_bytes());
+		data[40..48].copy_from_slice(&self.page_size.to_le_bytes());
+		data
+	}
 }
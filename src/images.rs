@@ -0,0 +1,70 @@
+use crate::xml::{optional_string, optional_transform, required_string};
+use crate::{Blob, Error, Transform};
+use roxmltree::{Document, Node};
+
+/// Descriptor with metadata for a single image.
+///
+/// This struct does not contain any actual pixel data,
+/// it just describes the properties and attributes of an image.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Image {
+	/// Globally unique identifier for the image.
+	pub guid:            String,
+	/// Optional GUID of the point cloud captured together with the image.
+	pub pointcloud_guid: Option<String>,
+	/// Optional user-defined name for the image.
+	pub name:            Option<String>,
+	/// Optional user-defined description of the image.
+	pub description:     Option<String>,
+	/// Optional transformation to convert data from the local image coordinates to the file-level coordinate system.
+	pub transform:       Option<Transform>,
+	/// JPEG encoded image data of the image representation.
+	pub jpeg:            Option<Blob>,
+	/// PNG encoded image data of the image representation.
+	pub png:             Option<Blob>,
+}
+
+pub fn images_from_document(document: &Document) -> Result<Vec<Image>, Error> {
+	// The images are optional, files without any images may not contain the tag at all
+	let Some(images2d_node) = document.descendants().find(|n| n.has_tag_name("images2D")) else {
+		return Ok(Vec::new());
+	};
+
+	let mut images = Vec::new();
+	for n in images2d_node.children() {
+		if n.has_tag_name("vectorChild") && n.attribute("type") == Some("Structure") {
+			let image = extract_image(&n)?;
+			images.push(image);
+		}
+	}
+	Ok(images)
+}
+
+const REPRESENTATIONS: [&str; 4] = [
+	"visualReferenceRepresentation",
+	"pinholeRepresentation",
+	"sphericalRepresentation",
+	"cylindricalRepresentation",
+];
+
+fn extract_image(node: &Node) -> Result<Image, Error> {
+	let representation = node
+		.children()
+		.find(|n| REPRESENTATIONS.iter().any(|name| n.has_tag_name(*name)));
+	let blob = |tag_name: &str| {
+		representation
+			.and_then(|r| r.children().find(|n| n.has_tag_name(tag_name)))
+			.map(|n| Blob::from_node(&n))
+			.transpose()
+	};
+	Ok(Image {
+		guid:            required_string(node, "guid")?,
+		pointcloud_guid: optional_string(node, "associatedData3DGuid")?,
+		name:            optional_string(node, "name")?,
+		description:     optional_string(node, "description")?,
+		transform:       optional_transform(node, "pose")?,
+		jpeg:            blob("jpegImage")?,
+		png:             blob("pngImage")?,
+	})
+}
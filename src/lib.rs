@@ -7,30 +7,72 @@
 )]
 #![feature(thread_local)]
 
+mod appender;
+mod backing;
+mod blob;
 mod bounds;
+mod crc32;
+mod date_time;
 mod error;
 mod header;
+mod images;
+mod limits;
+mod line_groups;
+mod metadata;
 mod mmap_paged;
+mod multi_reader;
+mod paged_reader;
+mod paged_writer;
 mod pc_reader;
+mod pc_writer;
 mod pointcloud;
 mod reader;
 mod record;
 mod root;
+mod stream_reader;
+mod strict;
+mod summary;
 mod transform;
+mod writer;
 mod xml;
 
+pub use self::appender::E57Appender;
+pub use self::backing::Backing;
+pub use self::blob::Blob;
 pub use self::bounds::CartesianBounds;
+pub use self::bounds::GeometryStats;
 pub use self::bounds::IndexBounds;
 pub use self::bounds::SphericalBounds;
+pub use self::date_time::DateTime;
 pub use self::error::Error;
 pub use self::header::Header;
+pub use self::images::Image;
+pub use self::limits::IntensityLimits;
+pub use self::line_groups::LineGroup;
+pub use self::line_groups::LineGrouping;
+pub use self::metadata::Metadata;
+pub use self::multi_reader::MultiPositions;
+pub use self::multi_reader::MultiReader;
 pub use self::pc_reader::*;
 pub use self::pointcloud::PointCloud;
+pub use self::pointcloud::PointCloudBuilder;
 pub use self::reader::Reader;
+pub use self::record::prototype_diff;
+pub use self::record::prototypes_compatible;
+pub use self::record::PrototypeDiff;
+pub use self::record::RawValues;
 pub use self::record::Record;
 pub use self::record::RecordDataType;
 pub use self::record::RecordName;
 pub use self::record::RecordValue;
+pub use self::stream_reader::StreamRawValues;
+pub use self::stream_reader::StreamReader;
+pub use self::summary::FileSummary;
+pub use self::transform::spherical_to_cartesian;
 pub use self::transform::Quaternion;
 pub use self::transform::Transform;
 pub use self::transform::Translation;
+pub use self::writer::verify_roundtrip;
+pub use self::writer::write_xyz;
+pub use self::writer::E57Writer;
+pub use memmap2::Mmap;
@@ -0,0 +1,27 @@
+use crate::xml::optional_real;
+use crate::Error;
+use roxmltree::Node;
+
+/// Optional minimum and maximum values for intensities.
+///
+/// The limits can be stored with a different data type than the intensity records,
+/// they are always converted to `f64`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntensityLimits {
+	pub intensity_min: Option<f64>,
+	pub intensity_max: Option<f64>,
+}
+
+impl IntensityLimits {
+	pub(crate) fn from_node(node: &Node) -> Result<Self, Error> {
+		let intensity_min = optional_real(node, "intensityMinimum")?;
+		let intensity_max = optional_real(node, "intensityMaximum")?;
+		Ok(Self { intensity_min, intensity_max })
+	}
+
+	/// Returns the minimum and maximum if both are defined.
+	pub fn range(&self) -> Option<(f64, f64)> {
+		Some((self.intensity_min?, self.intensity_max?))
+	}
+}
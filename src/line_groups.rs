@@ -0,0 +1,123 @@
+use crate::pc_reader::IntLoader;
+use crate::pc_reader::PropertyLoader;
+use crate::pc_reader::SectionHeader;
+use crate::xml::{location, required_string};
+use crate::{Error, RecordDataType};
+use roxmltree::Node;
+
+/// Describes how the points of a point cloud are grouped into scan lines.
+///
+/// The groups are stored in a separate compressed vector section and are read with [`crate::PointCloud::line_groups`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineGrouping {
+	/// Name of the point record that identifies the lines, usually `rowIndex` or `columnIndex`.
+	pub id_element_name: String,
+	/// Physical file offset of the start of the binary section with the groups.
+	pub file_offset:     u64,
+	/// Number of groups.
+	pub groups:          u64,
+	id:                  (usize, RecordDataType),
+	start:               (usize, RecordDataType),
+	count:               (usize, RecordDataType),
+}
+
+/// A single scan line of a point cloud.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineGroup {
+	/// Value of the identifying record for all points of the line.
+	pub id:    i64,
+	/// Index of the first point of the line.
+	pub start: u64,
+	/// Number of points in the line.
+	pub count: u64,
+}
+
+impl LineGrouping {
+	pub(crate) fn from_node(node: &Node) -> Result<Self, Error> {
+		let id_element_name = required_string(node, "idElementName")?;
+		let groups_tag = node
+			.children()
+			.find(|n| n.has_tag_name("groups") && n.attribute("type") == Some("CompressedVector"))
+			.ok_or_else(|| {
+				Error::Invalid(format!(
+					"Cannot find 'groups' tag with type 'CompressedVector' inside 'groupingByLine' at {}",
+					location(node)
+				))
+			})?;
+		let attribute = |name: &str| {
+			groups_tag
+				.attribute(name)
+				.ok_or_else(|| {
+					Error::Invalid(format!(
+						"Cannot find '{name}' attribute in 'groups' tag at {}",
+						location(&groups_tag)
+					))
+				})
+				.and_then(|value| Ok(value.parse::<u64>()?))
+		};
+		let file_offset = attribute("fileOffset")?;
+		let groups = attribute("recordCount")?;
+		let prototype_tag = groups_tag
+			.children()
+			.find(|n| n.has_tag_name("prototype") && n.attribute("type") == Some("Structure"))
+			.ok_or_else(|| {
+				Error::Invalid(format!(
+					"Cannot find 'prototype' child in 'groups' tag at {}",
+					location(&groups_tag)
+				))
+			})?;
+		let records = prototype_tag.children().filter(|n| n.is_element()).collect::<Vec<_>>();
+		let record = |name: &str| {
+			let (index, node) = records
+				.iter()
+				.enumerate()
+				.find(|(_, n)| n.has_tag_name(name))
+				.ok_or_else(|| {
+					Error::Invalid(format!(
+						"Cannot find '{name}' record in 'groups' prototype at {}",
+						location(&prototype_tag)
+					))
+				})?;
+			Ok::<_, Error>((index, RecordDataType::from_node(node)?))
+		};
+
+		Ok(Self {
+			id_element_name,
+			file_offset,
+			groups,
+			id: record("idElementValue")?,
+			start: record("startPointIndex")?,
+			count: record("pointCount")?,
+		})
+	}
+
+	pub(crate) fn read(&self, mmap: &memmap2::Mmap) -> Result<Vec<LineGroup>, Error> {
+		if self.groups == 0 {
+			return Ok(Vec::new());
+		}
+		let section = SectionHeader::read(self.file_offset, mmap)?;
+		let offset = section.logical_data_offset();
+		let loader = |(index, data_type): (usize, RecordDataType)| match data_type {
+			RecordDataType::Integer { min, max } | RecordDataType::ScaledInteger { min, max, .. } => {
+				IntLoader::new(offset, index, min, max, mmap)
+			},
+			_ => Error::Invalid(format!("Line group records must be integers, found {data_type:?}")).throw(),
+		};
+		let mut id = loader(self.id)?;
+		let mut start = loader(self.start)?;
+		let mut count = loader(self.count)?;
+
+		let mut groups = Vec::with_capacity(self.groups as usize);
+		for i in 0..self.groups {
+			let at_end = i == self.groups - 1;
+			groups.push(LineGroup {
+				id:    id.load(mmap, at_end)?,
+				start: start.load(mmap, at_end)? as u64,
+				count: count.load(mmap, at_end)? as u64,
+			});
+		}
+		Ok(groups)
+	}
+}
@@ -0,0 +1,103 @@
+use crate::images::images_from_document;
+use crate::mmap_paged;
+use crate::pointcloud::pointclouds_from_document;
+use crate::reader::{parse_document, xml_range, xml_to_string};
+use crate::root::epsg_from_wkt;
+use crate::root::root_from_document;
+use crate::root::Root;
+use crate::Error;
+use crate::Header;
+use crate::Image;
+use crate::PointCloud;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Metadata of an E57 file without access to the point data, created by [`crate::Reader::open_metadata_only`].
+///
+/// Only the header and the XML section are read from the file.
+/// Point clouds without a record count in the XML section report zero records,
+/// because inferring the count requires the binary sections.
+pub struct Metadata {
+	header:      Header,
+	root:        Root,
+	pointclouds: Vec<PointCloud>,
+	images:      Vec<Image>,
+}
+
+impl Metadata {
+	pub(crate) fn read(path: &Path) -> Result<Self, Error> {
+		let mut file = File::open(path)?;
+		let file_length = file.metadata()?.len() as usize;
+		let header = Header::read(&mut file)?;
+		let (xml_offset, xml_end) = xml_range(&header, file_length)?;
+
+		let mut xml_physical = vec![0_u8; xml_end - xml_offset];
+		file.seek(SeekFrom::Start(xml_offset as u64))?;
+		file.read_exact(&mut xml_physical)?;
+		let xml_raw = xml_physical
+			.iter()
+			.enumerate()
+			.filter(|(i, _)| mmap_paged::is_data_offset(xml_offset + i))
+			.map(|(_, b)| *b)
+			.collect();
+
+		let xml = xml_to_string(xml_raw)?;
+		let document = parse_document(&xml)?;
+		Ok(Self {
+			header,
+			root: root_from_document(&document)?,
+			pointclouds: pointclouds_from_document(&document)?,
+			images: images_from_document(&document)?,
+		})
+	}
+
+	/// Returns the contents of E57 binary file header structure.
+	pub fn header(&self) -> Header {
+		self.header.clone()
+	}
+
+	/// Returns format name stored in the XML section.
+	pub fn format_name(&self) -> &str {
+		&self.root.format
+	}
+
+	/// Returns GUID stored in the XML section.
+	pub fn guid(&self) -> &str {
+		&self.root.guid
+	}
+
+	/// Returns a list of all point clouds in the file.
+	pub fn pointclouds(&self) -> Vec<PointCloud> {
+		self.pointclouds.clone()
+	}
+
+	/// Returns a list of all images in the file.
+	pub fn images(&self) -> Vec<Image> {
+		self.images.clone()
+	}
+
+	/// Returns the version of the E57 format stored in the XML section as major and minor number.
+	///
+	/// Same as [`crate::Reader::version`], which is the accessor for files opened with point data.
+	pub fn version(&self) -> (i64, i64) {
+		(self.root.major_version, self.root.minor_version)
+	}
+
+	/// Returns the optional version string of the library that was used to write the file.
+	///
+	/// Same as [`crate::Reader::library_version`], which is the accessor for files opened with point data.
+	pub fn library_version(&self) -> Option<&str> {
+		self.root.library_version.as_deref()
+	}
+
+	/// Returns the optional coordinate system metadata.
+	pub fn coordinate_metadata(&self) -> Option<&str> {
+		self.root.coordinate_metadata.as_deref()
+	}
+
+	/// Returns the EPSG code of the coordinate reference system, if the coordinate metadata contains one.
+	pub fn crs_epsg(&self) -> Option<u32> {
+		self.coordinate_metadata().and_then(epsg_from_wkt)
+	}
+}
@@ -1,3 +1,7 @@
+/// Reads logical data starting at a physical offset and skips the CRC bytes at the end of each page.
+///
+/// The offset can point anywhere into the data area of a page,
+/// pages do not need to start at a specific position relative to the header.
 pub fn read(mut view: &mut [u8], mut offset: usize, mmap: &memmap2::Mmap) {
 	while !view.is_empty() {
 		let avaible = std::cmp::min(view.len(), 1020 - offset % 1024);
@@ -6,3 +10,18 @@ pub fn read(mut view: &mut [u8], mut offset: usize, mmap: &memmap2::Mmap) {
 		offset += avaible + 4;
 	}
 }
+
+/// Converts a logical offset (without CRC bytes) to a physical offset.
+pub fn to_physical(logical: usize) -> usize {
+	logical + (logical / 1020) * 4
+}
+
+/// Converts a physical offset to a logical offset (without CRC bytes).
+pub fn to_logical(physical: usize) -> usize {
+	physical - (physical / 1024) * 4
+}
+
+/// Returns true if the physical offset points to the data area of a page and not to its CRC checksum.
+pub fn is_data_offset(physical: usize) -> bool {
+	physical % 1024 < 1020
+}
@@ -0,0 +1,116 @@
+use crate::reader::pose;
+use crate::{Error, PointCloud, Reader, RecordName};
+use std::path::PathBuf;
+
+/// Reads multiple E57 files as a single virtual point cloud.
+///
+/// Datasets are often split into one file per scan station.
+/// The files are only opened when they are needed, at most one file is open at a time.
+/// Different prototypes are handled by only using the records that exist in all point clouds,
+/// see [`MultiReader::common_records`].
+#[derive(Clone, Debug)]
+pub struct MultiReader {
+	paths: Vec<PathBuf>,
+}
+
+impl MultiReader {
+	/// Creates a reader for the given list of files.
+	pub fn new(paths: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+		Self { paths: paths.into_iter().map(Into::into).collect() }
+	}
+
+	/// Returns the paths of all files.
+	pub fn paths(&self) -> &[PathBuf] {
+		&self.paths
+	}
+
+	/// Returns the names of the records that exist in all point clouds of all files.
+	///
+	/// This opens every file to read its XML section.
+	pub fn common_records(&self) -> Result<Vec<RecordName>, Error> {
+		let mut common: Option<Vec<RecordName>> = None;
+		for path in &self.paths {
+			let reader = Reader::from_file(path)?;
+			for pc in reader.pointclouds() {
+				let names = pc.prototype.iter().map(|r| r.name);
+				common = Some(match common {
+					None => names.collect(),
+					Some(common) => common
+						.into_iter()
+						.filter(|n| pc.prototype.iter().any(|r| r.name == *n))
+						.collect(),
+				});
+			}
+		}
+		Ok(common.unwrap_or_default())
+	}
+
+	/// Iterate over the Cartesian coordinates of all valid points of all point clouds in all files.
+	///
+	/// The pose of each point cloud is applied, so all points are returned in the common file-level coordinate system.
+	/// Points of one point cloud are decoded together, so the memory usage depends on the largest point cloud.
+	/// Returns an error for point clouds without Cartesian coordinates.
+	pub fn positions(&self) -> MultiPositions<'_> {
+		MultiPositions {
+			paths:       &self.paths,
+			reader:      None,
+			pointclouds: Vec::new(),
+			positions:   Vec::new().into_iter(),
+		}
+	}
+}
+
+/// Iterate over the positions of multiple files, created by [`MultiReader::positions`].
+pub struct MultiPositions<'a> {
+	paths:       &'a [PathBuf],
+	reader:      Option<Reader>,
+	pointclouds: Vec<PointCloud>,
+	positions:   std::vec::IntoIter<[f64; 3]>,
+}
+
+impl MultiPositions<'_> {
+	fn load_next(&mut self) -> Result<bool, Error> {
+		loop {
+			if let (Some(reader), Some(pc)) = (&mut self.reader, self.pointclouds.pop()) {
+				let mut positions = reader.positions(&pc)?;
+				if let Some(transform) = pose(&pc) {
+					for position in positions.iter_mut() {
+						*position = transform.apply(*position);
+					}
+				}
+				self.positions = positions.into_iter();
+				return Ok(true);
+			}
+			let Some((path, remaining)) = self.paths.split_first() else {
+				self.reader = None;
+				return Ok(false);
+			};
+			self.paths = remaining;
+			let reader = Reader::from_file(path)?;
+			self.pointclouds = reader.pointclouds();
+			self.pointclouds.reverse();
+			self.reader = Some(reader);
+		}
+	}
+}
+
+impl Iterator for MultiPositions<'_> {
+	type Item = Result<[f64; 3], Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(position) = self.positions.next() {
+				return Some(Ok(position));
+			}
+			match self.load_next() {
+				Ok(true) => {},
+				Ok(false) => return None,
+				Err(err) => {
+					self.paths = &[];
+					self.pointclouds.clear();
+					return Some(Err(err));
+				},
+			}
+		}
+	}
+}
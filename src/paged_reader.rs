@@ -0,0 +1,84 @@
+use crate::crc32::crc32c;
+use crate::Error;
+use std::io::{Read, Seek, SeekFrom};
+
+const PHYSICAL_PAGE_SIZE: usize = 1024;
+const LOGICAL_PAGE_SIZE: usize = PHYSICAL_PAGE_SIZE - 4;
+
+/// Reads logical data from pages with a trailing CRC checksum, without mapping the file into memory.
+///
+/// Only the most recently used page is kept in memory, its checksum is validated when it is loaded.
+pub(crate) struct PagedReader<T: Read + Seek> {
+	reader: T,
+	length: u64,
+	page:   Vec<u8>,
+	index:  Option<u64>,
+}
+
+impl<T: Read + Seek> PagedReader<T> {
+	pub fn new(mut reader: T) -> Result<Self, Error> {
+		let length = reader.seek(SeekFrom::End(0))?;
+		Ok(Self {
+			reader,
+			length,
+			page: vec![0; PHYSICAL_PAGE_SIZE],
+			index: None,
+		})
+	}
+
+	/// Physical length of the underlying file.
+	pub fn physical_length(&self) -> u64 {
+		self.length
+	}
+
+	/// Reads logical data starting at a physical offset and skips the checksums at the end of each page.
+	pub fn read_physical(&mut self, mut physical: u64, mut data: &mut [u8]) -> Result<(), Error> {
+		while !data.is_empty() {
+			let index = physical / PHYSICAL_PAGE_SIZE as u64;
+			let start = (physical % PHYSICAL_PAGE_SIZE as u64) as usize;
+			if start >= LOGICAL_PAGE_SIZE {
+				return Error::Invalid(format!("Offset {physical} points to the checksum of a page")).throw();
+			}
+			self.load(index)?;
+			let available = std::cmp::min(data.len(), LOGICAL_PAGE_SIZE - start);
+			data[..available].copy_from_slice(&self.page[start..(start + available)]);
+			data = &mut data[available..];
+			physical = (index + 1) * PHYSICAL_PAGE_SIZE as u64;
+		}
+		Ok(())
+	}
+
+	/// Reads logical data starting at a logical offset.
+	pub fn read_logical(&mut self, logical: u64, data: &mut [u8]) -> Result<(), Error> {
+		let physical = logical + logical / LOGICAL_PAGE_SIZE as u64 * 4;
+		self.read_physical(physical, data)
+	}
+
+	pub fn into_inner(self) -> T {
+		self.reader
+	}
+
+	fn load(&mut self, index: u64) -> Result<(), Error> {
+		if self.index == Some(index) {
+			return Ok(());
+		}
+		let offset = index * PHYSICAL_PAGE_SIZE as u64;
+		if offset + PHYSICAL_PAGE_SIZE as u64 > self.length {
+			return Error::Invalid(format!(
+				"Page {index} extends past the end of the file with {} bytes, the file is truncated",
+				self.length
+			))
+			.throw();
+		}
+		// Invalidate the cached page first, a failed read leaves it partially overwritten
+		self.index = None;
+		self.reader.seek(SeekFrom::Start(offset))?;
+		self.reader.read_exact(&mut self.page)?;
+		let (data, crc) = self.page.split_at(LOGICAL_PAGE_SIZE);
+		if crc32c(data).to_be_bytes() != crc {
+			return Error::Invalid(format!("Page {index} has an invalid checksum")).throw();
+		}
+		self.index = Some(index);
+		Ok(())
+	}
+}
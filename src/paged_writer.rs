@@ -0,0 +1,130 @@
+use crate::crc32::crc32c;
+use crate::Error;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+const PHYSICAL_PAGE_SIZE: usize = 1024;
+const LOGICAL_PAGE_SIZE: usize = PHYSICAL_PAGE_SIZE - 4;
+
+/// Writes logical data into pages with a trailing CRC checksum.
+///
+/// Data is written sequentially, already written pages can be patched with [`PagedWriter::write_at`].
+pub(crate) struct PagedWriter<T: Read + Write + Seek> {
+	writer: T,
+	page:   Vec<u8>,
+	index:  u64,
+	offset: usize,
+}
+
+impl<T: Read + Write + Seek> PagedWriter<T> {
+	pub fn new(writer: T) -> Self {
+		Self {
+			writer,
+			page: vec![0; LOGICAL_PAGE_SIZE],
+			index: 0,
+			offset: 0,
+		}
+	}
+
+	/// Continues writing after the existing data of a file, which must consist of complete pages.
+	pub fn resume(writer: T, physical_length: u64) -> Result<Self, Error> {
+		if !physical_length.is_multiple_of(PHYSICAL_PAGE_SIZE as u64) {
+			return Error::Invalid(format!(
+				"Length {physical_length} of the existing data is not a multiple of the page size"
+			))
+			.throw();
+		}
+		Ok(Self {
+			writer,
+			page: vec![0; LOGICAL_PAGE_SIZE],
+			index: physical_length / PHYSICAL_PAGE_SIZE as u64,
+			offset: 0,
+		})
+	}
+
+	/// Physical offset of the next byte that is written.
+	pub fn physical_position(&self) -> u64 {
+		self.index * PHYSICAL_PAGE_SIZE as u64 + self.offset as u64
+	}
+
+	/// Logical offset (without CRC bytes) of the next byte that is written.
+	pub fn logical_position(&self) -> u64 {
+		self.index * LOGICAL_PAGE_SIZE as u64 + self.offset as u64
+	}
+
+	/// Physical length of the file after the last page is completed.
+	pub fn physical_length(&self) -> u64 {
+		(self.index + (self.offset > 0) as u64) * PHYSICAL_PAGE_SIZE as u64
+	}
+
+	pub fn write_all(&mut self, mut data: &[u8]) -> Result<(), Error> {
+		while !data.is_empty() {
+			let available = std::cmp::min(data.len(), LOGICAL_PAGE_SIZE - self.offset);
+			self.page[self.offset..(self.offset + available)].copy_from_slice(&data[..available]);
+			self.offset += available;
+			data = &data[available..];
+			if self.offset == LOGICAL_PAGE_SIZE {
+				self.write_page()?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Writes zeros until the logical position is a multiple of the alignment.
+	pub fn align(&mut self, alignment: u64) -> Result<(), Error> {
+		let padding = self.logical_position().next_multiple_of(alignment) - self.logical_position();
+		self.write_all(&vec![0; padding as usize])
+	}
+
+	/// Overwrites already written data starting at a physical offset and updates the checksums of the affected pages.
+	pub fn write_at(&mut self, mut physical: u64, mut data: &[u8]) -> Result<(), Error> {
+		let mut buffer = vec![0_u8; PHYSICAL_PAGE_SIZE];
+		while !data.is_empty() {
+			let index = physical / PHYSICAL_PAGE_SIZE as u64;
+			let start = (physical % PHYSICAL_PAGE_SIZE as u64) as usize;
+			if start >= LOGICAL_PAGE_SIZE {
+				return Error::Invalid(format!("Offset {physical} points to the checksum of a page")).throw();
+			}
+			let available = std::cmp::min(data.len(), LOGICAL_PAGE_SIZE - start);
+			if index == self.index {
+				if start + available > self.offset {
+					return Error::Invalid(format!("Cannot overwrite unwritten data at offset {physical}")).throw();
+				}
+				self.page[start..(start + available)].copy_from_slice(&data[..available]);
+			} else if index < self.index {
+				self.writer.seek(SeekFrom::Start(index * PHYSICAL_PAGE_SIZE as u64))?;
+				self.writer.read_exact(&mut buffer)?;
+				buffer[start..(start + available)].copy_from_slice(&data[..available]);
+				let crc = crc32c(&buffer[..LOGICAL_PAGE_SIZE]);
+				buffer[LOGICAL_PAGE_SIZE..].copy_from_slice(&crc.to_be_bytes());
+				self.writer.seek(SeekFrom::Start(index * PHYSICAL_PAGE_SIZE as u64))?;
+				self.writer.write_all(&buffer)?;
+			} else {
+				return Error::Invalid(format!("Cannot overwrite unwritten data at offset {physical}")).throw();
+			}
+			data = &data[available..];
+			physical = (index + 1) * PHYSICAL_PAGE_SIZE as u64;
+		}
+		Ok(())
+	}
+
+	/// Pads and writes the last page and returns the underlying writer.
+	pub fn finish(mut self) -> Result<T, Error> {
+		if self.offset > 0 {
+			self.page[self.offset..].fill(0);
+			self.write_page()?;
+		}
+		self.writer.flush()?;
+		Ok(self.writer)
+	}
+
+	fn write_page(&mut self) -> Result<(), Error> {
+		let crc = crc32c(&self.page);
+		self.writer
+			.seek(SeekFrom::Start(self.index * PHYSICAL_PAGE_SIZE as u64))?;
+		self.writer.write_all(&self.page)?;
+		self.writer.write_all(&crc.to_be_bytes())?;
+		self.index += 1;
+		self.offset = 0;
+		Ok(())
+	}
+}
@@ -0,0 +1,40 @@
+use crate::CartesianBounds;
+
+/// Maximum number of point indices stored in [`BoundsViolations`].
+pub const MAX_REPORTED_VIOLATIONS: usize = 8;
+
+/// Points that are outside of the declared Cartesian bounds of a point cloud.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BoundsViolations {
+	/// Number of points outside of the bounds.
+	pub count:   u64,
+	/// Indices of the first points outside of the bounds, at most [`MAX_REPORTED_VIOLATIONS`].
+	pub indices: Vec<u64>,
+}
+
+/// Compares decoded points with the declared Cartesian bounds.
+pub(crate) struct BoundsCheck<Point> {
+	bounds:     CartesianBounds,
+	position:   fn(&Point) -> [f64; 3],
+	violations: BoundsViolations,
+}
+
+impl<Point> BoundsCheck<Point> {
+	pub fn new(bounds: CartesianBounds, position: fn(&Point) -> [f64; 3]) -> Self {
+		Self { bounds, position, violations: BoundsViolations::default() }
+	}
+
+	pub fn check(&mut self, index: u64, point: &Point) {
+		if self.bounds.contains((self.position)(point)) {
+			return;
+		}
+		self.violations.count += 1;
+		if self.violations.indices.len() < MAX_REPORTED_VIOLATIONS {
+			self.violations.indices.push(index);
+		}
+	}
+
+	pub fn violations(&self) -> &BoundsViolations {
+		&self.violations
+	}
+}
@@ -0,0 +1,178 @@
+use super::f64_reader;
+use super::i64_reader;
+use super::unit_f32_reader;
+use super::IntLoader;
+use super::PropertyLoader;
+use super::PropertyReader;
+use super::PropertySaver;
+use crate::error::INTERNAL_ERROR;
+use crate::Error;
+use crate::PointCloud;
+use crate::RecordDataType;
+use crate::RecordName;
+
+const XYZ: [RecordName; 3] = [RecordName::CartesianX, RecordName::CartesianY, RecordName::CartesianZ];
+const RGB: [RecordName; 3] = [RecordName::ColorRed, RecordName::ColorGreen, RecordName::ColorBlue];
+
+/// Cartesian coordinates with an 8-bit RGB color.
+pub type ColoredPosition = ([f64; 3], [u8; 3]);
+
+/// Cartesian coordinates with 8-bit color and the invalid state of a single point.
+#[derive(Default)]
+pub(crate) struct ColoredPoint {
+	pub position: [f64; 3],
+	pub color:    [u8; 3],
+	pub invalid:  bool,
+}
+
+/// Property reader specialized for scaled integer coordinates with 8-bit colors.
+///
+/// Decodes all six records of a point with a single call,
+/// which avoids the dynamic dispatch of the generic property readers for the most common layout.
+pub(crate) struct ColoredFastPath {
+	xyz: [(IntLoader, f64); 3],
+	rgb: [IntLoader; 3],
+}
+
+impl ColoredFastPath {
+	/// Returns `None` if the prototype does not consist of exactly the supported records.
+	pub fn reader(
+		pc: &PointCloud,
+		offset: usize,
+		mmap: &memmap2::Mmap,
+	) -> Result<Option<Box<dyn PropertyReader<ColoredPoint>>>, Error> {
+		if pc.prototype.len() != 6 {
+			return Ok(None);
+		}
+		let find = |name: RecordName| pc.prototype.iter().position(|r| r.name == name);
+
+		let mut xyz = Vec::with_capacity(3);
+		for name in XYZ {
+			let Some(index) = find(name) else {
+				return Ok(None);
+			};
+			let RecordDataType::ScaledInteger { min, max, scale } = pc.prototype[index].data_type else {
+				return Ok(None);
+			};
+			xyz.push((index, min, max, scale));
+		}
+		let mut rgb = Vec::with_capacity(3);
+		for name in RGB {
+			let Some(index) = find(name) else {
+				return Ok(None);
+			};
+			match pc.prototype[index].data_type {
+				RecordDataType::Integer { min: 0, max: 255 } => rgb.push(index),
+				_ => return Ok(None),
+			}
+		}
+
+		let xyz = xyz
+			.into_iter()
+			.map(|(index, min, max, scale)| Ok((IntLoader::new(offset, index, min, max, mmap)?, scale)))
+			.collect::<Result<Vec<_>, Error>>()?;
+		let rgb = rgb
+			.into_iter()
+			.map(|index| IntLoader::new(offset, index, 0, 255, mmap))
+			.collect::<Result<Vec<_>, Error>>()?;
+		Ok(Some(Box::new(Self {
+			xyz: xyz.try_into().ok().expect(INTERNAL_ERROR),
+			rgb: rgb.try_into().ok().expect(INTERNAL_ERROR),
+		})))
+	}
+}
+
+impl PropertyReader<ColoredPoint> for ColoredFastPath {
+	fn read(&mut self, mmap: &memmap2::Mmap, point: &mut ColoredPoint, at_end: bool) -> Result<(), Error> {
+		for ((loader, scale), value) in self.xyz.iter_mut().zip(point.position.iter_mut()) {
+			*value = loader.load(mmap, at_end)? as f64 * *scale;
+		}
+		for (loader, value) in self.rgb.iter_mut().zip(point.color.iter_mut()) {
+			*value = loader.load(mmap, at_end)? as u8;
+		}
+		Ok(())
+	}
+
+	fn restart(&mut self, mmap: &memmap2::Mmap, packet_offset: usize) -> Result<(), Error> {
+		for (loader, _) in self.xyz.iter_mut() {
+			loader.restart(mmap, packet_offset)?;
+		}
+		for loader in self.rgb.iter_mut() {
+			loader.restart(mmap, packet_offset)?;
+		}
+		Ok(())
+	}
+
+	fn can_restart(&self) -> bool {
+		true
+	}
+}
+
+struct SaveX;
+impl PropertySaver<ColoredPoint, f64> for SaveX {
+	fn save(point: &mut ColoredPoint, value: f64) {
+		point.position[0] = value;
+	}
+}
+
+struct SaveY;
+impl PropertySaver<ColoredPoint, f64> for SaveY {
+	fn save(point: &mut ColoredPoint, value: f64) {
+		point.position[1] = value;
+	}
+}
+
+struct SaveZ;
+impl PropertySaver<ColoredPoint, f64> for SaveZ {
+	fn save(point: &mut ColoredPoint, value: f64) {
+		point.position[2] = value;
+	}
+}
+
+struct SaveRed;
+impl PropertySaver<ColoredPoint, f32> for SaveRed {
+	fn save(point: &mut ColoredPoint, value: f32) {
+		point.color[0] = (value * 255.0).round() as u8;
+	}
+}
+
+struct SaveGreen;
+impl PropertySaver<ColoredPoint, f32> for SaveGreen {
+	fn save(point: &mut ColoredPoint, value: f32) {
+		point.color[1] = (value * 255.0).round() as u8;
+	}
+}
+
+struct SaveBlue;
+impl PropertySaver<ColoredPoint, f32> for SaveBlue {
+	fn save(point: &mut ColoredPoint, value: f32) {
+		point.color[2] = (value * 255.0).round() as u8;
+	}
+}
+
+struct SaveInvalid;
+impl PropertySaver<ColoredPoint, i64> for SaveInvalid {
+	fn save(point: &mut ColoredPoint, value: i64) {
+		point.invalid = value != 0;
+	}
+}
+
+/// Generic reader for Cartesian coordinates with colors of any data type, used if the fast path is not applicable.
+pub(crate) fn colored_reader(
+	name: RecordName,
+	data_type: RecordDataType,
+	offset: usize,
+	index: usize,
+	mmap: &memmap2::Mmap,
+) -> Result<Option<Box<dyn PropertyReader<ColoredPoint>>>, Error> {
+	Ok(Some(match name {
+		RecordName::CartesianX => f64_reader(data_type, offset, index, mmap, SaveX)?,
+		RecordName::CartesianY => f64_reader(data_type, offset, index, mmap, SaveY)?,
+		RecordName::CartesianZ => f64_reader(data_type, offset, index, mmap, SaveZ)?,
+		RecordName::CartesianInvalidState => i64_reader(data_type, offset, index, mmap, SaveInvalid)?,
+		RecordName::ColorRed => unit_f32_reader(data_type, None, offset, index, mmap, SaveRed)?,
+		RecordName::ColorGreen => unit_f32_reader(data_type, None, offset, index, mmap, SaveGreen)?,
+		RecordName::ColorBlue => unit_f32_reader(data_type, None, offset, index, mmap, SaveBlue)?,
+		_ => return Ok(None),
+	}))
+}
@@ -1,7 +1,10 @@
+/// Converts a raw value loaded from the file into the value stored in the point.
 pub trait PropertyConverter<V0, V1> {
+	/// Converts a single value.
 	fn convert(&self, v: V0) -> V1;
 }
 
+/// Multiplies integers with the scale of a scaled integer record.
 pub struct ScaledIntConverter {
 	pub scale: f64,
 }
@@ -12,6 +15,17 @@ impl PropertyConverter<i64, f64> for ScaledIntConverter {
 	}
 }
 
+/// Keeps the unscaled integer next to the scaled value.
+impl PropertyConverter<i64, (f64, i64)> for ScaledIntConverter {
+	fn convert(&self, v: i64) -> (f64, i64) {
+		(v as f64 * self.scale, v)
+	}
+}
+
+/// Normalizes integers from the range `min..=max` to `0.0..=1.0`.
+///
+/// The result is clamped, values outside of the range never leave `0.0..=1.0`.
+/// An empty range, for example of a constant record, maps values from `max` upwards to 1.0 and all others to 0.0.
 pub struct UnitIntConverter {
 	pub min: i64,
 	pub max: i64,
@@ -19,10 +33,96 @@ pub struct UnitIntConverter {
 
 impl PropertyConverter<i64, f32> for UnitIntConverter {
 	fn convert(&self, v: i64) -> f32 {
-		(v - self.min) as f32 / (self.max - self.min) as f32
+		if self.max <= self.min {
+			return if v >= self.max { 1.0 } else { 0.0 };
+		}
+		(((v as f64 - self.min as f64) / (self.max as f64 - self.min as f64)) as f32).clamp(0.0, 1.0)
+	}
+}
+
+/// Normalizes values of any data type from the range `min..=max` to `0.0..=1.0`.
+///
+/// The value is multiplied with the scale before the normalization,
+/// which allows limits with a different data type than the record, for example float limits for scaled integers.
+/// The result is clamped, values outside of the limits and rounding errors never leave `0.0..=1.0`.
+/// Empty limits map values from `max` upwards to 1.0 and all others to 0.0.
+pub struct UnitConverter {
+	pub scale: f64,
+	pub min:   f64,
+	pub max:   f64,
+}
+
+impl UnitConverter {
+	fn normalize(&self, v: f64) -> f32 {
+		let v = v * self.scale;
+		if self.max <= self.min {
+			return if v >= self.max { 1.0 } else { 0.0 };
+		}
+		(((v - self.min) / (self.max - self.min)) as f32).clamp(0.0, 1.0)
+	}
+}
+
+impl PropertyConverter<i64, f32> for UnitConverter {
+	fn convert(&self, v: i64) -> f32 {
+		self.normalize(v as f64)
+	}
+}
+
+impl PropertyConverter<f32, f32> for UnitConverter {
+	fn convert(&self, v: f32) -> f32 {
+		self.normalize(v as f64)
+	}
+}
+
+impl PropertyConverter<f64, f32> for UnitConverter {
+	fn convert(&self, v: f64) -> f32 {
+		self.normalize(v)
+	}
+}
+
+/// Keeps the original integer next to the normalized value.
+impl PropertyConverter<i64, (f32, i64)> for UnitConverter {
+	fn convert(&self, v: i64) -> (f32, i64) {
+		(self.normalize(v as f64), v)
+	}
+}
+
+/// Color space of decoded color values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+	/// Values are returned as stored, scanners usually store sRGB gamma encoded colors.
+	#[default]
+	Srgb,
+	/// Values are converted with the sRGB transfer function to linear color.
+	Linear,
+}
+
+impl ColorSpace {
+	/// Converts a normalized sRGB value between 0 and 1 into this color space.
+	pub fn from_srgb(self, value: f32) -> f32 {
+		match self {
+			ColorSpace::Srgb => value,
+			ColorSpace::Linear if value <= 0.04045 => value / 12.92,
+			ColorSpace::Linear => ((value + 0.055) / 1.055).powf(2.4),
+		}
+	}
+}
+
+/// Normalizes integer colors from the range `min..=max` to `0.0..=1.0` in the selected color space.
+pub struct ColorConverter {
+	pub min:         i64,
+	pub max:         i64,
+	pub color_space: ColorSpace,
+}
+
+impl PropertyConverter<i64, f32> for ColorConverter {
+	fn convert(&self, v: i64) -> f32 {
+		let value = UnitIntConverter { min: self.min, max: self.max }.convert(v);
+		self.color_space.from_srgb(value)
 	}
 }
 
+/// Truncates integers to `u8`, intended for records with values between 0 and 255.
 pub struct U8Converter;
 impl PropertyConverter<i64, u8> for U8Converter {
 	fn convert(&self, v: i64) -> u8 {
@@ -30,6 +130,7 @@ impl PropertyConverter<i64, u8> for U8Converter {
 	}
 }
 
+/// Passes the loaded value through unchanged.
 pub struct IdentityConverter;
 impl<V> PropertyConverter<V, V> for IdentityConverter {
 	fn convert(&self, v: V) -> V {
@@ -37,6 +138,7 @@ impl<V> PropertyConverter<V, V> for IdentityConverter {
 	}
 }
 
+/// Converts single precision values to double precision.
 pub struct F32ToF64Converter;
 impl PropertyConverter<f32, f64> for F32ToF64Converter {
 	fn convert(&self, v: f32) -> f64 {
@@ -0,0 +1,152 @@
+use super::f64_reader;
+use super::i64_reader;
+use super::PropertyReader;
+use super::PropertySaver;
+use crate::spherical_to_cartesian;
+use crate::Error;
+use crate::RecordDataType;
+use crate::RecordName;
+
+/// Structured grid of points from an organized point cloud, created by [`crate::Reader::range_image`].
+#[derive(Clone, Debug)]
+pub struct RangeImage {
+	/// Number of columns.
+	pub width:  usize,
+	/// Number of rows.
+	pub height: usize,
+	/// Cartesian coordinates in row-major order, cells without a valid point are `None`.
+	pub cells:  Vec<Option<[f64; 3]>>,
+}
+
+impl RangeImage {
+	/// Returns the cell at the given zero-based row and column.
+	pub fn get(&self, row: usize, column: usize) -> Option<[f64; 3]> {
+		if row >= self.height || column >= self.width {
+			return None;
+		}
+		self.cells[row * self.width + column]
+	}
+
+	/// Returns the distance of the point at the given row and column to the origin.
+	pub fn range(&self, row: usize, column: usize) -> Option<f64> {
+		self.get(row, column).map(|[x, y, z]| (x * x + y * y + z * z).sqrt())
+	}
+}
+
+/// Position with its grid location and invalid states of a single point.
+#[derive(Default)]
+pub(crate) struct GridPoint {
+	pub cartesian: [f64; 3],
+	pub spherical: [f64; 3],
+	pub row:       i64,
+	pub column:    i64,
+
+	pub cartesian_invalid: bool,
+	pub spherical_invalid: bool,
+}
+
+impl GridPoint {
+	/// Returns the Cartesian coordinates, converted from spherical coordinates if required.
+	pub fn position(&self, spherical: bool) -> Option<[f64; 3]> {
+		if !spherical {
+			return (!self.cartesian_invalid).then_some(self.cartesian);
+		}
+		if self.spherical_invalid {
+			return None;
+		}
+		spherical_to_cartesian(self.spherical)
+	}
+}
+
+struct SaveX;
+impl PropertySaver<GridPoint, f64> for SaveX {
+	fn save(point: &mut GridPoint, value: f64) {
+		point.cartesian[0] = value;
+	}
+}
+
+struct SaveY;
+impl PropertySaver<GridPoint, f64> for SaveY {
+	fn save(point: &mut GridPoint, value: f64) {
+		point.cartesian[1] = value;
+	}
+}
+
+struct SaveZ;
+impl PropertySaver<GridPoint, f64> for SaveZ {
+	fn save(point: &mut GridPoint, value: f64) {
+		point.cartesian[2] = value;
+	}
+}
+
+struct SaveRange;
+impl PropertySaver<GridPoint, f64> for SaveRange {
+	fn save(point: &mut GridPoint, value: f64) {
+		point.spherical[0] = value;
+	}
+}
+
+struct SaveAzimuth;
+impl PropertySaver<GridPoint, f64> for SaveAzimuth {
+	fn save(point: &mut GridPoint, value: f64) {
+		point.spherical[1] = value;
+	}
+}
+
+struct SaveElevation;
+impl PropertySaver<GridPoint, f64> for SaveElevation {
+	fn save(point: &mut GridPoint, value: f64) {
+		point.spherical[2] = value;
+	}
+}
+
+struct SaveRow;
+impl PropertySaver<GridPoint, i64> for SaveRow {
+	fn save(point: &mut GridPoint, value: i64) {
+		point.row = value;
+	}
+}
+
+struct SaveColumn;
+impl PropertySaver<GridPoint, i64> for SaveColumn {
+	fn save(point: &mut GridPoint, value: i64) {
+		point.column = value;
+	}
+}
+
+struct SaveCartesianInvalid;
+impl PropertySaver<GridPoint, i64> for SaveCartesianInvalid {
+	fn save(point: &mut GridPoint, value: i64) {
+		point.cartesian_invalid = value != 0;
+	}
+}
+
+struct SaveSphericalInvalid;
+impl PropertySaver<GridPoint, i64> for SaveSphericalInvalid {
+	fn save(point: &mut GridPoint, value: i64) {
+		point.spherical_invalid = value != 0;
+	}
+}
+
+/// Creates the readers for Cartesian and spherical coordinates together with the row and column index.
+pub(crate) fn grid_reader(
+	name: RecordName,
+	data_type: RecordDataType,
+	offset: usize,
+	index: usize,
+	mmap: &memmap2::Mmap,
+) -> Result<Option<Box<dyn PropertyReader<GridPoint>>>, Error> {
+	Ok(Some(match name {
+		RecordName::CartesianX => f64_reader(data_type, offset, index, mmap, SaveX)?,
+		RecordName::CartesianY => f64_reader(data_type, offset, index, mmap, SaveY)?,
+		RecordName::CartesianZ => f64_reader(data_type, offset, index, mmap, SaveZ)?,
+		RecordName::CartesianInvalidState => i64_reader(data_type, offset, index, mmap, SaveCartesianInvalid)?,
+		RecordName::SphericalRange => f64_reader(data_type, offset, index, mmap, SaveRange)?,
+		RecordName::SphericalAzimuth => f64_reader(data_type, offset, index, mmap, SaveAzimuth)?,
+		RecordName::SphericalElevation => f64_reader(data_type, offset, index, mmap, SaveElevation)?,
+		RecordName::SphericalInvalidState => i64_reader(data_type, offset, index, mmap, SaveSphericalInvalid)?,
+		RecordName::RowIndex => i64_reader(data_type, offset, index, mmap, SaveRow)?,
+		RecordName::ColumnIndex => i64_reader(data_type, offset, index, mmap, SaveColumn)?,
+		_ => return Ok(None),
+	}))
+}
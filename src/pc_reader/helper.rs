@@ -0,0 +1,111 @@
+use super::F32Loader;
+use super::F32ToF64Converter;
+use super::F64Loader;
+use super::GenPropertyReader;
+use super::IdentityConverter;
+use super::IntLoader;
+use super::PropertyReader;
+use super::PropertySaver;
+use super::ScaledIntConverter;
+use super::UnitConverter;
+use crate::Error;
+use crate::RecordDataType;
+
+/// Creates a property reader that converts values of any data type to `f64`.
+///
+/// Scaled integers are multiplied with their scale, all other values are converted directly.
+pub fn f64_reader<Point, Saver>(
+	data_type: RecordDataType,
+	offset: usize,
+	index: usize,
+	mmap: &memmap2::Mmap,
+	saver: Saver,
+) -> Result<Box<dyn PropertyReader<Point>>, Error>
+where
+	Point: 'static,
+	Saver: PropertySaver<Point, f64> + 'static,
+{
+	Ok(match data_type {
+		RecordDataType::Single { .. } => {
+			GenPropertyReader::boxed(F32Loader::new(offset, index, mmap)?, F32ToF64Converter, saver)
+		},
+		RecordDataType::Double { .. } => {
+			GenPropertyReader::boxed(F64Loader::new(offset, index, mmap)?, IdentityConverter, saver)
+		},
+		RecordDataType::ScaledInteger { min, max, scale } => GenPropertyReader::boxed(
+			IntLoader::new(offset, index, min, max, mmap)?,
+			ScaledIntConverter { scale },
+			saver,
+		),
+		RecordDataType::Integer { min, max } => GenPropertyReader::boxed(
+			IntLoader::new(offset, index, min, max, mmap)?,
+			ScaledIntConverter { scale: 1.0 },
+			saver,
+		),
+	})
+}
+
+/// Creates a property reader for the raw values of integer and scaled integer records.
+///
+/// Scaled integers are not multiplied with their scale.
+/// Returns an error for floating point records.
+pub fn i64_reader<Point, Saver>(
+	data_type: RecordDataType,
+	offset: usize,
+	index: usize,
+	mmap: &memmap2::Mmap,
+	saver: Saver,
+) -> Result<Box<dyn PropertyReader<Point>>, Error>
+where
+	Point: 'static,
+	Saver: PropertySaver<Point, i64> + 'static,
+{
+	match data_type {
+		RecordDataType::ScaledInteger { min, max, .. } | RecordDataType::Integer { min, max } => Ok(
+			GenPropertyReader::boxed(IntLoader::new(offset, index, min, max, mmap)?, IdentityConverter, saver),
+		),
+		_ => Error::Invalid(format!("Cannot read {data_type:?} record as integer")).throw(),
+	}
+}
+
+/// Creates a property reader that normalizes values of any data type to `0.0..=1.0`.
+///
+/// The limits take precedence over the minimum and maximum of the record data type,
+/// they are used as `f64` while scaled integers are multiplied with their scale first.
+/// Passing the [`crate::IntensityLimits`] of a point cloud normalizes intensities against the dynamic range of the scanner,
+/// which is often narrower than the range of the record data type.
+/// Returns an error for floating point records without limits and without minimum and maximum.
+pub fn unit_f32_reader<Point, Saver>(
+	data_type: RecordDataType,
+	limits: Option<(f64, f64)>,
+	offset: usize,
+	index: usize,
+	mmap: &memmap2::Mmap,
+	saver: Saver,
+) -> Result<Box<dyn PropertyReader<Point>>, Error>
+where
+	Point: 'static,
+	Saver: PropertySaver<Point, f32> + 'static,
+{
+	let (scale, declared) = match data_type {
+		RecordDataType::Single { min, max } => (1.0, min.zip(max).map(|(min, max)| (min as f64, max as f64))),
+		RecordDataType::Double { min, max } => (1.0, min.zip(max)),
+		RecordDataType::ScaledInteger { min, max, scale } => (scale, Some((min as f64 * scale, max as f64 * scale))),
+		RecordDataType::Integer { min, max } => (1.0, Some((min as f64, max as f64))),
+	};
+	let Some((min, max)) = limits.or(declared) else {
+		return Error::Invalid(format!("Cannot normalize {data_type:?} record without limits")).throw();
+	};
+	let converter = UnitConverter { scale, min, max };
+	Ok(match data_type {
+		RecordDataType::Single { .. } => {
+			GenPropertyReader::boxed(F32Loader::new(offset, index, mmap)?, converter, saver)
+		},
+		RecordDataType::Double { .. } => {
+			GenPropertyReader::boxed(F64Loader::new(offset, index, mmap)?, converter, saver)
+		},
+		RecordDataType::ScaledInteger { min, max, .. } | RecordDataType::Integer { min, max } => {
+			GenPropertyReader::boxed(IntLoader::new(offset, index, min, max, mmap)?, converter, saver)
+		},
+	})
+}
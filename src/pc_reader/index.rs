@@ -0,0 +1,129 @@
+use super::loader::index_mmap;
+use super::packet::records_in_bytestreams;
+use super::packet::stored_records;
+use super::packet::values_aligned;
+use super::PacketIterator;
+use super::PacketType;
+use super::SectionHeader;
+use crate::error::INTERNAL_ERROR;
+use crate::mmap_paged::to_logical;
+use crate::mmap_paged::to_physical;
+use crate::Error;
+use crate::PointCloud;
+use crate::Record;
+
+const INDEX_PACKET_HEADER_SIZE: usize = 16;
+const INDEX_ENTRY_SIZE: usize = 16;
+
+/// Entry of an index packet that points to the data packet containing a specific record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct IndexEntry {
+	/// Number of the first record stored in the data packet.
+	pub record_number:   u64,
+	/// Physical file offset of the data packet.
+	pub physical_offset: u64,
+}
+
+/// Reads the entries of the index packet at the physical offset.
+pub(crate) fn read_index(physical_offset: u64, mmap: &memmap2::Mmap) -> Result<Vec<IndexEntry>, Error> {
+	let offset = to_logical(physical_offset as usize);
+	if to_physical(offset + INDEX_PACKET_HEADER_SIZE) > mmap.len() {
+		return Error::Invalid("Index packet is located outside of the file".into()).throw();
+	}
+	let header = index_mmap(mmap, offset, offset + 6);
+	if header[0] != 0 {
+		return Error::Invalid(format!(
+			"Expected index packet (0) but found packet type ({})",
+			header[0]
+		))
+		.throw();
+	}
+	let length = u16::from_le_bytes(header[2..4].try_into().expect(INTERNAL_ERROR)) as usize + 1;
+	let entry_count = u16::from_le_bytes(header[4..6].try_into().expect(INTERNAL_ERROR)) as usize;
+	if INDEX_PACKET_HEADER_SIZE + entry_count * INDEX_ENTRY_SIZE > length {
+		return Error::Invalid(format!(
+			"Index packet with length {length} cannot contain {entry_count} entries"
+		))
+		.throw();
+	}
+	if to_physical(offset + length) > mmap.len() {
+		return Error::Invalid("Index packet extends past the end of the file".into()).throw();
+	}
+
+	let mut entries = Vec::with_capacity(entry_count);
+	for index in 0..entry_count {
+		let start = offset + INDEX_PACKET_HEADER_SIZE + index * INDEX_ENTRY_SIZE;
+		let record_number = index_mmap(mmap, start, start + 8);
+		let record_number = u64::from_le_bytes(record_number.try_into().expect(INTERNAL_ERROR));
+		let physical_offset = index_mmap(mmap, start + 8, start + 16);
+		let physical_offset = u64::from_le_bytes(physical_offset.try_into().expect(INTERNAL_ERROR));
+		entries.push(IndexEntry { record_number, physical_offset });
+	}
+	Ok(entries)
+}
+
+/// Determines the number of records of a point cloud without a record count.
+///
+/// Uses the last entry of the index packet and the size of the data packet it points to.
+/// Sections without an index are measured by the size of all data packets.
+/// Returns an error for prototypes with only constant records, which store no data to count.
+pub(crate) fn infer_record_count(pc: &PointCloud, mmap: &memmap2::Mmap) -> Result<u64, Error> {
+	let constant = || Error::Invalid("Cannot infer the record count of a prototype with only constant records".into());
+	let section = SectionHeader::read(pc.file_offset, mmap)?;
+	if section.index_offset != 0 {
+		if let Some(last) = read_index(section.index_offset, mmap)?.last() {
+			let start = SectionHeader { data_offset: last.physical_offset, ..section };
+			let packet = PacketIterator::new(&start, mmap).next().transpose()?;
+			let Some(packet) = packet.filter(|p| p.packet_type == PacketType::Data) else {
+				return Error::Invalid("Last index entry does not point to a data packet".into()).throw();
+			};
+			let records = records_in_bytestreams(&pc.prototype, &packet.bytestream_sizes).ok_or_else(constant)?;
+			return match last.record_number.checked_add(records) {
+				Some(records) => Ok(records),
+				None => Error::Invalid(format!(
+					"Index entry with record number {} is invalid",
+					last.record_number
+				))
+				.throw(),
+			};
+		}
+	}
+	stored_records(&section, &pc.prototype, mmap)?.ok_or_else(constant)
+}
+
+/// Finds the data packet to continue decoding from to reach the given record.
+///
+/// Returns the logical offset of the packet and the number of its first record.
+/// Returns `None` if the section has no index or the packet does not start at a value boundary in all bytestreams.
+/// The boundaries are verified with the headers of the preceding data packets, their bytestreams are not decoded.
+pub(crate) fn seek_packet(
+	section: &SectionHeader,
+	prototype: &[Record],
+	record: u64,
+	mmap: &memmap2::Mmap,
+) -> Result<Option<(usize, u64)>, Error> {
+	if section.index_offset == 0 {
+		return Ok(None);
+	}
+	let entries = read_index(section.index_offset, mmap)?;
+	let Some(entry) = entries.iter().rev().find(|e| e.record_number <= record) else {
+		return Ok(None);
+	};
+	let target = to_logical(entry.physical_offset as usize) as u64;
+	let mut bytes = vec![0_u64; prototype.len()];
+	for packet in PacketIterator::new(section, mmap) {
+		let packet = packet?;
+		if packet.offset == target {
+			let aligned = values_aligned(prototype, &bytes, entry.record_number);
+			let found = packet.packet_type == PacketType::Data && aligned;
+			return Ok(found.then_some((target as usize, entry.record_number)));
+		}
+		if packet.offset > target {
+			break;
+		}
+		for (sum, size) in bytes.iter_mut().zip(&packet.bytestream_sizes) {
+			*sum += size;
+		}
+	}
+	Ok(None)
+}
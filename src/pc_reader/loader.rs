@@ -1,3 +1,5 @@
+use crate::mmap_paged::to_physical;
+use crate::record::integer_bits;
 use crate::{error::INTERNAL_ERROR, Error};
 
 const ALIGNMENT_SIZE: usize = 4;
@@ -24,16 +26,38 @@ impl Position {
 	}
 
 	fn load_next(&mut self, mmap: &memmap2::Mmap) -> Result<usize, Error> {
-		let header = index_mmap(mmap, self.offset, self.offset + 6);
-		if header[0] != 1 {
-			return Err(Error::Invalid(format!(
-				"only data headers (1) allowed, got ({})",
-				header[0]
-			)));
-		}
+		let header = loop {
+			if to_physical(self.offset + 6) > mmap.len() {
+				return Err(Error::Invalid(
+					"reached the end of the file before all records were read, the compressed vector section \
+					 contains fewer points than its 'recordCount'"
+						.into(),
+				));
+			}
+			let header = index_mmap(mmap, self.offset, self.offset + 6);
+			match header[0] {
+				1 => break header,
+				// Empty packets are only padding and can be skipped
+				2 => self.offset += u16::from_le_bytes(header[2..4].try_into().expect(INTERNAL_ERROR)) as usize + 1,
+				0 => {
+					return Err(Error::Invalid(
+						"reached an index packet before all records were read, the compressed vector section \
+						 contains fewer points than its 'recordCount'"
+							.into(),
+					))
+				},
+				id => return Err(Error::Invalid(format!("only data headers (1) allowed, got ({id})"))),
+			}
+		};
 		let _comp_restart_flag = header[1] & 1 != 0;
 		let packet_length = u16::from_le_bytes(header[2..4].try_into().expect(INTERNAL_ERROR)) as usize + 1;
 		let bytestream_count = u16::from_le_bytes(header[4..6].try_into().expect(INTERNAL_ERROR));
+		if self.index >= bytestream_count as usize {
+			return Err(Error::Invalid(format!(
+				"bytestream ({}) is missing, packet contains only {} bytestreams",
+				self.index, bytestream_count
+			)));
+		}
 
 		let mut block_current = 6 + bytestream_count as usize * 2;
 		let mut block_size = 0;
@@ -56,11 +80,26 @@ impl Position {
 	}
 }
 
+/// Loads the raw values of a single record from the bytestreams of the data packets.
 pub trait PropertyLoader<V> {
+	/// Loads the next value, `at_end` must be true for the last value of the point cloud.
 	fn load(&mut self, mmap: &memmap2::Mmap, at_end: bool) -> Result<V, Error>;
+
+	/// Continues loading at the start of the data packet at the logical offset.
+	///
+	/// Used by [`crate::PointCloudReader::seek_point`], loaders without support return an error.
+	fn restart(&mut self, mmap: &memmap2::Mmap, packet_offset: usize) -> Result<(), Error> {
+		let _ = (mmap, packet_offset);
+		Error::Unimplemented("Property loader does not support seeking".into()).throw()
+	}
+
+	/// Returns true if [`PropertyLoader::restart`] is implemented.
+	fn can_restart(&self) -> bool {
+		false
+	}
 }
 
-fn index_mmap(mmap: &memmap2::Mmap, start: usize, end: usize) -> &[u8] {
+pub(crate) fn index_mmap(mmap: &memmap2::Mmap, start: usize, end: usize) -> &[u8] {
 	#[thread_local]
 	static mut BACKUP: [u8; 16] = [0u8; 16];
 	let pages_start = start / LOGICAL_PAGE_SIZE;
@@ -79,6 +118,7 @@ fn index_mmap(mmap: &memmap2::Mmap, start: usize, end: usize) -> &[u8] {
 	&mmap[start..end]
 }
 
+/// Loads bit-packed values of integer and scaled integer records.
 pub struct IntLoader {
 	position: Position,
 	min:      i64,
@@ -95,9 +135,14 @@ impl IntLoader {
 		max: i64,
 		mmap: &memmap2::Mmap,
 	) -> Result<Self, Error> {
-		let range = max - min;
-		let bits = u64::BITS - range.leading_zeros();
-		let mask = (1u64 << bits) - 1;
+		if max < min {
+			return Error::Invalid(format!(
+				"Integer range with minimum '{min}' and maximum '{max}' is invalid"
+			))
+			.throw();
+		}
+		let bits = integer_bits(min, max);
+		let mask = u64::MAX.checked_shr(u64::BITS - bits).unwrap_or(0);
 		Ok(IntLoader {
 			position: Position::new(prototype_offset, prototype_index, mmap)?,
 			min,
@@ -110,8 +155,13 @@ impl IntLoader {
 
 impl PropertyLoader<i64> for IntLoader {
 	fn load(&mut self, mmap: &memmap2::Mmap, at_end: bool) -> Result<i64, Error> {
-		let end_offset = ((self.offset + self.bits + 7) / 8) as usize;
-		let mut tmp = [0u8; 8];
+		// Constant values have an empty bytestream, which must not advance to the next packet
+		if self.bits == 0 {
+			return Ok(self.min);
+		}
+		// Values with up to 64 bits can start at any bit of a byte and span up to nine bytes
+		let end_offset = (self.offset + self.bits).div_ceil(8) as usize;
+		let mut tmp = [0u8; 16];
 		tmp[0..end_offset].copy_from_slice(index_mmap(
 			mmap,
 			self.position.current,
@@ -132,13 +182,24 @@ impl PropertyLoader<i64> for IntLoader {
 			}
 		}
 
-		let uint_value = (u64::from_le_bytes(tmp) >> self.offset) & self.mask;
-		let int_value = uint_value as i64 + self.min;
+		let uint_value = (u128::from_le_bytes(tmp) >> self.offset) as u64 & self.mask;
+		let int_value = self.min.wrapping_add(uint_value as i64);
 		self.offset = (self.offset + self.bits) % 8;
 		Ok(int_value)
 	}
+
+	fn restart(&mut self, mmap: &memmap2::Mmap, packet_offset: usize) -> Result<(), Error> {
+		self.position = Position::new(packet_offset, self.position.index, mmap)?;
+		self.offset = 0;
+		Ok(())
+	}
+
+	fn can_restart(&self) -> bool {
+		true
+	}
 }
 
+/// Loads values of double precision floating point records.
 pub struct F64Loader {
 	position: Position,
 }
@@ -173,8 +234,18 @@ impl PropertyLoader<f64> for F64Loader {
 		}
 		Ok(f64::from_le_bytes(tmp))
 	}
+
+	fn restart(&mut self, mmap: &memmap2::Mmap, packet_offset: usize) -> Result<(), Error> {
+		self.position = Position::new(packet_offset, self.position.index, mmap)?;
+		Ok(())
+	}
+
+	fn can_restart(&self) -> bool {
+		true
+	}
 }
 
+/// Loads values of single precision floating point records.
 pub struct F32Loader {
 	position: Position,
 }
@@ -209,4 +280,13 @@ impl PropertyLoader<f32> for F32Loader {
 		}
 		Ok(f32::from_le_bytes(tmp))
 	}
+
+	fn restart(&mut self, mmap: &memmap2::Mmap, packet_offset: usize) -> Result<(), Error> {
+		self.position = Position::new(packet_offset, self.position.index, mmap)?;
+		Ok(())
+	}
+
+	fn can_restart(&self) -> bool {
+		true
+	}
 }
@@ -1,32 +1,131 @@
+mod bounds_check;
+mod colored;
 mod converter;
+mod grid;
+mod helper;
+mod index;
 mod loader;
+mod organized;
+mod packet;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod point;
+mod position;
+mod prefetch;
+mod pulses;
+mod raw;
+mod section;
+mod stats;
+mod validity;
 
-use crate::error::INTERNAL_ERROR;
-use crate::mmap_paged;
+use self::bounds_check::BoundsCheck;
+pub(crate) use self::colored::colored_reader;
+pub(crate) use self::colored::ColoredFastPath;
+pub(crate) use self::grid::grid_reader;
+pub(crate) use self::index::infer_record_count;
+use self::index::seek_packet;
+use self::packet::stored_records;
+#[cfg(feature = "rayon")]
+pub(crate) use self::parallel::parallel_points;
+pub(crate) use self::point::point_reader;
+pub(crate) use self::point::supported_by_point;
+pub(crate) use self::position::position_reader;
+use self::prefetch::Prefetcher;
+pub(crate) use self::raw::raw_reader;
+pub(crate) use self::section::SectionHeader;
+pub use self::section::SectionInfo;
 use crate::Error;
 use crate::PointCloud;
 use crate::RecordDataType;
 use crate::RecordName;
 
+pub use self::bounds_check::BoundsViolations;
+pub use self::bounds_check::MAX_REPORTED_VIOLATIONS;
+pub use self::colored::ColoredPosition;
+pub use self::converter::ColorConverter;
+pub use self::converter::ColorSpace;
 pub use self::converter::F32ToF64Converter;
 pub use self::converter::IdentityConverter;
 pub use self::converter::PropertyConverter;
 pub use self::converter::ScaledIntConverter;
 pub use self::converter::U8Converter;
+pub use self::converter::UnitConverter;
 pub use self::converter::UnitIntConverter;
+pub use self::grid::RangeImage;
+pub use self::helper::f64_reader;
+pub use self::helper::i64_reader;
+pub use self::helper::unit_f32_reader;
 pub use self::loader::F32Loader;
 pub use self::loader::F64Loader;
 pub use self::loader::IntLoader;
 pub use self::loader::PropertyLoader;
+pub use self::organized::Organized;
+pub use self::packet::decode_packet;
+pub use self::packet::PacketInfo;
+pub use self::packet::PacketIterator;
+pub use self::packet::PacketType;
+pub use self::point::InvalidPolicy;
+pub use self::point::Point;
+pub use self::point::PointOptions;
+pub use self::prefetch::DEFAULT_PREFETCH_PAGES;
+pub use self::pulses::Pulses;
+pub use self::raw::RawValuesReader;
+pub use self::raw::TypedRawValues;
+pub use self::stats::ReadStats;
+pub use self::validity::validity_reader;
+pub use self::validity::Validity;
+pub use self::validity::ValidityConverter;
 
+/// Stores a converted value of a single record in a user-defined point type.
+///
+/// Implement this trait for a marker type per point attribute to decode directly into a custom point struct.
 pub trait PropertySaver<Point, V> {
+	/// Writes the value into the point.
 	fn save(point: &mut Point, value: V);
 }
 
+/// Point types with Cartesian coordinates that can be modified after decoding.
+pub trait CartesianPoint {
+	/// Returns the X, Y and Z coordinates of the point.
+	fn position(&self) -> [f64; 3];
+	/// Replaces the X, Y and Z coordinates of the point.
+	fn set_position(&mut self, position: [f64; 3]);
+}
+
+impl CartesianPoint for [f64; 3] {
+	fn position(&self) -> [f64; 3] {
+		*self
+	}
+
+	fn set_position(&mut self, position: [f64; 3]) {
+		*self = position;
+	}
+}
+
+/// Decodes the next value of a single record and stores it in the point.
+///
+/// Usually created with [`GenPropertyReader`], which combines a [`PropertyLoader`],
+/// a [`PropertyConverter`] and a [`PropertySaver`].
 pub trait PropertyReader<Point> {
+	/// Reads the next value into the point.
+	/// `at_end` is true for the last point of the point cloud, no further packets are loaded after it.
 	fn read(&mut self, mmap: &memmap2::Mmap, point: &mut Point, at_end: bool) -> Result<(), Error>;
+
+	/// Continues reading at the start of the data packet at the logical offset, see [`PropertyLoader::restart`].
+	fn restart(&mut self, mmap: &memmap2::Mmap, packet_offset: usize) -> Result<(), Error> {
+		let _ = (mmap, packet_offset);
+		Error::Unimplemented("Property reader does not support seeking".into()).throw()
+	}
+
+	/// Returns true if [`PropertyReader::restart`] is implemented.
+	///
+	/// Without restart support [`PointCloudReader::seek_point`] can only seek forward by decoding sequentially.
+	fn can_restart(&self) -> bool {
+		false
+	}
 }
 
+/// Generic property reader that loads a raw value, converts it and saves it in the point.
 pub struct GenPropertyReader<Loader, Saver, Converter, Point, V0, V1>
 where
 	Loader: PropertyLoader<V0>,
@@ -45,6 +144,7 @@ where
 	Converter: PropertyConverter<V0, V1>,
 	Saver: PropertySaver<Point, V1>,
 {
+	/// Creates a new property reader from its parts.
 	pub fn new(loader: Loader, converter: Converter, saver: Saver) -> Self {
 		GenPropertyReader {
 			loader,
@@ -54,6 +154,7 @@ where
 		}
 	}
 
+	/// Creates a new boxed property reader, ready to be returned from the reader closure.
 	pub fn boxed(loader: Loader, converter: Converter, saver: Saver) -> Box<Self> {
 		Box::new(Self::new(loader, converter, saver))
 	}
@@ -72,8 +173,20 @@ where
 		Saver::save(point, value);
 		Ok(())
 	}
+
+	fn restart(&mut self, mmap: &memmap2::Mmap, packet_offset: usize) -> Result<(), Error> {
+		self.loader.restart(mmap, packet_offset)
+	}
+
+	fn can_restart(&self) -> bool {
+		self.loader.can_restart()
+	}
 }
 
+type PointTransform<'a, Point> = Box<dyn Fn(&mut Point) + 'a>;
+type FastPath<Point> = fn(&PointCloud, usize, &memmap2::Mmap) -> Result<Option<Box<dyn PropertyReader<Point>>>, Error>;
+type PointFilter<'a, Point> = Box<dyn Fn(&Point) -> bool + 'a>;
+
 /// Iterate over all points of an existing point cloud to read it.
 pub struct PointCloudReader<'a, Point>
 where
@@ -83,6 +196,13 @@ where
 	read: u64,
 
 	property_readers: Vec<Box<dyn PropertyReader<Point>>>,
+	complete:         Option<PointTransform<'a, Point>>,
+	transforms:       Vec<PointTransform<'a, Point>>,
+	invalid_policy:   Option<PointTransform<'a, Point>>,
+	filter:           Option<PointFilter<'a, Point>>,
+	bounds_check:     Option<BoundsCheck<Point>>,
+	peeked:           Option<Option<Result<Point, Error>>>,
+	prefetcher:       Prefetcher,
 	mmap:             &'a memmap2::Mmap,
 }
 
@@ -100,29 +220,63 @@ where
 			&'a memmap2::Mmap,
 		) -> Result<Option<Box<dyn PropertyReader<Point>>>, Error>,
 	{
-		let mut buffer = [0_u8; 32];
-		mmap_paged::read(&mut buffer, pc.file_offset as usize, mmap);
-
-		let section_id = buffer[0];
-		let section_length = u64::from_le_bytes(buffer[8..16].try_into().expect(INTERNAL_ERROR));
-		let data_offset = u64::from_le_bytes(buffer[16..24].try_into().expect(INTERNAL_ERROR));
-		let _index_offset = u64::from_le_bytes(buffer[24..32].try_into().expect(INTERNAL_ERROR));
+		Self::with_fast_path(pc, mmap, f, None)
+	}
 
-		if section_id != 1 {
-			return Error::Invalid("Section ID of the compressed vector section header is not 1".into()).throw();
-		}
-		if section_length % 4 != 0 {
-			return Error::Invalid("Section length is not aligned and a multiple of four".into()).throw();
+	/// Creates a reader that uses a single property reader for all records if the fast path supports the layout.
+	///
+	/// The fast path returns `None` for unsupported prototypes, which are read with the property readers of `f`.
+	/// All checks of the point cloud and its section are the same for both variants.
+	pub(crate) fn with_fast_path<F>(
+		pc: &PointCloud,
+		mmap: &'a memmap2::Mmap,
+		f: F,
+		fast_path: Option<FastPath<Point>>,
+	) -> Result<Self, Error>
+	where
+		F: Fn(
+			RecordName,
+			RecordDataType,
+			usize,
+			usize,
+			&'a memmap2::Mmap,
+		) -> Result<Option<Box<dyn PropertyReader<Point>>>, Error>,
+	{
+		// Point clouds without a record count that could not be inferred report the error when they are read
+		if pc.infer_records {
+			infer_record_count(pc, mmap)?;
 		}
-
+		let section = SectionHeader::read(pc.file_offset, mmap)?;
 		let pc = pc.clone();
+		let logical_offset = section.logical_data_offset();
 
-		let logical_offset = data_offset as usize;
-		let logical_offset = logical_offset - (logical_offset / 1024) * 4;
+		// Bytestreams are mapped to records by their index, so their numbers must match
+		if let Some(packet) = PacketIterator::new(&section, mmap).next() {
+			let packet = packet?;
+			if packet.packet_type == PacketType::Data && packet.bytestream_count() != pc.prototype.len() {
+				return Error::Invalid(format!(
+					"Data packet contains {} bytestreams, but the prototype has {} records",
+					packet.bytestream_count(),
+					pc.prototype.len()
+				))
+				.throw();
+			}
+		}
 
 		let mut property_readers = Vec::<Box<dyn PropertyReader<Point>>>::new();
+		let fast_path = match fast_path {
+			Some(fast_path) if pc.records > 0 => fast_path(&pc, logical_offset, mmap)?,
+			_ => None,
+		};
 
-		for (index, prototype) in pc.prototype.iter().enumerate() {
+		// Loaders read the first data packet immediately, but empty point clouds may have no packets at all
+		let records = if pc.records == 0 || fast_path.is_some() {
+			&[][..]
+		} else {
+			&pc.prototype[..]
+		};
+		property_readers.extend(fast_path);
+		for (index, prototype) in records.iter().enumerate() {
 			let reader = match f(
 				prototype.name,
 				prototype.data_type,
@@ -136,29 +290,288 @@ where
 			property_readers.push(reader);
 		}
 
-		Ok(PointCloudReader { mmap, property_readers, pc, read: 0 })
+		Ok(PointCloudReader {
+			mmap,
+			property_readers,
+			complete: None,
+			transforms: Vec::new(),
+			invalid_policy: None,
+			filter: None,
+			bounds_check: None,
+			peeked: None,
+			prefetcher: Prefetcher::new(&section, DEFAULT_PREFETCH_PAGES, mmap),
+			pc,
+			read: 0,
+		})
 	}
 }
 
-impl<'a, Point> Iterator for PointCloudReader<'a, Point>
+impl<'a, Point> PointCloudReader<'a, Point>
 where
 	Point: Default,
 {
-	type Item = Result<Point, Error>;
+	/// Checks if the section contains as many records as declared in the XML section.
+	fn check_record_count(&self) -> Result<(), Error> {
+		let section = SectionHeader::read(self.pc.file_offset, self.mmap)?;
+		let stored = stored_records(&section, &self.pc.prototype, self.mmap)?;
+		if let Some(stored) = stored.filter(|stored| *stored < self.pc.records) {
+			return Error::Invalid(format!(
+				"Compressed vector section contains only {stored} of the {} points declared by 'recordCount'",
+				self.pc.records
+			))
+			.throw();
+		}
+		Ok(())
+	}
 
-	fn next(&mut self) -> Option<Self::Item> {
+	/// Decodes the next point into an existing point, returns `None` if all points were read.
+	pub(crate) fn read_into(&mut self, p: &mut Point) -> Option<Result<(), Error>> {
 		if self.read >= self.pc.records {
 			return None;
 		}
-		let mut p = Point::default();
+		if let Err(err) = self.read_properties(p) {
+			return Some(Err(err));
+		}
+		if let Some(bounds_check) = &mut self.bounds_check {
+			bounds_check.check(self.read, p);
+		}
+		for transform in self.transforms.iter() {
+			transform(p);
+		}
+		if let Some(invalid_policy) = &self.invalid_policy {
+			invalid_policy(p);
+		}
+		self.read += 1;
+		Some(Ok(()))
+	}
+
+	/// Decodes all properties of the next point without any checks or transformations.
+	///
+	/// Attributes derived from the decoded properties, like Cartesian coordinates of spherical point clouds,
+	/// are completed here, so the bounds check and all transformations see the final point.
+	fn read_properties(&mut self, p: &mut Point) -> Result<(), Error> {
 		let at_end = self.read >= self.pc.records - 1;
+		self.prefetcher.advance(self.mmap, self.read, self.pc.records);
 		for reader in self.property_readers.iter_mut() {
-			if let Err(err) = reader.read(self.mmap, &mut p, at_end) {
-				return Some(Err(err));
+			if let Err(err) = reader.read(self.mmap, p, at_end) {
+				return Err(self.check_record_count().err().unwrap_or(err));
 			}
 		}
-		self.read += 1;
+		if let Some(complete) = &self.complete {
+			complete(p);
+		}
+		Ok(())
+	}
+
+	/// Continues reading with the given point at the start of the data packet at the logical offset.
+	fn restart(&mut self, packet_offset: usize, first: u64) -> Result<(), Error> {
+		for reader in self.property_readers.iter_mut() {
+			reader.restart(self.mmap, packet_offset)?;
+		}
+		self.peeked = None;
+		self.read = first;
+		Ok(())
+	}
 
-		Some(Ok(p))
+	/// Moves the reader to the point with the given index, the next call of the iterator returns this point.
+	///
+	/// Uses the index packets of the compressed vector section to jump to the data packet that contains the point
+	/// and decodes only the points before it within this packet.
+	/// The preceding packets are only inspected by their headers.
+	/// Sections without an index, or with values that span multiple packets,
+	/// are decoded sequentially from the current position, or from the start when seeking backwards.
+	/// In this case the cost is the same as reading all skipped points.
+	/// Custom property readers without [`PropertyReader::can_restart`] are always decoded sequentially
+	/// and seeking backwards returns an error.
+	///
+	/// The index counts all points of the point cloud, regardless of any filter like [`InvalidPolicy::DropPoint`].
+	pub fn seek_point(&mut self, index: u64) -> Result<(), Error> {
+		if index > self.pc.records {
+			return Error::Invalid(format!(
+				"Cannot seek to point {index}, the point cloud contains only {} points",
+				self.pc.records
+			))
+			.throw();
+		}
+		let can_restart = self.property_readers.iter().all(|r| r.can_restart());
+		if !can_restart && index < self.read {
+			return Error::Unimplemented(format!(
+				"Cannot seek back to point {index}, the property readers do not support seeking"
+			))
+			.throw();
+		}
+		self.peeked = None;
+		if index == self.pc.records {
+			self.read = index;
+			return Ok(());
+		}
+
+		let section = SectionHeader::read(self.pc.file_offset, self.mmap)?;
+		let restart = match seek_packet(&section, &self.pc.prototype, index, self.mmap)? {
+			_ if !can_restart => None,
+			Some((offset, first)) if first > self.read || index < self.read => Some((offset, first)),
+			_ if index < self.read => Some((section.logical_data_offset(), 0)),
+			_ => None,
+		};
+		if let Some((offset, first)) = restart {
+			self.restart(offset, first)?;
+		}
+
+		let mut point = Point::default();
+		while self.read < index {
+			self.read_properties(&mut point)?;
+			self.read += 1;
+		}
+		Ok(())
+	}
+
+	/// Sets the number of pages that are touched ahead of the decoding position.
+	///
+	/// Reading ahead reduces stalls when the data is loaded from slow storage like spinning disks or network filesystems.
+	/// Defaults to [`DEFAULT_PREFETCH_PAGES`], a value of zero disables the read-ahead.
+	pub fn with_prefetch(mut self, pages: usize) -> Self {
+		self.prefetcher.set_pages(pages);
+		self
+	}
+
+	/// Reads up to `n` points and stops decoding afterwards.
+	///
+	/// Useful to inspect the first points of a large point cloud.
+	/// The remaining points can still be read with the iterator.
+	pub fn take_n(&mut self, n: usize) -> Result<Vec<Point>, Error> {
+		let peeked = self.peeked.as_ref().is_some_and(|p| p.is_some()) as u64;
+		let remaining = (self.pc.records - self.read + peeked).min(n as u64) as usize;
+		let mut points = Vec::with_capacity(remaining);
+		for _ in 0..remaining {
+			match self.next() {
+				Some(point) => points.push(point?),
+				None => break,
+			}
+		}
+		Ok(points)
+	}
+
+	/// Returns a reference to the next point without advancing the iterator.
+	///
+	/// The point is decoded and buffered until it is returned by the next call of the iterator.
+	pub fn peek(&mut self) -> Option<&Result<Point, Error>> {
+		if self.peeked.is_none() {
+			let next = self.decode_next();
+			self.peeked = Some(next);
+		}
+		self.peeked.as_ref().and_then(Option::as_ref)
+	}
+
+	fn decode_next(&mut self) -> Option<Result<Point, Error>> {
+		loop {
+			let mut p = Point::default();
+			match self.read_into(&mut p)? {
+				Ok(()) if self.filter.as_ref().is_some_and(|keep| !keep(&p)) => continue,
+				Ok(()) => return Some(Ok(p)),
+				Err(err) => return Some(Err(err)),
+			}
+		}
+	}
+
+	/// Reads all remaining points and measures the throughput of the decoding.
+	///
+	/// The byte count covers the whole binary section of the point cloud.
+	pub fn read_all_timed(mut self) -> Result<(Vec<Point>, ReadStats), Error> {
+		let start = std::time::Instant::now();
+		let section = SectionHeader::read(self.pc.file_offset, self.mmap)?;
+		let points = self.by_ref().collect::<Result<Vec<_>, _>>()?;
+		let stats = ReadStats {
+			points:   points.len() as u64,
+			bytes:    section.section_length,
+			duration: start.elapsed(),
+		};
+		Ok((points, stats))
+	}
+
+	/// Returns the points that were outside of the declared Cartesian bounds so far.
+	///
+	/// Returns `None` if the bounds check is not enabled with [`PointCloudReader::with_bounds_check`].
+	pub fn bounds_violations(&self) -> Option<&BoundsViolations> {
+		self.bounds_check.as_ref().map(|check| check.violations())
+	}
+
+	/// Returns each point together with its row and column index.
+	///
+	/// The indices are decoded alongside the points without building the whole grid,
+	/// which keeps the memory usage low for large organized point clouds.
+	/// Returns an error for point clouds without row and column index records.
+	pub fn organized(self) -> Result<Organized<'a, Point>, Error> {
+		Organized::new(self)
+	}
+
+	/// Groups consecutive points that belong to the same laser pulse of a multi-return sensor.
+	///
+	/// The closure must return the zero-based return index of a point,
+	/// every point with the return index 0 starts a new group.
+	/// This assumes that all returns of a pulse are stored contiguously in the file.
+	pub fn pulses<F>(self, return_index: F) -> Pulses<'a, Point, F>
+	where
+		F: Fn(&Point) -> i64,
+	{
+		Pulses::new(self, return_index)
+	}
+}
+
+impl<'a, Point> PointCloudReader<'a, Point>
+where
+	Point: Default + CartesianPoint,
+{
+	/// Applies a custom transformation to the Cartesian coordinates of each point.
+	///
+	/// The transformation is called after all properties of a point are decoded,
+	/// spherical coordinates are already converted to Cartesian coordinates at this point.
+	/// It runs after the bounds check and after clamping with [`PointCloudReader::with_clamp_to_bounds`].
+	/// Multiple transformations are applied in the order they were added,
+	/// for readers from [`crate::Reader::pointcloud_transformed`] the pose is applied before any added transformation.
+	pub fn with_transform(mut self, transform: impl Fn([f64; 3]) -> [f64; 3] + 'a) -> Self {
+		self.transforms
+			.push(Box::new(move |p: &mut Point| p.set_position(transform(p.position()))));
+		self
+	}
+
+	/// Clamps the coordinates of each point into the declared Cartesian bounds of the point cloud.
+	///
+	/// Salvages files where single corrupt values produce coordinates far outside of the bounds.
+	/// The clamping is applied before all other transformations, regardless of the order they were added.
+	/// Point clouds without Cartesian bounds are not modified, partial bounds clamp only the axes with available limits.
+	pub fn with_clamp_to_bounds(mut self) -> Self {
+		if let Some(bounds) = self.pc.cartesian_bounds.clone() {
+			self.transforms.insert(
+				0,
+				Box::new(move |p: &mut Point| p.set_position(bounds.clamp(p.position()))),
+			);
+		}
+		self
+	}
+
+	/// Compares the coordinates of each point with the declared Cartesian bounds of the point cloud.
+	///
+	/// Points are checked before any transformation is applied.
+	/// The bounds check does not stop the decoding, the offending points are reported by
+	/// [`PointCloudReader::bounds_violations`].
+	/// Point clouds without Cartesian bounds never report any violations, partial bounds check only the available limits.
+	pub fn with_bounds_check(mut self) -> Self {
+		let bounds = self.pc.cartesian_bounds.clone().unwrap_or_default();
+		self.bounds_check = Some(BoundsCheck::new(bounds, Point::position));
+		self
+	}
+}
+
+impl<'a, Point> Iterator for PointCloudReader<'a, Point>
+where
+	Point: Default,
+{
+	type Item = Result<Point, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		match self.peeked.take() {
+			Some(peeked) => peeked,
+			None => self.decode_next(),
+		}
 	}
 }
@@ -0,0 +1,81 @@
+use super::IntLoader;
+use super::PointCloudReader;
+use super::PropertyLoader;
+use super::SectionHeader;
+use crate::Error;
+use crate::RecordDataType;
+use crate::RecordName;
+
+/// Iterate over points together with their row and column index.
+///
+/// Created by [`PointCloudReader::organized`].
+pub struct Organized<'a, Point>
+where
+	Point: Default,
+{
+	reader: PointCloudReader<'a, Point>,
+	row:    IntLoader,
+	column: IntLoader,
+	loaded: u64,
+}
+
+impl<'a, Point> Organized<'a, Point>
+where
+	Point: Default,
+{
+	pub(crate) fn new(reader: PointCloudReader<'a, Point>) -> Result<Self, Error> {
+		let section = SectionHeader::read(reader.pc.file_offset, reader.mmap)?;
+		let offset = section.logical_data_offset();
+		let loader = |name: RecordName| {
+			let Some(index) = reader.pc.prototype.iter().position(|r| r.name == name) else {
+				return Error::Invalid(format!("Point cloud is not organized, it has no {name:?} record")).throw();
+			};
+			match reader.pc.prototype[index].data_type {
+				RecordDataType::Integer { min, max } | RecordDataType::ScaledInteger { min, max, .. } => {
+					IntLoader::new(offset, index, min, max, reader.mmap)
+				},
+				data_type => Error::Invalid(format!("Cannot read {data_type:?} record as {name:?}")).throw(),
+			}
+		};
+		let row = loader(RecordName::RowIndex)?;
+		let column = loader(RecordName::ColumnIndex)?;
+		let mut organized = Self { row, column, loaded: 0, reader };
+
+		// Skip the indices of points that were already returned by the reader
+		let peeked = organized.reader.peeked.as_ref().is_some_and(|p| p.is_some()) as u64;
+		for _ in 0..(organized.reader.read - peeked) {
+			organized.load_indices()?;
+		}
+		Ok(organized)
+	}
+
+	fn load_indices(&mut self) -> Result<(i64, i64), Error> {
+		let at_end = self.loaded + 1 >= self.reader.pc.records;
+		self.loaded += 1;
+		let row = self.row.load(self.reader.mmap, at_end)?;
+		let column = self.column.load(self.reader.mmap, at_end)?;
+		Ok((row, column))
+	}
+}
+
+impl<'a, Point> Iterator for Organized<'a, Point>
+where
+	Point: Default,
+{
+	type Item = Result<(i64, i64, Point), Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let point = match self.reader.next()? {
+			Ok(point) => point,
+			Err(err) => return Some(Err(err)),
+		};
+		// Filtered points are decoded by the reader without being returned, their indices are skipped
+		loop {
+			match self.load_indices() {
+				Ok(_) if self.loaded < self.reader.read => continue,
+				Ok((row, column)) => return Some(Ok((row, column, point))),
+				Err(err) => return Some(Err(err)),
+			}
+		}
+	}
+}
@@ -0,0 +1,315 @@
+use super::index::read_index;
+use super::loader::index_mmap;
+use super::SectionHeader;
+use crate::error::INTERNAL_ERROR;
+use crate::mmap_paged;
+use crate::mmap_paged::to_physical;
+use crate::pc_writer::points_per_packet;
+use crate::Error;
+use crate::PointCloud;
+use crate::RawValues;
+use crate::Record;
+use crate::RecordDataType;
+use crate::RecordValue;
+
+const DATA_PACKET_HEADER_SIZE: usize = 6;
+
+/// Type of a packet inside a compressed vector section.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PacketType {
+	/// Index packet with entries pointing to data packets.
+	Index,
+	/// Data packet with the actual bytestreams of the point records.
+	Data,
+	/// Empty packet used as padding.
+	Empty,
+}
+
+/// Describes a single packet of a compressed vector section.
+#[derive(Clone, Debug)]
+pub struct PacketInfo {
+	/// Logical (without CRC bytes) offset of the packet inside the file.
+	pub offset:           u64,
+	/// Type of the packet.
+	pub packet_type:      PacketType,
+	/// Logical length of the packet in bytes, including its header.
+	pub length:           u64,
+	/// Length in bytes of each bytestream stored in the packet.
+	/// The number of entries is the bytestream count of the packet.
+	/// Only data packets contain bytestreams, the list is empty for all other packet types.
+	pub bytestream_sizes: Vec<u64>,
+}
+
+impl PacketInfo {
+	/// Returns the number of bytestreams stored in the packet.
+	pub fn bytestream_count(&self) -> usize {
+		self.bytestream_sizes.len()
+	}
+}
+
+/// Iterate over the headers of all packets of a compressed vector section.
+pub struct PacketIterator<'a> {
+	mmap:   &'a memmap2::Mmap,
+	offset: usize,
+	end:    usize,
+}
+
+impl<'a> PacketIterator<'a> {
+	pub(crate) fn new(section: &SectionHeader, mmap: &'a memmap2::Mmap) -> Self {
+		Self {
+			mmap,
+			offset: section.logical_data_offset(),
+			end: section.logical_end(),
+		}
+	}
+
+	fn read_packet(&self) -> Result<PacketInfo, Error> {
+		read_packet_info(self.mmap, self.offset)
+	}
+}
+
+fn read_packet_info(mmap: &memmap2::Mmap, offset: usize) -> Result<PacketInfo, Error> {
+	if to_physical(offset + DATA_PACKET_HEADER_SIZE - 1) >= mmap.len() {
+		return Error::Invalid("Packet header is located outside of the file".into()).throw();
+	}
+	let header = index_mmap(mmap, offset, offset + 4);
+	let packet_type = match header[0] {
+		0 => PacketType::Index,
+		1 => PacketType::Data,
+		2 => PacketType::Empty,
+		id => return Error::Invalid(format!("Found unknown packet type ({id})")).throw(),
+	};
+	let length = u16::from_le_bytes(header[2..4].try_into().expect(INTERNAL_ERROR)) as usize + 1;
+	if to_physical(offset + length - 1) >= mmap.len() {
+		return Error::Invalid("Packet extends past the end of the file".into()).throw();
+	}
+
+	let mut bytestream_sizes = Vec::new();
+	if packet_type == PacketType::Data {
+		let data = index_mmap(mmap, offset + 4, offset + 6);
+		let bytestream_count = u16::from_le_bytes(data.try_into().expect(INTERNAL_ERROR)) as usize;
+		bytestream_sizes.reserve(bytestream_count);
+		for index in 0..bytestream_count {
+			let start = offset + DATA_PACKET_HEADER_SIZE + index * 2;
+			let data = index_mmap(mmap, start, start + 2);
+			let size = u16::from_le_bytes(data.try_into().expect(INTERNAL_ERROR));
+			bytestream_sizes.push(size as u64);
+		}
+	}
+
+	Ok(PacketInfo {
+		offset: offset as u64,
+		packet_type,
+		length: length as u64,
+		bytestream_sizes,
+	})
+}
+
+impl<'a> Iterator for PacketIterator<'a> {
+	type Item = Result<PacketInfo, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.offset >= self.end {
+			return None;
+		}
+		match self.read_packet() {
+			Ok(packet) => {
+				self.offset += packet.length as usize;
+				Some(Ok(packet))
+			},
+			Err(err) => {
+				self.offset = self.end;
+				Some(Err(err))
+			},
+		}
+	}
+}
+
+/// Counts the number of complete records that are stored in the data packets of a section.
+///
+/// Returns `None` for prototypes with only constant records, which store no data to count.
+pub(crate) fn stored_records(
+	section: &SectionHeader,
+	prototype: &[Record],
+	mmap: &memmap2::Mmap,
+) -> Result<Option<u64>, Error> {
+	let mut bytes = vec![0_u64; prototype.len()];
+	for packet in PacketIterator::new(section, mmap) {
+		let packet = packet?;
+		for (sum, size) in bytes.iter_mut().zip(packet.bytestream_sizes) {
+			*sum += size;
+		}
+	}
+	Ok(records_in_bytestreams(prototype, &bytes))
+}
+
+/// Counts the number of complete records in bytestreams with the given sizes.
+///
+/// Returns `None` for prototypes with only constant records, which store no data to count.
+pub(crate) fn records_in_bytestreams(prototype: &[Record], sizes: &[u64]) -> Option<u64> {
+	prototype
+		.iter()
+		.zip(sizes)
+		.filter_map(|(record, bytes)| (bytes * 8).checked_div(record.data_type.bit_width() as u64))
+		.min()
+}
+
+/// Returns true if all bytestreams with the given sizes end exactly after the value of the given record.
+///
+/// Decoding can only continue independently after such a boundary,
+/// otherwise bit-packed values span the end of the bytestreams.
+pub(crate) fn values_aligned(prototype: &[Record], sizes: &[u64], records: u64) -> bool {
+	prototype
+		.iter()
+		.zip(sizes)
+		.all(|(record, bytes)| bytes * 8 == records * record.data_type.bit_width() as u64)
+}
+
+/// Decodes the raw values of all points stored in a single data packet of a point cloud.
+///
+/// The offset is the logical offset of the packet, which excludes the four CRC bytes at the end of each
+/// 1024 byte page. Physical file offsets are converted with `physical - (physical / 1024) * 4`,
+/// the offsets reported by [`PacketInfo`] are already logical.
+///
+/// The bytestreams of the packet are mapped to the records of the prototype by their index.
+/// Values of bit-packed integers must not span multiple packets,
+/// which is the case for all byte-aligned data types and for writers that flush their bit buffer per packet.
+/// The headers of the preceding packets are read to verify this, an error is returned for packets
+/// that continue values of the previous packet.
+/// Packets of prototypes with only constant records contain no data,
+/// their number of points is taken from the index or from the record count if the section has a single data packet.
+pub fn decode_packet(mmap: &memmap2::Mmap, pc: &PointCloud, logical_offset: usize) -> Result<Vec<RawValues>, Error> {
+	let prototype = &pc.prototype;
+	let section = SectionHeader::read(pc.file_offset, mmap)?;
+	let mut preceding = vec![0_u64; prototype.len()];
+	let mut data_packets = 0;
+	let mut packet = None;
+	for info in PacketIterator::new(&section, mmap) {
+		let info = info?;
+		if info.packet_type != PacketType::Data {
+			continue;
+		}
+		data_packets += 1;
+		if info.offset == logical_offset as u64 {
+			packet = Some(info);
+		} else if packet.is_none() {
+			for (sum, size) in preceding.iter_mut().zip(&info.bytestream_sizes) {
+				*sum += size;
+			}
+		}
+	}
+	let Some(packet) = packet else {
+		return Error::Invalid(format!(
+			"Section contains no data packet at logical offset {logical_offset}"
+		))
+		.throw();
+	};
+	if packet.bytestream_count() != prototype.len() {
+		return Error::Invalid(format!(
+			"Data packet contains {} bytestreams, but the prototype has {} records",
+			packet.bytestream_count(),
+			prototype.len()
+		))
+		.throw();
+	}
+	if let Some(first) = records_in_bytestreams(prototype, &preceding) {
+		if !values_aligned(prototype, &preceding, first) {
+			return Error::Invalid(format!(
+				"Data packet at logical offset {logical_offset} continues values of the previous packet"
+			))
+			.throw();
+		}
+	}
+	let points = match records_in_bytestreams(prototype, &packet.bytestream_sizes) {
+		Some(points) => points,
+		None => constant_packet_records(&section, pc, logical_offset, data_packets, mmap)?,
+	} as usize;
+
+	let mut start = logical_offset + DATA_PACKET_HEADER_SIZE + packet.bytestream_count() * 2;
+	let mut streams = Vec::with_capacity(prototype.len());
+	for size in &packet.bytestream_sizes {
+		let mut stream = vec![0_u8; *size as usize];
+		mmap_paged::read(&mut stream, to_physical(start), mmap);
+		start += *size as usize;
+		streams.push(stream);
+	}
+
+	let mut values = vec![RawValues::with_capacity(prototype.len()); points];
+	for (record, stream) in prototype.iter().zip(&streams) {
+		for (index, point) in values.iter_mut().enumerate() {
+			point.push(decode_value(&record.data_type, stream, index));
+		}
+	}
+	Ok(values)
+}
+
+/// Returns the number of points in a data packet of a prototype with only constant records.
+///
+/// The count is limited by the points a data packet of the maximum size can hold,
+/// where each point occupies at least one bit.
+fn constant_packet_records(
+	section: &SectionHeader,
+	pc: &PointCloud,
+	logical_offset: usize,
+	data_packets: usize,
+	mmap: &memmap2::Mmap,
+) -> Result<u64, Error> {
+	let mut records = None;
+	if section.index_offset != 0 {
+		let entries = read_index(section.index_offset, mmap)?;
+		let physical_offset = to_physical(logical_offset) as u64;
+		if let Some(position) = entries.iter().position(|e| e.physical_offset == physical_offset) {
+			let end = entries.get(position + 1).map_or(pc.records, |e| e.record_number);
+			records = Some(end.saturating_sub(entries[position].record_number));
+		}
+	}
+	if records.is_none() && data_packets == 1 {
+		records = Some(pc.records);
+	}
+	let Some(records) = records else {
+		return Error::Invalid(format!(
+			"Cannot determine the number of points in the data packet at logical offset {logical_offset}, \
+			the prototype contains only constant records"
+		))
+		.throw();
+	};
+	let capacity = points_per_packet(&pc.prototype)? as u64;
+	if records > capacity {
+		return Error::Invalid(format!(
+			"Data packet at logical offset {logical_offset} would contain {records} points, \
+			but a data packet can hold at most {capacity} points"
+		))
+		.throw();
+	}
+	Ok(records)
+}
+
+fn decode_value(data_type: &RecordDataType, stream: &[u8], index: usize) -> RecordValue {
+	match *data_type {
+		RecordDataType::Single { .. } => RecordValue::Single(f32::from_le_bytes(
+			stream[index * 4..(index + 1) * 4].try_into().expect(INTERNAL_ERROR),
+		)),
+		RecordDataType::Double { .. } => RecordValue::Double(f64::from_le_bytes(
+			stream[index * 8..(index + 1) * 8].try_into().expect(INTERNAL_ERROR),
+		)),
+		RecordDataType::ScaledInteger { min, .. } => {
+			RecordValue::ScaledInteger(decode_int(data_type, min, stream, index))
+		},
+		RecordDataType::Integer { min, .. } => RecordValue::Integer(decode_int(data_type, min, stream, index)),
+	}
+}
+
+fn decode_int(data_type: &RecordDataType, min: i64, stream: &[u8], index: usize) -> i64 {
+	let bits = data_type.bit_width() as usize;
+	if bits == 0 {
+		return min;
+	}
+	let bit_start = index * bits;
+	let byte_start = bit_start / 8;
+	let byte_end = (bit_start + bits).div_ceil(8);
+	let mut tmp = [0_u8; 16];
+	tmp[..byte_end - byte_start].copy_from_slice(&stream[byte_start..byte_end]);
+	let raw =
+		(u128::from_le_bytes(tmp) >> (bit_start % 8)) as u64 & u64::MAX.checked_shr(64 - bits as u32).unwrap_or(0);
+	min.wrapping_add(raw as i64)
+}
@@ -0,0 +1,85 @@
+use super::packet::records_in_bytestreams;
+use super::packet::values_aligned;
+use super::point_reader;
+use super::PacketIterator;
+use super::PacketType;
+use super::Point;
+use super::PointOptions;
+use super::SectionHeader;
+use crate::Error;
+use crate::PointCloud;
+use crate::Record;
+use rayon::iter::Either;
+use rayon::prelude::*;
+
+/// Minimum number of points decoded by a single task, smaller ranges are merged with the following packets.
+const MIN_TASK_POINTS: u64 = 1 << 16;
+
+/// Creates a parallel iterator over the built-in point type.
+///
+/// Every task decodes a disjoint range of data packets with its own point reader.
+pub(crate) fn parallel_points<'a>(
+	pc: &PointCloud,
+	mmap: &'a memmap2::Mmap,
+) -> Result<impl ParallelIterator<Item = Result<Point, Error>> + 'a, Error> {
+	let section = SectionHeader::read(pc.file_offset, mmap)?;
+	let mut tasks = Vec::new();
+	if pc.records > 0 {
+		let mut start = (section.logical_data_offset(), 0);
+		for (offset, first) in packet_boundaries(&section, &pc.prototype, mmap)? {
+			if first >= pc.records {
+				break;
+			}
+			if first - start.1 >= MIN_TASK_POINTS {
+				tasks.push((start.0, start.1, first));
+				start = (offset, first);
+			}
+		}
+		tasks.push((start.0, start.1, pc.records));
+	}
+
+	let pc = pc.clone();
+	Ok(tasks.into_par_iter().flat_map_iter(move |(offset, first, end)| {
+		let reader = point_reader(&pc, &PointOptions::default(), mmap).and_then(|mut reader| {
+			if first > 0 {
+				reader.restart(offset, first)?;
+			}
+			Ok(reader)
+		});
+		match reader {
+			Ok(reader) => Either::Left(reader.take((end - first) as usize)),
+			Err(err) => Either::Right(std::iter::once(Err(err))),
+		}
+	}))
+}
+
+/// Returns the logical offsets and first records of all data packets that start at a value boundary in all bytestreams.
+///
+/// Decoding can start at any of these packets, independent of the preceding packets.
+/// Only the packet headers are read, the first packet is always included.
+fn packet_boundaries(
+	section: &SectionHeader,
+	prototype: &[Record],
+	mmap: &memmap2::Mmap,
+) -> Result<Vec<(usize, u64)>, Error> {
+	let mut boundaries = Vec::new();
+	let mut bytes = vec![0_u64; prototype.len()];
+	for packet in PacketIterator::new(section, mmap) {
+		let packet = packet?;
+		if packet.packet_type != PacketType::Data {
+			continue;
+		}
+		// Prototypes with only constant records have no data to count the records of the previous packets
+		let records = records_in_bytestreams(prototype, &bytes);
+		let aligned = records.filter(|records| values_aligned(prototype, &bytes, *records));
+		if boundaries.is_empty() {
+			boundaries.push((packet.offset as usize, 0));
+		} else if let Some(records) = aligned {
+			boundaries.push((packet.offset as usize, records));
+		}
+		for (sum, size) in bytes.iter_mut().zip(&packet.bytestream_sizes) {
+			*sum += size;
+		}
+	}
+	Ok(boundaries)
+}
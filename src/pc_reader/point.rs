@@ -0,0 +1,608 @@
+use super::f64_reader;
+use super::i64_reader;
+use super::unit_f32_reader;
+use super::CartesianPoint;
+use super::ColorSpace;
+use super::GenPropertyReader;
+use super::IntLoader;
+use super::PointCloudReader;
+use super::PropertyReader;
+use super::PropertySaver;
+use super::ScaledIntConverter;
+use super::UnitConverter;
+use crate::spherical_to_cartesian;
+use crate::Error;
+use crate::PointCloud;
+use crate::Record;
+use crate::RecordDataType;
+use crate::RecordName;
+
+/// Point with the common attributes of E57 files, read with [`crate::Reader::points`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Point {
+	/// Cartesian coordinates.
+	pub position:          [f64; 3],
+	/// Unscaled integer coordinates if all Cartesian coordinates are stored as scaled integers.
+	///
+	/// Contains the values as stored in the file, transformations are only applied to the position.
+	/// Allows lossless conversions without accumulating floating point errors.
+	pub scaled_position:   Option<[i64; 3]>,
+	/// Cartesian invalid state, 0 (valid), 1 (only the direction is meaningful) or 2 (invalid).
+	///
+	/// Contains the spherical invalid state if the position was converted from spherical coordinates.
+	pub cartesian_invalid: i64,
+	/// Range, azimuth and elevation as stored in the file, `None` if the point cloud has no spherical coordinates.
+	///
+	/// The angles are in radians, scaled integers are multiplied with their scale.
+	/// Points without a return, marked with a zero range and the spherical invalid state 1, have no spherical coordinates.
+	pub spherical:         Option<[f64; 3]>,
+	/// Spherical invalid state, 0 (valid), 1 (only the direction is meaningful) or 2 (invalid).
+	pub spherical_invalid: i64,
+	/// Intensity normalized to `0.0..=1.0`, `None` if the point cloud has no intensities or the value is invalid.
+	pub intensity:         Option<f32>,
+	/// Row index in the scan grid, `None` if the point cloud has no row indices.
+	///
+	/// Available for points with any Cartesian invalid state to rebuild the whole grid.
+	pub row:               Option<i64>,
+	/// Column index in the scan grid, `None` if the point cloud has no column indices.
+	pub column:            Option<i64>,
+	/// Red, green and blue color normalized to `0.0..=1.0`, `None` if the point cloud has no colors or the value is invalid.
+	///
+	/// Colors are sRGB unless a different color space is selected with [`PointOptions::color_space`].
+	pub color:             Option<[f32; 3]>,
+	/// Red, green and blue color as stored in the file, see [`PointCloudReader::with_color_raw`].
+	///
+	/// Keeps the full precision of colors with more than 8 bits, for example from 12-bit color records.
+	/// `None` if the option is not enabled, the point cloud has no integer colors or the value is invalid.
+	pub color_raw:         Option<[i64; 3]>,
+	/// Time stamp in seconds, `None` if the point cloud has no time stamps or the value is invalid.
+	///
+	/// Relative to the acquisition start of the point cloud, see [`crate::PointCloud::acquisition_start`].
+	pub timestamp:         Option<f64>,
+	/// Total number of returns of the pulse for multi-return sensors.
+	pub return_count:      Option<i64>,
+	/// Zero-based index of the return within its pulse for multi-return sensors.
+	pub return_index:      Option<i64>,
+	/// Standard deviation of the X, Y and Z coordinate in meters, `None` if the point cloud has no uncertainty records.
+	///
+	/// Read from the `pointStdevX/Y/Z` or `stdevX/Y/Z` extension records, missing axes are zero.
+	pub stdev:             Option<[f32; 3]>,
+
+	color_int:         Option<[i64; 3]>,
+	transparent:       bool,
+	no_return:         bool,
+	intensity_invalid: bool,
+	color_invalid:     bool,
+	timestamp_invalid: bool,
+}
+
+/// Handling of points with an invalid intensity, color or time stamp, see [`PointCloudReader::with_invalid_policy`].
+///
+/// Spherical points without a return, marked with a zero range and the spherical invalid state 1,
+/// are handled like points with an invalid attribute.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InvalidPolicy {
+	/// Keeps the point and sets only the invalid attribute to `None`.
+	#[default]
+	NullAttribute,
+	/// Skips the whole point if any of its attributes is invalid.
+	DropPoint,
+	/// Like [`InvalidPolicy::NullAttribute`], but invalid colors are set to black with an alpha of 0.
+	///
+	/// Renderers can suppress these points with the alpha channel of [`Point::rgba`].
+	Transparent,
+}
+
+/// Options for decoding the built-in point type, see [`crate::Reader::points_with_options`].
+///
+/// Normalization ranges are used with the precedence override, then limits of the point cloud, then record range.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct PointOptions {
+	/// Minimum and maximum for normalizing intensities, replaces the intensity limits and the record range.
+	pub intensity_range: Option<(f64, f64)>,
+	/// Minimum and maximum for normalizing all color channels, replaces the record range.
+	pub color_range:     Option<(f64, f64)>,
+	/// Color space of the normalized colors, the colors as stored are assumed to be sRGB.
+	pub color_space:     ColorSpace,
+}
+
+impl PointOptions {
+	/// Normalizes intensities with the given range, useful for files with wrong or missing limits.
+	pub fn override_intensity_range(mut self, min: f64, max: f64) -> Self {
+		self.intensity_range = Some((min, max));
+		self
+	}
+
+	/// Normalizes colors with the given range, useful for files with a wrong color record range.
+	pub fn override_color_range(mut self, min: f64, max: f64) -> Self {
+		self.color_range = Some((min, max));
+		self
+	}
+
+	/// Converts the normalized colors into the given color space, for example linear color for rendering.
+	///
+	/// Raw colors are not converted, see [`PointCloudReader::with_color_raw`].
+	pub fn color_space(mut self, color_space: ColorSpace) -> Self {
+		self.color_space = color_space;
+		self
+	}
+}
+
+impl Point {
+	/// Returns the color with the intensity as alpha channel, for example as uniform input for shaders.
+	///
+	/// Points without color use the intensity for all channels, points without intensity have an alpha of 1.0.
+	/// Points with an invalid color have an alpha of 0 with [`InvalidPolicy::Transparent`].
+	pub fn rgba(&self) -> [f32; 4] {
+		if self.transparent {
+			return [0.0; 4];
+		}
+		let alpha = self.intensity.unwrap_or(1.0);
+		let [r, g, b] = self.color.unwrap_or([alpha; 3]);
+		[r, g, b, alpha]
+	}
+}
+
+impl CartesianPoint for Point {
+	fn position(&self) -> [f64; 3] {
+		self.position
+	}
+
+	fn set_position(&mut self, position: [f64; 3]) {
+		self.position = position;
+	}
+}
+
+struct SaveX;
+impl PropertySaver<Point, f64> for SaveX {
+	fn save(point: &mut Point, value: f64) {
+		point.position[0] = value;
+	}
+}
+
+struct SaveY;
+impl PropertySaver<Point, f64> for SaveY {
+	fn save(point: &mut Point, value: f64) {
+		point.position[1] = value;
+	}
+}
+
+struct SaveZ;
+impl PropertySaver<Point, f64> for SaveZ {
+	fn save(point: &mut Point, value: f64) {
+		point.position[2] = value;
+	}
+}
+
+struct SaveScaledX;
+impl PropertySaver<Point, (f64, i64)> for SaveScaledX {
+	fn save(point: &mut Point, (value, raw): (f64, i64)) {
+		point.position[0] = value;
+		point.scaled_position.get_or_insert([0; 3])[0] = raw;
+	}
+}
+
+struct SaveScaledY;
+impl PropertySaver<Point, (f64, i64)> for SaveScaledY {
+	fn save(point: &mut Point, (value, raw): (f64, i64)) {
+		point.position[1] = value;
+		point.scaled_position.get_or_insert([0; 3])[1] = raw;
+	}
+}
+
+struct SaveScaledZ;
+impl PropertySaver<Point, (f64, i64)> for SaveScaledZ {
+	fn save(point: &mut Point, (value, raw): (f64, i64)) {
+		point.position[2] = value;
+		point.scaled_position.get_or_insert([0; 3])[2] = raw;
+	}
+}
+
+struct SaveRange;
+impl PropertySaver<Point, f64> for SaveRange {
+	fn save(point: &mut Point, value: f64) {
+		point.spherical.get_or_insert([0.0; 3])[0] = value;
+	}
+}
+
+struct SaveAzimuth;
+impl PropertySaver<Point, f64> for SaveAzimuth {
+	fn save(point: &mut Point, value: f64) {
+		point.spherical.get_or_insert([0.0; 3])[1] = value;
+	}
+}
+
+struct SaveElevation;
+impl PropertySaver<Point, f64> for SaveElevation {
+	fn save(point: &mut Point, value: f64) {
+		point.spherical.get_or_insert([0.0; 3])[2] = value;
+	}
+}
+
+struct SaveCartesianInvalid;
+impl PropertySaver<Point, i64> for SaveCartesianInvalid {
+	fn save(point: &mut Point, value: i64) {
+		point.cartesian_invalid = value;
+	}
+}
+
+struct SaveSphericalInvalid;
+impl PropertySaver<Point, i64> for SaveSphericalInvalid {
+	fn save(point: &mut Point, value: i64) {
+		point.spherical_invalid = value;
+	}
+}
+
+struct SaveIntensity;
+impl PropertySaver<Point, f32> for SaveIntensity {
+	fn save(point: &mut Point, value: f32) {
+		point.intensity = Some(value);
+	}
+}
+
+struct SaveRow;
+impl PropertySaver<Point, i64> for SaveRow {
+	fn save(point: &mut Point, value: i64) {
+		point.row = Some(value);
+	}
+}
+
+struct SaveColumn;
+impl PropertySaver<Point, i64> for SaveColumn {
+	fn save(point: &mut Point, value: i64) {
+		point.column = Some(value);
+	}
+}
+
+struct SaveRed;
+impl PropertySaver<Point, f32> for SaveRed {
+	fn save(point: &mut Point, value: f32) {
+		point.color.get_or_insert([0.0; 3])[0] = value;
+	}
+}
+
+impl PropertySaver<Point, (f32, i64)> for SaveRed {
+	fn save(point: &mut Point, (value, raw): (f32, i64)) {
+		point.color.get_or_insert([0.0; 3])[0] = value;
+		point.color_int.get_or_insert([0; 3])[0] = raw;
+	}
+}
+
+struct SaveGreen;
+impl PropertySaver<Point, f32> for SaveGreen {
+	fn save(point: &mut Point, value: f32) {
+		point.color.get_or_insert([0.0; 3])[1] = value;
+	}
+}
+
+impl PropertySaver<Point, (f32, i64)> for SaveGreen {
+	fn save(point: &mut Point, (value, raw): (f32, i64)) {
+		point.color.get_or_insert([0.0; 3])[1] = value;
+		point.color_int.get_or_insert([0; 3])[1] = raw;
+	}
+}
+
+struct SaveBlue;
+impl PropertySaver<Point, f32> for SaveBlue {
+	fn save(point: &mut Point, value: f32) {
+		point.color.get_or_insert([0.0; 3])[2] = value;
+	}
+}
+
+impl PropertySaver<Point, (f32, i64)> for SaveBlue {
+	fn save(point: &mut Point, (value, raw): (f32, i64)) {
+		point.color.get_or_insert([0.0; 3])[2] = value;
+		point.color_int.get_or_insert([0; 3])[2] = raw;
+	}
+}
+
+struct SaveColorInvalid;
+impl PropertySaver<Point, i64> for SaveColorInvalid {
+	fn save(point: &mut Point, value: i64) {
+		point.color_invalid = value != 0;
+	}
+}
+
+struct SaveReturnCount;
+impl PropertySaver<Point, i64> for SaveReturnCount {
+	fn save(point: &mut Point, value: i64) {
+		point.return_count = Some(value);
+	}
+}
+
+struct SaveReturnIndex;
+impl PropertySaver<Point, i64> for SaveReturnIndex {
+	fn save(point: &mut Point, value: i64) {
+		point.return_index = Some(value);
+	}
+}
+
+struct SaveIntensityInvalid;
+impl PropertySaver<Point, i64> for SaveIntensityInvalid {
+	fn save(point: &mut Point, value: i64) {
+		point.intensity_invalid = value != 0;
+	}
+}
+
+struct SaveTimestamp;
+impl PropertySaver<Point, f64> for SaveTimestamp {
+	fn save(point: &mut Point, value: f64) {
+		point.timestamp = Some(value);
+	}
+}
+
+struct SaveTimestampInvalid;
+impl PropertySaver<Point, i64> for SaveTimestampInvalid {
+	fn save(point: &mut Point, value: i64) {
+		point.timestamp_invalid = value != 0;
+	}
+}
+
+struct SaveStdevX;
+impl PropertySaver<Point, f64> for SaveStdevX {
+	fn save(point: &mut Point, value: f64) {
+		point.stdev.get_or_insert([0.0; 3])[0] = value as f32;
+	}
+}
+
+struct SaveStdevY;
+impl PropertySaver<Point, f64> for SaveStdevY {
+	fn save(point: &mut Point, value: f64) {
+		point.stdev.get_or_insert([0.0; 3])[1] = value as f32;
+	}
+}
+
+struct SaveStdevZ;
+impl PropertySaver<Point, f64> for SaveStdevZ {
+	fn save(point: &mut Point, value: f64) {
+		point.stdev.get_or_insert([0.0; 3])[2] = value as f32;
+	}
+}
+
+/// Returns true if the record is decoded into the built-in point type, see [`PointCloud::unsupported_by_point`].
+///
+/// Invalid states, indices and return numbers are only read from integer records, floating point variants are skipped.
+pub(crate) fn supported_by_point(record: &Record) -> bool {
+	let integer = matches!(
+		record.data_type,
+		RecordDataType::Integer { .. } | RecordDataType::ScaledInteger { .. }
+	);
+	integer
+		|| !matches!(
+			record.name,
+			RecordName::CartesianInvalidState
+				| RecordName::SphericalInvalidState
+				| RecordName::IsIntensityInvalid
+				| RecordName::IsColorInvalid
+				| RecordName::IsTimeStampInvalid
+				| RecordName::RowIndex
+				| RecordName::ColumnIndex
+				| RecordName::ReturnCount
+				| RecordName::ReturnIndex
+		)
+}
+
+/// Creates a reader for scaled integers that keeps the unscaled value.
+fn scaled_reader<Saver>(
+	data_type: RecordDataType,
+	offset: usize,
+	index: usize,
+	mmap: &memmap2::Mmap,
+	saver: Saver,
+) -> Result<Box<dyn PropertyReader<Point>>, Error>
+where
+	Saver: PropertySaver<Point, (f64, i64)> + 'static,
+{
+	match data_type {
+		RecordDataType::ScaledInteger { min, max, scale } => Ok(GenPropertyReader::boxed(
+			IntLoader::new(offset, index, min, max, mmap)?,
+			ScaledIntConverter { scale },
+			saver,
+		)),
+		_ => Error::Invalid(format!("Cannot read {data_type:?} record as scaled integer")).throw(),
+	}
+}
+
+/// Creates a reader for colors that keeps the original value of integer records.
+///
+/// The range takes precedence over the minimum and maximum of the record.
+fn color_reader<Saver>(
+	data_type: RecordDataType,
+	range: Option<(f64, f64)>,
+	offset: usize,
+	index: usize,
+	mmap: &memmap2::Mmap,
+	saver: Saver,
+) -> Result<Box<dyn PropertyReader<Point>>, Error>
+where
+	Saver: PropertySaver<Point, f32> + PropertySaver<Point, (f32, i64)> + 'static,
+{
+	match data_type {
+		RecordDataType::ScaledInteger { min, max, .. } | RecordDataType::Integer { min, max } => {
+			let scale = match data_type {
+				RecordDataType::ScaledInteger { scale, .. } => scale,
+				_ => 1.0,
+			};
+			let (low, high) = range.unwrap_or((min as f64 * scale, max as f64 * scale));
+			Ok(GenPropertyReader::<_, _, _, _, i64, (f32, i64)>::boxed(
+				IntLoader::new(offset, index, min, max, mmap)?,
+				UnitConverter { scale, min: low, max: high },
+				saver,
+			))
+		},
+		_ => unit_f32_reader(data_type, unit_limits(data_type, range), offset, index, mmap, saver),
+	}
+}
+
+/// Creates a reader for the built-in point type.
+///
+/// Intensities are normalized with the range of the options, the intensity limits of the point cloud
+/// or the minimum and maximum of the record, in this order.
+/// Colors are normalized with the range of the options or the minimum and maximum of their records,
+/// and converted into the color space of the options.
+/// Spherical coordinates are always decoded into [`Point::spherical`].
+/// Point clouds with only spherical coordinates are also converted to Cartesian coordinates,
+/// points with a negative or non-finite range are marked as invalid.
+/// Points without a return get the unit vector of their direction instead of collapsing to the origin.
+/// Records that are not supported are skipped, see [`PointCloud::unsupported_by_point`].
+pub(crate) fn point_reader<'a>(
+	pc: &PointCloud,
+	options: &PointOptions,
+	mmap: &'a memmap2::Mmap,
+) -> Result<PointCloudReader<'a, Point>, Error> {
+	let limits = options
+		.intensity_range
+		.or_else(|| pc.intensity_limits.as_ref().and_then(|l| l.range()));
+	let colors = options.color_range;
+	let color_space = options.color_space;
+	let scaled = [RecordName::CartesianX, RecordName::CartesianY, RecordName::CartesianZ]
+		.iter()
+		.all(|name| {
+			pc.prototype
+				.iter()
+				.any(|r| r.name == *name && matches!(r.data_type, RecordDataType::ScaledInteger { .. }))
+		});
+	let has = |name: RecordName| pc.prototype.iter().any(|r| r.name == name);
+	let spherical = !has(RecordName::CartesianX) && has(RecordName::SphericalRange);
+	let mut reader = PointCloudReader::new(pc, mmap, |name, data_type, offset, index, mmap| {
+		if !supported_by_point(&Record { name, data_type }) {
+			return Ok(None);
+		}
+		Ok(Some(match name {
+			RecordName::CartesianX if scaled => scaled_reader(data_type, offset, index, mmap, SaveScaledX)?,
+			RecordName::CartesianY if scaled => scaled_reader(data_type, offset, index, mmap, SaveScaledY)?,
+			RecordName::CartesianZ if scaled => scaled_reader(data_type, offset, index, mmap, SaveScaledZ)?,
+			RecordName::CartesianX => f64_reader(data_type, offset, index, mmap, SaveX)?,
+			RecordName::CartesianY => f64_reader(data_type, offset, index, mmap, SaveY)?,
+			RecordName::CartesianZ => f64_reader(data_type, offset, index, mmap, SaveZ)?,
+			RecordName::CartesianInvalidState => i64_reader(data_type, offset, index, mmap, SaveCartesianInvalid)?,
+			RecordName::SphericalRange => f64_reader(data_type, offset, index, mmap, SaveRange)?,
+			RecordName::SphericalAzimuth => f64_reader(data_type, offset, index, mmap, SaveAzimuth)?,
+			RecordName::SphericalElevation => f64_reader(data_type, offset, index, mmap, SaveElevation)?,
+			RecordName::SphericalInvalidState => i64_reader(data_type, offset, index, mmap, SaveSphericalInvalid)?,
+			RecordName::Intensity => unit_f32_reader(
+				data_type,
+				unit_limits(data_type, limits),
+				offset,
+				index,
+				mmap,
+				SaveIntensity,
+			)?,
+			RecordName::IsIntensityInvalid => i64_reader(data_type, offset, index, mmap, SaveIntensityInvalid)?,
+			RecordName::RowIndex => i64_reader(data_type, offset, index, mmap, SaveRow)?,
+			RecordName::ColumnIndex => i64_reader(data_type, offset, index, mmap, SaveColumn)?,
+			RecordName::ColorRed => color_reader(data_type, colors, offset, index, mmap, SaveRed)?,
+			RecordName::ColorGreen => color_reader(data_type, colors, offset, index, mmap, SaveGreen)?,
+			RecordName::ColorBlue => color_reader(data_type, colors, offset, index, mmap, SaveBlue)?,
+			RecordName::IsColorInvalid => i64_reader(data_type, offset, index, mmap, SaveColorInvalid)?,
+			RecordName::TimeStamp => f64_reader(data_type, offset, index, mmap, SaveTimestamp)?,
+			RecordName::IsTimeStampInvalid => i64_reader(data_type, offset, index, mmap, SaveTimestampInvalid)?,
+			RecordName::ReturnCount => i64_reader(data_type, offset, index, mmap, SaveReturnCount)?,
+			RecordName::ReturnIndex => i64_reader(data_type, offset, index, mmap, SaveReturnIndex)?,
+			RecordName::StdevX => f64_reader(data_type, offset, index, mmap, SaveStdevX)?,
+			RecordName::StdevY => f64_reader(data_type, offset, index, mmap, SaveStdevY)?,
+			RecordName::StdevZ => f64_reader(data_type, offset, index, mmap, SaveStdevZ)?,
+		}))
+	})?;
+	reader.transforms.push(Box::new(move |p: &mut Point| {
+		if p.no_return {
+			p.spherical = None;
+		}
+		if p.intensity_invalid {
+			p.intensity = None;
+		}
+		if p.color_invalid {
+			p.color = None;
+		} else if color_space != ColorSpace::Srgb {
+			p.color = p.color.map(|color| color.map(|v| color_space.from_srgb(v)));
+		}
+		if p.timestamp_invalid {
+			p.timestamp = None;
+		}
+	}));
+	if has(RecordName::SphericalRange) {
+		reader.complete = Some(Box::new(move |p: &mut Point| {
+			// A zero range with the invalid state 1 is the sentinel for a beam without a return
+			p.no_return = p.spherical_invalid == 1 && p.spherical.is_some_and(|s| s[0] == 0.0);
+			if !spherical {
+				return;
+			}
+			p.cartesian_invalid = p.spherical_invalid;
+			let coordinates = match p.spherical {
+				Some([_, azimuth, elevation]) if p.no_return => Some([1.0, azimuth, elevation]),
+				coordinates => coordinates,
+			};
+			match coordinates.and_then(spherical_to_cartesian) {
+				Some(position) => p.position = position,
+				None => p.cartesian_invalid = 2,
+			}
+		}));
+	}
+	Ok(reader)
+}
+
+/// Floating point values without any limits are expected to be normalized already.
+fn unit_limits(data_type: RecordDataType, limits: Option<(f64, f64)>) -> Option<(f64, f64)> {
+	match data_type {
+		RecordDataType::Single { min: None, .. }
+		| RecordDataType::Single { max: None, .. }
+		| RecordDataType::Double { min: None, .. }
+		| RecordDataType::Double { max: None, .. } => limits.or(Some((0.0, 1.0))),
+		_ => limits,
+	}
+}
+
+impl PointCloudReader<'_, Point> {
+	/// Selects how points with an invalid intensity, color or time stamp are handled.
+	///
+	/// By default only the invalid attributes are set to `None`.
+	/// The Cartesian invalid state is never used to skip points, it is available in [`Point::cartesian_invalid`].
+	/// Only spherical points without a return are dropped, because their range carries no information.
+	/// Selecting a policy again replaces the previous policy.
+	pub fn with_invalid_policy(mut self, policy: InvalidPolicy) -> Self {
+		self.filter = match policy {
+			InvalidPolicy::NullAttribute | InvalidPolicy::Transparent => None,
+			InvalidPolicy::DropPoint => Some(Box::new(|p: &Point| {
+				!p.no_return && !p.intensity_invalid && !p.color_invalid && !p.timestamp_invalid
+			})),
+		};
+		let colored = self.pc.prototype.iter().any(|r| r.name == RecordName::ColorRed);
+		self.invalid_policy = None;
+		if policy == InvalidPolicy::Transparent && colored {
+			self.invalid_policy = Some(Box::new(|p: &mut Point| {
+				if p.color_invalid {
+					p.color = Some([0.0; 3]);
+					p.transparent = true;
+				}
+			}));
+		}
+		self
+	}
+
+	/// Keeps the original integer colors in [`Point::color_raw`].
+	///
+	/// Useful for colors with more than 8 bits, where the normalized `f32` values lose precision.
+	/// Point clouds with floating point color records are not modified.
+	pub fn with_color_raw(mut self) -> Self {
+		self.transforms.push(Box::new(|p: &mut Point| {
+			p.color_raw = if p.color_invalid { None } else { p.color_int }
+		}));
+		self
+	}
+
+	/// Fills the color of each point with its intensity in all three channels.
+	///
+	/// Gives visualization code a uniform color path for point clouds that have intensities but no colors.
+	/// Point clouds with color records are not modified, points with an invalid intensity keep no color.
+	pub fn with_intensity_as_color(mut self) -> Self {
+		let has = |name: RecordName| self.pc.prototype.iter().any(|r| r.name == name);
+		let colored = has(RecordName::ColorRed) || has(RecordName::ColorGreen) || has(RecordName::ColorBlue);
+		if !colored && has(RecordName::Intensity) {
+			self.transforms
+				.push(Box::new(|p: &mut Point| p.color = p.intensity.map(|i| [i; 3])));
+		}
+		self
+	}
+}
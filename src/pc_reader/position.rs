@@ -0,0 +1,58 @@
+use super::f64_reader;
+use super::i64_reader;
+use super::PropertyReader;
+use super::PropertySaver;
+use crate::Error;
+use crate::RecordDataType;
+use crate::RecordName;
+
+/// Cartesian coordinates with the invalid state of a single point.
+#[derive(Default)]
+pub(crate) struct PositionPoint {
+	pub position: [f64; 3],
+	pub invalid:  bool,
+}
+
+struct SaveX;
+impl PropertySaver<PositionPoint, f64> for SaveX {
+	fn save(point: &mut PositionPoint, value: f64) {
+		point.position[0] = value;
+	}
+}
+
+struct SaveY;
+impl PropertySaver<PositionPoint, f64> for SaveY {
+	fn save(point: &mut PositionPoint, value: f64) {
+		point.position[1] = value;
+	}
+}
+
+struct SaveZ;
+impl PropertySaver<PositionPoint, f64> for SaveZ {
+	fn save(point: &mut PositionPoint, value: f64) {
+		point.position[2] = value;
+	}
+}
+
+struct SaveInvalid;
+impl PropertySaver<PositionPoint, i64> for SaveInvalid {
+	fn save(point: &mut PositionPoint, value: i64) {
+		point.invalid = value != 0;
+	}
+}
+
+pub(crate) fn position_reader(
+	name: RecordName,
+	data_type: RecordDataType,
+	offset: usize,
+	index: usize,
+	mmap: &memmap2::Mmap,
+) -> Result<Option<Box<dyn PropertyReader<PositionPoint>>>, Error> {
+	Ok(Some(match name {
+		RecordName::CartesianX => f64_reader(data_type, offset, index, mmap, SaveX)?,
+		RecordName::CartesianY => f64_reader(data_type, offset, index, mmap, SaveY)?,
+		RecordName::CartesianZ => f64_reader(data_type, offset, index, mmap, SaveZ)?,
+		RecordName::CartesianInvalidState => i64_reader(data_type, offset, index, mmap, SaveInvalid)?,
+		_ => return Ok(None),
+	}))
+}
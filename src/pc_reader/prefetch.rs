@@ -0,0 +1,41 @@
+use super::SectionHeader;
+
+const PAGE_SIZE: usize = 1024;
+
+/// Number of pages touched ahead of the decoding position by default.
+pub const DEFAULT_PREFETCH_PAGES: usize = 64;
+
+/// Touches the pages of the memory map ahead of the decoding position,
+/// so the operating system loads them before the loaders need them.
+pub(crate) struct Prefetcher {
+	pages:   usize,
+	start:   usize,
+	end:     usize,
+	touched: usize,
+}
+
+impl Prefetcher {
+	pub fn new(section: &SectionHeader, pages: usize, mmap: &memmap2::Mmap) -> Self {
+		let start = section.data_offset as usize;
+		let end = section.physical_end().min(mmap.len());
+		Self { pages, start, end, touched: start }
+	}
+
+	pub fn set_pages(&mut self, pages: usize) {
+		self.pages = pages;
+	}
+
+	/// Touches all pages up to the window after the estimated position of the given point.
+	pub fn advance(&mut self, mmap: &memmap2::Mmap, read: u64, records: u64) {
+		if self.pages == 0 || self.touched >= self.end {
+			return;
+		}
+		// The loaders move through the section roughly proportional to the decoded points
+		let position = self.start + ((self.end - self.start) as u128 * read as u128 / records as u128) as usize;
+		let target = (position + self.pages * PAGE_SIZE).min(self.end);
+		while self.touched < target {
+			std::hint::black_box(mmap[self.touched]);
+			self.touched += PAGE_SIZE;
+		}
+	}
+}
@@ -0,0 +1,56 @@
+use super::PointCloudReader;
+use crate::Error;
+
+/// Iterate over groups of points that belong to the same laser pulse.
+///
+/// Created by [`PointCloudReader::pulses`].
+pub struct Pulses<'a, Point, F>
+where
+	Point: Default,
+	F: Fn(&Point) -> i64,
+{
+	reader:       PointCloudReader<'a, Point>,
+	return_index: F,
+	pending:      Option<Point>,
+}
+
+impl<'a, Point, F> Pulses<'a, Point, F>
+where
+	Point: Default,
+	F: Fn(&Point) -> i64,
+{
+	pub(crate) fn new(reader: PointCloudReader<'a, Point>, return_index: F) -> Self {
+		Self { reader, return_index, pending: None }
+	}
+}
+
+impl<'a, Point, F> Iterator for Pulses<'a, Point, F>
+where
+	Point: Default,
+	F: Fn(&Point) -> i64,
+{
+	type Item = Result<Vec<Point>, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let first = match self.pending.take() {
+			Some(point) => point,
+			None => match self.reader.next()? {
+				Ok(point) => point,
+				Err(err) => return Some(Err(err)),
+			},
+		};
+		let mut pulse = vec![first];
+		for point in self.reader.by_ref() {
+			let point = match point {
+				Ok(point) => point,
+				Err(err) => return Some(Err(err)),
+			};
+			if (self.return_index)(&point) == 0 {
+				self.pending = Some(point);
+				break;
+			}
+			pulse.push(point);
+		}
+		Some(Ok(pulse))
+	}
+}
@@ -0,0 +1,105 @@
+use super::F32Loader;
+use super::F64Loader;
+use super::GenPropertyReader;
+use super::IntLoader;
+use super::PointCloudReader;
+use super::PropertyConverter;
+use super::PropertyReader;
+use super::PropertySaver;
+use crate::Error;
+use crate::RawValues;
+use crate::Record;
+use crate::RecordDataType;
+use crate::RecordName;
+use crate::RecordValue;
+
+struct IntegerValue;
+impl PropertyConverter<i64, RecordValue> for IntegerValue {
+	fn convert(&self, v: i64) -> RecordValue {
+		RecordValue::Integer(v)
+	}
+}
+
+struct ScaledIntegerValue;
+impl PropertyConverter<i64, RecordValue> for ScaledIntegerValue {
+	fn convert(&self, v: i64) -> RecordValue {
+		RecordValue::ScaledInteger(v)
+	}
+}
+
+struct SingleValue;
+impl PropertyConverter<f32, RecordValue> for SingleValue {
+	fn convert(&self, v: f32) -> RecordValue {
+		RecordValue::Single(v)
+	}
+}
+
+struct DoubleValue;
+impl PropertyConverter<f64, RecordValue> for DoubleValue {
+	fn convert(&self, v: f64) -> RecordValue {
+		RecordValue::Double(v)
+	}
+}
+
+struct PushValue;
+impl PropertySaver<RawValues, RecordValue> for PushValue {
+	fn save(point: &mut RawValues, value: RecordValue) {
+		point.push(value);
+	}
+}
+
+/// Creates readers that append the raw value of every record in prototype order.
+pub(crate) fn raw_reader(
+	_name: RecordName,
+	data_type: RecordDataType,
+	offset: usize,
+	index: usize,
+	mmap: &memmap2::Mmap,
+) -> Result<Option<Box<dyn PropertyReader<RawValues>>>, Error> {
+	Ok(Some(match data_type {
+		RecordDataType::Single { .. } => {
+			GenPropertyReader::boxed(F32Loader::new(offset, index, mmap)?, SingleValue, PushValue)
+		},
+		RecordDataType::Double { .. } => {
+			GenPropertyReader::boxed(F64Loader::new(offset, index, mmap)?, DoubleValue, PushValue)
+		},
+		RecordDataType::ScaledInteger { min, max, .. } => GenPropertyReader::boxed(
+			IntLoader::new(offset, index, min, max, mmap)?,
+			ScaledIntegerValue,
+			PushValue,
+		),
+		RecordDataType::Integer { min, max } => {
+			GenPropertyReader::boxed(IntLoader::new(offset, index, min, max, mmap)?, IntegerValue, PushValue)
+		},
+	}))
+}
+
+/// Iterator over the raw values of all points, created by [`crate::Reader::pointcloud_raw`].
+pub type RawValuesReader<'a> = PointCloudReader<'a, RawValues>;
+
+/// Iterator over the raw values of all points together with their records, created by [`crate::Reader::typed_raw`].
+///
+/// Every point allocates a new vector with a copy of the records,
+/// use [`crate::Reader::for_each_raw`] for large point clouds.
+pub struct TypedRawValues<'a> {
+	reader:    PointCloudReader<'a, RawValues>,
+	prototype: Vec<Record>,
+}
+
+impl<'a> TypedRawValues<'a> {
+	pub(crate) fn new(reader: PointCloudReader<'a, RawValues>, prototype: Vec<Record>) -> Self {
+		Self { reader, prototype }
+	}
+}
+
+impl Iterator for TypedRawValues<'_> {
+	type Item = Result<Vec<(Record, RecordValue)>, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let values = match self.reader.next()? {
+			Ok(values) => values,
+			Err(err) => return Some(Err(err)),
+		};
+		Some(Ok(self.prototype.iter().copied().zip(values).collect()))
+	}
+}
@@ -0,0 +1,100 @@
+use crate::error::INTERNAL_ERROR;
+use crate::mmap_paged;
+use crate::mmap_paged::to_logical;
+use crate::mmap_paged::to_physical;
+use crate::Error;
+
+/// Header of a compressed vector section.
+pub(crate) struct SectionHeader {
+	pub section_offset: u64,
+	pub section_length: u64,
+	pub data_offset:    u64,
+	pub index_offset:   u64,
+}
+
+/// Offsets and length of the binary section of a point cloud.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SectionInfo {
+	/// Physical file offset of the first data packet.
+	pub data_offset:    u64,
+	/// Physical file offset of the first index packet, zero if the section has no index.
+	pub index_offset:   u64,
+	/// Logical length of the whole section in bytes, including the section header.
+	pub section_length: u64,
+}
+
+impl SectionHeader {
+	pub fn read(section_offset: u64, mmap: &memmap2::Mmap) -> Result<Self, Error> {
+		if to_physical(to_logical(section_offset as usize) + 32) > mmap.len() {
+			return truncated(section_offset);
+		}
+		if !mmap_paged::is_data_offset(section_offset as usize) {
+			return Error::Invalid(format!(
+				"Compressed vector section offset {section_offset} points to the checksum of a page"
+			))
+			.throw();
+		}
+
+		let mut buffer = [0_u8; 32];
+		mmap_paged::read(&mut buffer, section_offset as usize, mmap);
+		Self::parse(section_offset, &buffer, mmap.len())
+	}
+
+	/// Parses and validates the logical bytes of a section header.
+	pub fn parse(section_offset: u64, buffer: &[u8; 32], file_length: usize) -> Result<Self, Error> {
+		let section_id = buffer[0];
+		let section_length = u64::from_le_bytes(buffer[8..16].try_into().expect(INTERNAL_ERROR));
+		let data_offset = u64::from_le_bytes(buffer[16..24].try_into().expect(INTERNAL_ERROR));
+		let index_offset = u64::from_le_bytes(buffer[24..32].try_into().expect(INTERNAL_ERROR));
+
+		if section_id != 1 {
+			return Error::Invalid("Section ID of the compressed vector section header is not 1".into()).throw();
+		}
+		if section_length % 4 != 0 {
+			return Error::Invalid("Section length is not aligned and a multiple of four".into()).throw();
+		}
+
+		let header = Self { section_offset, section_length, data_offset, index_offset };
+		if header.physical_end() > file_length {
+			return truncated(section_offset);
+		}
+		if header.logical_data_offset() > header.logical_end() {
+			return Error::Invalid(format!(
+				"Data offset {data_offset} is outside of the compressed vector section at offset {section_offset}"
+			))
+			.throw();
+		}
+		Ok(header)
+	}
+
+	/// Returns the public information about the section.
+	pub fn info(&self) -> SectionInfo {
+		SectionInfo {
+			data_offset:    self.data_offset,
+			index_offset:   self.index_offset,
+			section_length: self.section_length,
+		}
+	}
+
+	/// Logical offset of the first data packet.
+	pub fn logical_data_offset(&self) -> usize {
+		to_logical(self.data_offset as usize)
+	}
+
+	/// Logical offset of the first byte after the section.
+	pub fn logical_end(&self) -> usize {
+		to_logical(self.section_offset as usize) + self.section_length as usize
+	}
+
+	/// Physical offset of the first byte after the section.
+	pub fn physical_end(&self) -> usize {
+		to_physical(self.logical_end())
+	}
+}
+
+fn truncated(section_offset: u64) -> Result<SectionHeader, Error> {
+	Error::Invalid(format!(
+		"Compressed vector section at offset {section_offset} extends past the end of the file, the file is truncated"
+	))
+	.throw()
+}
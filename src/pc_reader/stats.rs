@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+/// Throughput metrics of a complete read, created by [`super::PointCloudReader::read_all_timed`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReadStats {
+	/// Number of decoded points.
+	pub points:   u64,
+	/// Number of bytes in the binary section of the point cloud.
+	pub bytes:    u64,
+	/// Wall time spent decoding.
+	pub duration: Duration,
+}
+
+impl ReadStats {
+	/// Returns the number of decoded points per second.
+	pub fn points_per_second(&self) -> f64 {
+		self.points as f64 / self.duration.as_secs_f64()
+	}
+
+	/// Returns the number of decoded bytes per second.
+	pub fn bytes_per_second(&self) -> f64 {
+		self.bytes as f64 / self.duration.as_secs_f64()
+	}
+}
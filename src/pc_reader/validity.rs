@@ -0,0 +1,84 @@
+use super::GenPropertyReader;
+use super::IntLoader;
+use super::PropertyConverter;
+use super::PropertyReader;
+use super::PropertySaver;
+use crate::Error;
+use crate::RecordDataType;
+use crate::RecordName;
+
+/// Bit flags for a compact validity field of a point.
+///
+/// A set bit marks the corresponding attribute as invalid,
+/// so a zero field means that all attributes are valid.
+pub struct Validity;
+
+impl Validity {
+	/// Cartesian coordinate is invalid or only a direction.
+	pub const CARTESIAN: u8 = 1 << 0;
+	/// Spherical coordinate is invalid or has no meaningful range.
+	pub const SPHERICAL: u8 = 1 << 1;
+	/// Intensity is invalid.
+	pub const INTENSITY: u8 = 1 << 2;
+	/// Color is invalid.
+	pub const COLOR: u8 = 1 << 3;
+	/// Time stamp is invalid.
+	pub const TIME_STAMP: u8 = 1 << 4;
+
+	/// Returns the flag for an invalid state record or `None` for all other records.
+	pub fn flag(name: RecordName) -> Option<u8> {
+		Some(match name {
+			RecordName::CartesianInvalidState => Self::CARTESIAN,
+			RecordName::SphericalInvalidState => Self::SPHERICAL,
+			RecordName::IsIntensityInvalid => Self::INTENSITY,
+			RecordName::IsColorInvalid => Self::COLOR,
+			RecordName::IsTimeStampInvalid => Self::TIME_STAMP,
+			_ => return None,
+		})
+	}
+}
+
+/// Converts the value of an invalid state record to its validity flag.
+pub struct ValidityConverter {
+	pub flag: u8,
+}
+
+impl PropertyConverter<i64, u8> for ValidityConverter {
+	fn convert(&self, v: i64) -> u8 {
+		if v != 0 {
+			self.flag
+		} else {
+			0
+		}
+	}
+}
+
+/// Creates a property reader for invalid state records that passes the validity flag to the saver.
+///
+/// The saver should combine the flags of all records, for example with `point.validity |= value`.
+/// Returns `None` for records without a validity flag.
+pub fn validity_reader<Point, Saver>(
+	name: RecordName,
+	data_type: RecordDataType,
+	offset: usize,
+	index: usize,
+	mmap: &memmap2::Mmap,
+	saver: Saver,
+) -> Result<Option<Box<dyn PropertyReader<Point>>>, Error>
+where
+	Point: 'static,
+	Saver: PropertySaver<Point, u8> + 'static,
+{
+	let flag = match Validity::flag(name) {
+		Some(flag) => flag,
+		None => return Ok(None),
+	};
+	match data_type {
+		RecordDataType::Integer { min, max } => Ok(Some(GenPropertyReader::boxed(
+			IntLoader::new(offset, index, min, max, mmap)?,
+			ValidityConverter { flag },
+			saver,
+		))),
+		_ => Error::Invalid(format!("Invalid state record {name:?} must be an integer")).throw(),
+	}
+}
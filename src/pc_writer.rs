@@ -0,0 +1,288 @@
+use crate::paged_writer::PagedWriter;
+use crate::record::integer_bits;
+use crate::Error;
+use crate::Point;
+use crate::RawValues;
+use crate::Record;
+use crate::RecordDataType;
+use crate::RecordName;
+use crate::RecordValue;
+use std::io::{Read, Seek, Write};
+
+const SECTION_HEADER_SIZE: usize = 32;
+const DATA_PACKET_HEADER_SIZE: usize = 6;
+const MAX_PACKET_SIZE: usize = 65536;
+
+/// Collects the bit-packed values of a single record, starting with the least significant bit.
+struct ByteStream {
+	bytes:   Vec<u8>,
+	pending: u128,
+	bits:    u32,
+}
+
+impl ByteStream {
+	fn new() -> Self {
+		Self { bytes: Vec::new(), pending: 0, bits: 0 }
+	}
+
+	fn push(&mut self, value: u64, bits: u32) {
+		self.pending |= (value as u128) << self.bits;
+		self.bits += bits;
+		while self.bits >= 8 {
+			self.bytes.push(self.pending as u8);
+			self.pending >>= 8;
+			self.bits -= 8;
+		}
+	}
+
+	/// Writes the remaining bits and returns the bytes of the stream.
+	fn take(&mut self) -> Vec<u8> {
+		if self.bits > 0 {
+			self.bytes.push(self.pending as u8);
+			self.pending = 0;
+			self.bits = 0;
+		}
+		std::mem::take(&mut self.bytes)
+	}
+}
+
+/// Checks the prototype and returns the number of points written into a single data packet.
+///
+/// The number is a multiple of eight, which ensures that all bytestreams of a packet end at a byte boundary.
+pub(crate) fn points_per_packet(prototype: &[Record]) -> Result<usize, Error> {
+	for record in prototype {
+		match record.data_type {
+			RecordDataType::Integer { min, max } | RecordDataType::ScaledInteger { min, max, .. } if max < min => {
+				return Error::Invalid(format!(
+					"Maximum value '{max}' and minimum value '{min}' of record {:?} are inconsistent",
+					record.name
+				))
+				.throw();
+			},
+			_ => {},
+		}
+	}
+	// Bytes required for eight points, where each bit of a value is exactly one byte
+	// Prototypes with only constant records have no data in the bytestreams at all
+	let bytes_per_eight = prototype
+		.iter()
+		.map(|r| r.data_type.bit_width() as usize)
+		.sum::<usize>()
+		.max(1);
+	let header_size = DATA_PACKET_HEADER_SIZE + prototype.len() * 2;
+	let available = MAX_PACKET_SIZE.saturating_sub(header_size + 3);
+	match available / bytes_per_eight * 8 {
+		0 => Error::Invalid(format!(
+			"Prototype with {} records is too large for a data packet",
+			prototype.len()
+		))
+		.throw(),
+		points => Ok(points),
+	}
+}
+
+/// Encodes a single value and checks that it matches the record data type.
+fn encode(stream: &mut ByteStream, record: &Record, value: &RecordValue) -> Result<(), Error> {
+	match (value, record.data_type) {
+		(RecordValue::Single(v), RecordDataType::Single { .. }) => stream.push(v.to_bits() as u64, 32),
+		(RecordValue::Double(v), RecordDataType::Double { .. }) => stream.push(v.to_bits(), 64),
+		(RecordValue::ScaledInteger(v), RecordDataType::ScaledInteger { min, max, .. })
+		| (RecordValue::Integer(v), RecordDataType::Integer { min, max }) => {
+			if *v < min || *v > max {
+				return Error::Invalid(format!(
+					"Value {v} of record {:?} is outside of the range from {min} to {max}",
+					record.name
+				))
+				.throw();
+			}
+			stream.push(v.wrapping_sub(min) as u64, integer_bits(min, max));
+		},
+		_ => {
+			return Error::Invalid(format!(
+				"Value {value:?} does not match the data type {:?} of record {:?}",
+				record.data_type, record.name
+			))
+			.throw()
+		},
+	}
+	Ok(())
+}
+
+/// Converts a point into raw values in prototype order.
+///
+/// Real values are rounded to the nearest integer or multiple of the scale.
+/// Normalized intensities and colors are mapped to the minimum and maximum of their records,
+/// floating point records without limits use the range from 0 to 1 like the point reader.
+/// Scaled integer coordinates and integer colors use the original values of
+/// [`Point::scaled_position`] and [`Point::color_raw`] if available.
+/// Missing intensities, colors and time stamps are written as 1 into their invalid state records
+/// with the minimum of the value record as placeholder.
+/// Fails if the point has no value for a record of the prototype and the prototype has no invalid state for it.
+pub(crate) fn point_values(point: &Point, prototype: &[Record]) -> Result<RawValues, Error> {
+	let reals = point_reals(point, prototype)?;
+	let values = prototype.iter().zip(reals).map(|(record, real)| {
+		let axis = match record.name {
+			RecordName::CartesianX => 0,
+			RecordName::CartesianY => 1,
+			RecordName::CartesianZ => 2,
+			_ => return real_value(real, &record.data_type),
+		};
+		match (point.scaled_position, record.data_type) {
+			(Some(scaled), RecordDataType::ScaledInteger { .. }) => RecordValue::ScaledInteger(scaled[axis]),
+			_ => real_value(real, &record.data_type),
+		}
+	});
+	Ok(values.collect())
+}
+
+/// Returns the values of a point in prototype order in the units of the records, before they are rounded.
+///
+/// Uses the same conversions as [`point_values`].
+pub(crate) fn point_reals(point: &Point, prototype: &[Record]) -> Result<Vec<f64>, Error> {
+	let has = |name: RecordName| prototype.iter().any(|r| r.name == name);
+	let intensity = point.intensity.or(has(RecordName::IsIntensityInvalid).then_some(0.0));
+	let color_invalid = point.color.is_none() && point.color_raw.is_none();
+	let placeholder_color = color_invalid && has(RecordName::IsColorInvalid);
+	let timestamp = point.timestamp.or(has(RecordName::IsTimeStampInvalid).then_some(0.0));
+	let mut reals = Vec::with_capacity(prototype.len());
+	for record in prototype {
+		let data_type = &record.data_type;
+		let integer = |value: Option<i64>| value.map(|v| v as f64);
+		let state = |invalid: bool| Some(invalid as i64 as f64);
+		let color = |channel: usize| match (point.color_raw, data_type) {
+			_ if placeholder_color => Some(unit_real(0.0, data_type)),
+			(Some(raw), RecordDataType::Integer { .. }) => Some(raw[channel] as f64),
+			_ => point.color.map(|c| unit_real(c[channel], data_type)),
+		};
+		let real = match record.name {
+			RecordName::CartesianX => Some(cartesian_real(point, 0, data_type)),
+			RecordName::CartesianY => Some(cartesian_real(point, 1, data_type)),
+			RecordName::CartesianZ => Some(cartesian_real(point, 2, data_type)),
+			RecordName::CartesianInvalidState => integer(Some(point.cartesian_invalid)),
+			RecordName::SphericalRange => point.spherical.map(|s| s[0]),
+			RecordName::SphericalAzimuth => point.spherical.map(|s| s[1]),
+			RecordName::SphericalElevation => point.spherical.map(|s| s[2]),
+			RecordName::SphericalInvalidState => integer(Some(point.spherical_invalid)),
+			RecordName::Intensity => intensity.map(|v| unit_real(v, data_type)),
+			RecordName::IsIntensityInvalid => state(point.intensity.is_none()),
+			RecordName::ColorRed => color(0),
+			RecordName::ColorGreen => color(1),
+			RecordName::ColorBlue => color(2),
+			RecordName::IsColorInvalid => state(color_invalid),
+			RecordName::RowIndex => integer(point.row),
+			RecordName::ColumnIndex => integer(point.column),
+			RecordName::ReturnCount => integer(point.return_count),
+			RecordName::ReturnIndex => integer(point.return_index),
+			RecordName::TimeStamp => timestamp,
+			RecordName::IsTimeStampInvalid => state(point.timestamp.is_none()),
+			RecordName::StdevX => point.stdev.map(|s| s[0] as f64),
+			RecordName::StdevY => point.stdev.map(|s| s[1] as f64),
+			RecordName::StdevZ => point.stdev.map(|s| s[2] as f64),
+		};
+		match real {
+			Some(real) => reals.push(real),
+			None => return Error::Invalid(format!("Point has no value for record {:?}", record.name)).throw(),
+		}
+	}
+	Ok(reals)
+}
+
+fn cartesian_real(point: &Point, axis: usize, data_type: &RecordDataType) -> f64 {
+	match (point.scaled_position, data_type) {
+		(Some(scaled), RecordDataType::ScaledInteger { scale, .. }) => scaled[axis] as f64 * scale,
+		_ => point.position[axis],
+	}
+}
+
+fn real_value(value: f64, data_type: &RecordDataType) -> RecordValue {
+	match *data_type {
+		RecordDataType::Single { .. } => RecordValue::Single(value as f32),
+		RecordDataType::Double { .. } => RecordValue::Double(value),
+		RecordDataType::ScaledInteger { scale, .. } => RecordValue::ScaledInteger((value / scale).round() as i64),
+		RecordDataType::Integer { .. } => RecordValue::Integer(value.round() as i64),
+	}
+}
+
+/// Maps a value normalized to `0.0..=1.0` to the range of the record.
+fn unit_real(value: f32, data_type: &RecordDataType) -> f64 {
+	let (min, max) = match *data_type {
+		RecordDataType::Single { min: Some(min), max: Some(max) } => (min as f64, max as f64),
+		RecordDataType::Double { min: Some(min), max: Some(max) } => (min, max),
+		RecordDataType::Single { .. } | RecordDataType::Double { .. } => (0.0, 1.0),
+		RecordDataType::ScaledInteger { min, max, scale } => (min as f64 * scale, max as f64 * scale),
+		RecordDataType::Integer { min, max } => (min as f64, max as f64),
+	};
+	min + value as f64 * (max - min)
+}
+
+fn write_packet<T: Read + Write + Seek>(writer: &mut PagedWriter<T>, streams: &mut [ByteStream]) -> Result<(), Error> {
+	let streams = streams.iter_mut().map(ByteStream::take).collect::<Vec<_>>();
+	let size = DATA_PACKET_HEADER_SIZE + streams.len() * 2 + streams.iter().map(Vec::len).sum::<usize>();
+	let padded = size.next_multiple_of(4);
+
+	let mut packet = Vec::with_capacity(padded);
+	packet.push(1);
+	packet.push(0);
+	packet.extend_from_slice(&((padded - 1) as u16).to_le_bytes());
+	packet.extend_from_slice(&(streams.len() as u16).to_le_bytes());
+	for stream in &streams {
+		packet.extend_from_slice(&(stream.len() as u16).to_le_bytes());
+	}
+	for stream in &streams {
+		packet.extend_from_slice(stream);
+	}
+	packet.resize(padded, 0);
+	writer.write_all(&packet)
+}
+
+/// Writes a compressed vector section with the points and returns its physical offset and the number of points.
+///
+/// The number of points per packet must be calculated with [`points_per_packet`] for the same prototype.
+/// On error the section is already partially written.
+pub(crate) fn write_section<T: Read + Write + Seek>(
+	writer: &mut PagedWriter<T>,
+	prototype: &[Record],
+	points_per_packet: usize,
+	points: impl Iterator<Item = Result<RawValues, Error>>,
+) -> Result<(u64, u64), Error> {
+	writer.align(4)?;
+	let section_offset = writer.physical_position();
+	let section_start = writer.logical_position();
+	writer.write_all(&[0; SECTION_HEADER_SIZE])?;
+	let data_offset = writer.physical_position();
+
+	let mut streams = prototype.iter().map(|_| ByteStream::new()).collect::<Vec<_>>();
+	let mut records = 0_u64;
+	let mut in_packet = 0;
+	for values in points {
+		let values = values?;
+		if values.len() != prototype.len() {
+			return Error::Invalid(format!(
+				"Point {records} has {} values, but the prototype contains {} records",
+				values.len(),
+				prototype.len()
+			))
+			.throw();
+		}
+		for ((stream, record), value) in streams.iter_mut().zip(prototype).zip(&values) {
+			encode(stream, record, value)?;
+		}
+		records += 1;
+		in_packet += 1;
+		if in_packet == points_per_packet {
+			write_packet(writer, &mut streams)?;
+			in_packet = 0;
+		}
+	}
+	if in_packet > 0 {
+		write_packet(writer, &mut streams)?;
+	}
+
+	let section_length = writer.logical_position() - section_start;
+	let mut header = [0_u8; SECTION_HEADER_SIZE];
+	header[0] = 1;
+	header[8..16].copy_from_slice(&section_length.to_le_bytes());
+	header[16..24].copy_from_slice(&data_offset.to_le_bytes());
+	writer.write_at(section_offset, &header)?;
+	Ok((section_offset, records))
+}
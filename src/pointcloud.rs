@@ -1,12 +1,29 @@
-use crate::xml::{optional_double, optional_string, optional_transform, required_string};
-use crate::{CartesianBounds, Error, IndexBounds, Record, RecordDataType, RecordName, SphericalBounds, Transform};
+use crate::pc_reader::supported_by_point;
+use crate::pc_reader::SectionHeader;
+use crate::xml::{location, optional_real, optional_string, optional_transform, required_string};
+use crate::CartesianBounds;
+use crate::DateTime;
+use crate::Error;
+use crate::IndexBounds;
+use crate::IntensityLimits;
+use crate::LineGroup;
+use crate::LineGrouping;
+use crate::Reader;
+use crate::Record;
+use crate::RecordDataType;
+use crate::RecordName;
+use crate::SectionInfo;
+use crate::SphericalBounds;
+use crate::Transform;
 use roxmltree::{Document, Node};
+use std::collections::HashMap;
 
 /// Descriptor with metadata for a single point cloud.
 ///
 /// This struct does not contain any actual point data,
 /// it just describes the properties and attributes of a point cloud.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub struct PointCloud {
 	/// Globally unique identifier for the point cloud.
@@ -18,6 +35,12 @@ pub struct PointCloud {
 	/// List of point attributes that exist for this point cloud.
 	pub prototype:   Vec<Record>,
 
+	/// The XML section contains no record count and it could not be inferred from the binary section.
+	///
+	/// The record count is zero and reading the point cloud returns the error of the inference.
+	#[cfg_attr(feature = "serde", serde(skip))]
+	pub(crate) infer_records: bool,
+
 	/// Optional user-defined name for the point cloud.
 	pub name:                 Option<String>,
 	/// Optional user-defined description of the point cloud.
@@ -29,7 +52,17 @@ pub struct PointCloud {
 	/// Optional index bounds (row, column, return values) for the point cloud.
 	pub index_bounds:         Option<IndexBounds>,
 	/// Optional transformation to convert data from the local point cloud coordinates to the file-level coordinate system.
+	///
+	/// E57 files have no pose at the root level, this transformation alone yields the file-level coordinates.
 	pub transform:            Option<Transform>,
+	/// Optional start date and time when the point cloud was captured.
+	pub acquisition_start:    Option<DateTime>,
+	/// Optional end date and time when the point cloud was captured.
+	pub acquisition_end:      Option<DateTime>,
+	/// Optional minimum and maximum values for intensities.
+	pub intensity_limits:     Option<IntensityLimits>,
+	/// Optional grouping of the points into scan lines.
+	pub line_grouping:        Option<LineGrouping>,
 	/// Optional name of the manufacturer for the sensor used to capture the point cloud.
 	pub sensor_vendor:        Option<String>,
 	/// Optional model name of the sensor used for capturing.
@@ -50,6 +83,204 @@ pub struct PointCloud {
 	pub atmospheric_pressure: Option<f64>,
 }
 
+impl PointCloud {
+	/// Returns the data types of all prototype records keyed by their name.
+	pub fn prototype_map(&self) -> HashMap<RecordName, RecordDataType> {
+		self.prototype.iter().map(|r| (r.name, r.data_type)).collect()
+	}
+
+	/// Reads the header of the binary section of the point cloud.
+	///
+	/// Returns the physical data and index offsets together with the length of the section.
+	pub fn section_info(&self, reader: &Reader) -> Result<SectionInfo, Error> {
+		Ok(SectionHeader::read(self.file_offset, reader.mmap())?.info())
+	}
+
+	/// Reads the scan lines of the point cloud with their start index and number of points.
+	///
+	/// Returns `None` if the point cloud does not define a grouping by lines.
+	pub fn line_groups(&self, reader: &Reader) -> Result<Option<Vec<LineGroup>>, Error> {
+		match &self.line_grouping {
+			Some(grouping) => Ok(Some(grouping.read(reader.mmap())?)),
+			None => Ok(None),
+		}
+	}
+
+	/// Returns true if the prototype consists only of the Cartesian X, Y and Z coordinates.
+	///
+	/// The coordinates can be stored with any data type and in any order.
+	/// Such point clouds can be read with specialized readers for plain positions.
+	pub fn is_standard_xyz(&self) -> bool {
+		let has = |name: RecordName| self.prototype.iter().filter(|r| r.name == name).count() == 1;
+		self.prototype.len() == 3
+			&& has(RecordName::CartesianX)
+			&& has(RecordName::CartesianY)
+			&& has(RecordName::CartesianZ)
+	}
+
+	/// Returns the names of all prototype records that are skipped by the built-in point type.
+	///
+	/// Use the raw values, for example with [`Reader::pointcloud_raw`], if any of these records is required.
+	pub fn unsupported_by_point(&self) -> Vec<RecordName> {
+		self.prototype
+			.iter()
+			.filter(|r| !supported_by_point(r))
+			.map(|r| r.name)
+			.collect()
+	}
+
+	/// Returns the ratio between the uncompressed size of the points and the size of the binary section.
+	///
+	/// The uncompressed size stores every value in the smallest number of whole bytes for the bit width of its record.
+	/// Values above one show how much space is saved by bit-packing the values.
+	/// Returns an error for binary sections with a length of zero.
+	pub fn compression_ratio(&self, reader: &Reader) -> Result<f64, Error> {
+		let section = SectionHeader::read(self.file_offset, reader.mmap())?;
+		if section.section_length == 0 {
+			return Error::Invalid(format!(
+				"Binary section at offset {} has a length of zero",
+				self.file_offset
+			))
+			.throw();
+		}
+		let point_size: u64 = self
+			.prototype
+			.iter()
+			.map(|r| r.data_type.bit_width().div_ceil(8) as u64)
+			.sum();
+		Ok(self.records as f64 * point_size as f64 / section.section_length as f64)
+	}
+
+	/// Returns the scales of the Cartesian X, Y and Z coordinates.
+	///
+	/// Returns `None` if any of the three coordinates is missing or not stored as scaled integer.
+	pub fn cartesian_scales(&self) -> Option<[f64; 3]> {
+		let scale = |name: RecordName| {
+			self.prototype
+				.iter()
+				.find(|r| r.name == name)
+				.and_then(|r| match r.data_type {
+					RecordDataType::ScaledInteger { scale, .. } => Some(scale),
+					_ => None,
+				})
+		};
+		Some([
+			scale(RecordName::CartesianX)?,
+			scale(RecordName::CartesianY)?,
+			scale(RecordName::CartesianZ)?,
+		])
+	}
+}
+
+/// Builder to construct point cloud metadata, created with [`PointCloud::builder`].
+#[derive(Clone, Debug)]
+pub struct PointCloudBuilder {
+	pc: PointCloud,
+}
+
+macro_rules! builder_setters {
+	($($(#[$doc:meta])* $name:ident: $type:ty),* $(,)?) => {
+		$(
+			$(#[$doc])*
+			pub fn $name(mut self, value: $type) -> Self {
+				self.pc.$name = Some(value);
+				self
+			}
+		)*
+	};
+}
+
+impl PointCloud {
+	/// Creates a builder for point cloud metadata with the given GUID.
+	///
+	/// The file offset and the number of records are determined when the points are written.
+	pub fn builder(guid: impl Into<String>) -> PointCloudBuilder {
+		PointCloudBuilder {
+			pc: PointCloud {
+				guid: guid.into(),
+				..Default::default()
+			},
+		}
+	}
+}
+
+impl PointCloudBuilder {
+	/// Sets the list of point attributes.
+	pub fn prototype(mut self, prototype: Vec<Record>) -> Self {
+		self.pc.prototype = prototype;
+		self
+	}
+
+	builder_setters! {
+		/// Sets the user-defined name.
+		name: String,
+		/// Sets the user-defined description.
+		description: String,
+		/// Sets the Cartesian bounds.
+		cartesian_bounds: CartesianBounds,
+		/// Sets the spherical bounds.
+		spherical_bounds: SphericalBounds,
+		/// Sets the index bounds.
+		index_bounds: IndexBounds,
+		/// Sets the intensity limits.
+		intensity_limits: IntensityLimits,
+		/// Sets the transformation to the file-level coordinate system.
+		transform: Transform,
+		/// Sets the start of the acquisition.
+		acquisition_start: DateTime,
+		/// Sets the end of the acquisition.
+		acquisition_end: DateTime,
+		/// Sets the name of the sensor manufacturer.
+		sensor_vendor: String,
+		/// Sets the model name of the sensor.
+		sensor_model: String,
+		/// Sets the serial number of the sensor.
+		sensor_serial: String,
+		/// Sets the version of the sensor hardware.
+		sensor_hw_version: String,
+		/// Sets the version of the sensor software.
+		sensor_sw_version: String,
+		/// Sets the version of the sensor firmware.
+		sensor_fw_version: String,
+		/// Sets the ambient temperature in degrees Celsius.
+		temperature: f64,
+		/// Sets the relative humidity in percent.
+		humidity: f64,
+		/// Sets the atmospheric pressure in Pascals.
+		atmospheric_pressure: f64,
+	}
+
+	/// Validates and returns the point cloud metadata.
+	///
+	/// Fails if the GUID is empty, the prototype is empty or contains a record name more than once.
+	pub fn build(self) -> Result<PointCloud, Error> {
+		self.pc.validate()?;
+		Ok(self.pc)
+	}
+}
+
+impl PointCloud {
+	/// Checks the requirements of [`PointCloudBuilder::build`].
+	pub(crate) fn validate(&self) -> Result<(), Error> {
+		if self.guid.is_empty() {
+			return Error::Invalid("Point cloud GUID must not be empty".into()).throw();
+		}
+		if self.prototype.is_empty() {
+			return Error::Invalid("Point cloud prototype must contain at least one record".into()).throw();
+		}
+		for (i, record) in self.prototype.iter().enumerate() {
+			if self.prototype[..i].iter().any(|r| r.name == record.name) {
+				return Error::Invalid(format!(
+					"Point cloud prototype contains the record {:?} more than once",
+					record.name
+				))
+				.throw();
+			}
+		}
+		Ok(())
+	}
+}
+
 pub fn pointclouds_from_document(document: &Document) -> Result<Vec<PointCloud>, Error> {
 	let data3d_node = document
 		.descendants()
@@ -78,43 +309,96 @@ fn extract_pointcloud(node: &Node) -> Result<PointCloud, Error> {
 	let sensor_hw_version = optional_string(node, "sensorHardwareVersion")?;
 	let sensor_sw_version = optional_string(node, "sensorSoftwareVersion")?;
 	let sensor_fw_version = optional_string(node, "sensorFirmwareVersion")?;
-	let temperature = optional_double(node, "temperature")?;
-	let humidity = optional_double(node, "relativeHumidity")?;
-	let atmospheric_pressure = optional_double(node, "atmosphericPressure")?;
+	let temperature = optional_real(node, "temperature")?;
+	let humidity = optional_real(node, "relativeHumidity")?;
+	let atmospheric_pressure = optional_real(node, "atmosphericPressure")?;
 	let transform = optional_transform(node, "pose")?;
 	let cartesian_bounds = node.children().find(|n| n.has_tag_name("cartesianBounds"));
 	let spherical_bounds = node.children().find(|n| n.has_tag_name("sphericalBounds"));
 	let index_bounds = node.children().find(|n| n.has_tag_name("indexBounds"));
+	let intensity_limits = node.children().find(|n| n.has_tag_name("intensityLimits"));
+	let acquisition_start = node.children().find(|n| n.has_tag_name("acquisitionStart"));
+	let acquisition_end = node.children().find(|n| n.has_tag_name("acquisitionEnd"));
+	let line_grouping = node
+		.children()
+		.find(|n| n.has_tag_name("pointGroupingSchemes"))
+		.and_then(|n| n.children().find(|n| n.has_tag_name("groupingByLine")));
 
 	let points_tag = node
 		.children()
-		.find(|n| n.has_tag_name("points") && n.attribute("type") == Some("CompressedVector"))
-		.ok_or(Error::Invalid(
-			"Cannot find 'points' tag inside 'data3D' child".into(),
-		))?;
+		.find(|n| n.has_tag_name("points"))
+		.ok_or_else(|| {
+			Error::Invalid(format!(
+				"Cannot find 'points' tag inside 'data3D' child at {}",
+				location(node)
+			))
+		})?;
+	match points_tag.attribute("type") {
+		Some("CompressedVector") => {},
+		Some("Vector") => {
+			return Error::Unimplemented(format!(
+				"Uncompressed 'Vector' point storage is not supported at {}",
+				location(&points_tag)
+			))
+			.throw()
+		},
+		Some(found_type) => {
+			return Error::Invalid(format!(
+				"Found 'points' tag with type '{found_type}' instead of 'CompressedVector' at {}",
+				location(&points_tag)
+			))
+			.throw()
+		},
+		None => {
+			return Error::Invalid(format!(
+				"XML tag 'points' has no 'type' attribute at {}",
+				location(&points_tag)
+			))
+			.throw()
+		},
+	}
 	let file_offset = points_tag
 		.attribute("fileOffset")
-		.ok_or(Error::Invalid(
-			"Cannot find 'fileOffset' attribute in 'points' tag".into(),
-		))?
-		.parse::<u64>()?;
-	let records = points_tag
-		.attribute("recordCount")
-		.ok_or(Error::Invalid(
-			"Cannot find 'recordCount' attribute in 'points' tag".into(),
-		))?
+		.ok_or_else(|| {
+			Error::Invalid(format!(
+				"Cannot find 'fileOffset' attribute in 'points' tag at {}",
+				location(&points_tag)
+			))
+		})?
 		.parse::<u64>()?;
+	// Some minimal files omit the record count, it is inferred later from the binary section
+	let records = points_tag.attribute("recordCount").map(str::parse::<u64>).transpose()?;
 	let prototype_tag = points_tag
 		.children()
 		.find(|n| n.has_tag_name("prototype") && n.attribute("type") == Some("Structure"))
-		.ok_or(Error::Invalid(
-			"Cannot find 'prototype' child in 'points' tag".into(),
-		))?;
+		.ok_or_else(|| {
+			Error::Invalid(format!(
+				"Cannot find 'prototype' child in 'points' tag at {}",
+				location(&points_tag)
+			))
+		})?;
 	let mut prototype = Vec::new();
 	for n in prototype_tag.children() {
 		if n.is_element() {
 			let tag_name = n.tag_name().name();
-			let name = RecordName::from_tag_name(tag_name)?;
+			let name = match RecordName::from_tag_name(tag_name) {
+				Ok(name) => name,
+				// Records of extensions are namespaced with a prefix declared in the root element
+				Err(err) => match n
+					.tag_name()
+					.namespace()
+					.and_then(|ns| n.lookup_prefix(ns).map(|p| (p, ns)))
+				{
+					Some((prefix, namespace)) => {
+						return Error::Unimplemented(format!(
+							"Found unknown record '{prefix}:{tag_name}' of extension namespace '{namespace}' at {}",
+							location(&n)
+						))
+						.throw()
+					},
+					None => return Err(err),
+				},
+			};
 			let data_type = RecordDataType::from_node(&n)?;
 			prototype.push(Record { name, data_type });
 		}
@@ -124,7 +408,8 @@ fn extract_pointcloud(node: &Node) -> Result<PointCloud, Error> {
 		guid,
 		name,
 		file_offset,
-		records,
+		records: records.unwrap_or(0),
+		infer_records: records.is_none(),
 		prototype,
 		cartesian_bounds: if let Some(node) = cartesian_bounds {
 			Some(CartesianBounds::from_node(&node)?)
@@ -141,6 +426,26 @@ fn extract_pointcloud(node: &Node) -> Result<PointCloud, Error> {
 		} else {
 			None
 		},
+		intensity_limits: if let Some(node) = intensity_limits {
+			Some(IntensityLimits::from_node(&node)?)
+		} else {
+			None
+		},
+		line_grouping: if let Some(node) = line_grouping {
+			Some(LineGrouping::from_node(&node)?)
+		} else {
+			None
+		},
+		acquisition_start: if let Some(node) = acquisition_start {
+			Some(DateTime::from_node(&node)?)
+		} else {
+			None
+		},
+		acquisition_end: if let Some(node) = acquisition_end {
+			Some(DateTime::from_node(&node)?)
+		} else {
+			None
+		},
 		transform,
 		description,
 		sensor_vendor,
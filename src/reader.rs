@@ -1,16 +1,51 @@
+use crate::crc32::crc32c;
+use crate::error::INTERNAL_ERROR;
+use crate::images::images_from_document;
 use crate::mmap_paged;
+use crate::pc_reader::colored_reader;
+use crate::pc_reader::decode_packet;
+use crate::pc_reader::grid_reader;
+use crate::pc_reader::infer_record_count;
+#[cfg(feature = "rayon")]
+use crate::pc_reader::parallel_points;
+use crate::pc_reader::point_reader;
+use crate::pc_reader::position_reader;
+use crate::pc_reader::raw_reader;
+use crate::pc_reader::ColoredFastPath;
+use crate::pc_reader::PacketIterator;
 use crate::pc_reader::PointCloudReader;
 use crate::pc_reader::PropertyReader;
+use crate::pc_reader::SectionHeader;
+use crate::pc_reader::TypedRawValues;
 use crate::pointcloud::pointclouds_from_document;
+use crate::root::epsg_from_wkt;
 use crate::root::root_from_document;
 use crate::root::Root;
+use crate::strict::check_sections;
+use crate::strict::check_structure;
+use crate::Backing;
+use crate::Blob;
+use crate::ColoredPosition;
 use crate::Error;
+use crate::FileSummary;
+use crate::GeometryStats;
 use crate::Header;
+use crate::Image;
+use crate::Metadata;
+use crate::Point;
 use crate::PointCloud;
+use crate::PointOptions;
+use crate::RangeImage;
+use crate::RawValues;
+use crate::RawValuesReader;
 use crate::RecordDataType;
 use crate::RecordName;
+use crate::RecordValue;
+use crate::Transform;
+use memmap2::Mmap;
 use roxmltree::Document;
 use std::fs::File;
+use std::io::Write;
 use std::path::Path;
 
 /// Main interface for reading E57 files.
@@ -19,27 +54,97 @@ pub struct Reader {
 	header:      Header,
 	root:        Root,
 	pointclouds: Vec<PointCloud>,
+	images:      Vec<Image>,
+
+	crc_validated: bool,
 }
 
 impl Reader {
 	/// Creates a new E57 instance for from a reader.
-	pub fn new(mut reader: File) -> Result<Self, Error> {
-		// Read, parse and validate E57 header
-		let header = Header::read(&mut reader)?;
+	pub fn new(reader: File) -> Result<Self, Error> {
+		let mmap = unsafe { memmap2::MmapOptions::new().map(&reader)? };
+		Self::from_mmap(mmap, false)
+	}
 
-		// Set up paged reader for the CRC page layer
+	/// Creates a new E57 instance from the contents of an E57 file in memory.
+	///
+	/// The data is copied into an anonymous memory map.
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+		if bytes.is_empty() {
+			return Error::Invalid("Cannot read E57 file without any data".into()).throw();
+		}
+		let mut mmap = memmap2::MmapMut::map_anon(bytes.len())?;
+		mmap.copy_from_slice(bytes);
+		Self::from_mmap(mmap.make_read_only()?, false)
+	}
+
+	fn from_mmap(mmap: Mmap, strict: bool) -> Result<Self, Error> {
+		// Read, parse and validate E57 header
+		let header = Header::read(&mut &mmap[..])?;
+		let (xml_offset, _) = xml_range(&header, mmap.len())?;
 
 		// Read and parse XML data
 		let mut xml_raw = vec![0_u8; header.xml_length as usize];
-		let mmap = unsafe { memmap2::MmapOptions::new().map(&reader)? };
+		mmap_paged::read(&mut xml_raw, xml_offset, &mmap);
+		let xml = xml_to_string(xml_raw)?;
+		let document = parse_document(&xml)?;
+		let root = root_from_document(&document)?;
+		let mut pointclouds = pointclouds_from_document(&document)?;
+		for pc in pointclouds.iter_mut().filter(|pc| pc.infer_records) {
+			// Failures are reported when the point cloud is read, the other point clouds stay readable
+			if let Ok(records) = infer_record_count(pc, &mmap) {
+				pc.records = records;
+				pc.infer_records = false;
+			}
+		}
+		let images = images_from_document(&document)?;
+		if strict {
+			check_structure(mmap.len(), &header, &document, &root, &pointclouds)?;
+			check_sections(&mmap, &header, &pointclouds, &images)?;
+		}
+		Ok(Self {
+			mmap,
+			header,
+			root,
+			pointclouds,
+			images,
+			crc_validated: false,
+		})
+	}
 
-		mmap_paged::read(&mut xml_raw, header.phys_xml_offset as usize, &mmap);
+	/// Validates the CRC checksums of all pages in the file.
+	///
+	/// Returns an error for the first page with a mismatching checksum.
+	pub fn validate_crc(&mut self) -> Result<(), Error> {
+		const PAGE_SIZE: usize = 1024;
+		const CHECKSUM_SIZE: usize = 4;
 
-		let xml = String::from_utf8(xml_raw)?;
-		let document = Document::parse(&xml)?;
-		let root = root_from_document(&document)?;
-		let pointclouds = pointclouds_from_document(&document)?;
-		Ok(Self { mmap, header, root, pointclouds })
+		if !self.mmap.len().is_multiple_of(PAGE_SIZE) {
+			return Error::Invalid("File size is not a multiple of the page size".into()).throw();
+		}
+		for (index, page) in self.mmap.chunks_exact(PAGE_SIZE).enumerate() {
+			let (data, checksum) = page.split_at(PAGE_SIZE - CHECKSUM_SIZE);
+			let expected = u32::from_be_bytes(checksum.try_into().expect(INTERNAL_ERROR));
+			if crc32c(data) != expected {
+				return Error::Invalid(format!("Detected invalid checksum for page {index}")).throw();
+			}
+		}
+		self.crc_validated = true;
+		Ok(())
+	}
+
+	/// Validates that the binary sections of all point clouds and image blobs, the XML section and the file header do not overlap.
+	///
+	/// Overlapping sections indicate a corrupt file or a bug in the producer,
+	/// reading one point cloud could return the data of another.
+	/// Returns [`Error::Invalid`] naming the first pair of overlapping sections.
+	pub fn validate_sections(&self) -> Result<(), Error> {
+		check_sections(&self.mmap, &self.header, &self.pointclouds, &self.images)
+	}
+
+	/// Returns true if the CRC checksums of all pages were successfully validated.
+	pub fn crc_validated(&self) -> bool {
+		self.crc_validated
 	}
 
 	/// Returns the contents of E57 binary file header structure.
@@ -47,6 +152,14 @@ impl Reader {
 		self.header.clone()
 	}
 
+	/// Returns the file length declared in the header if it disagrees with the actual file size.
+	///
+	/// Reading always uses the actual file size, a mismatch usually indicates a bug in the producer.
+	pub fn file_length_mismatch(&self) -> Option<u64> {
+		let declared = self.header.phys_length;
+		(declared != self.mmap.len() as u64).then_some(declared)
+	}
+
 	/// Returns format name stored in the XML section.
 	pub fn format_name(&self) -> &str {
 		&self.root.format
@@ -57,26 +170,384 @@ impl Reader {
 		&self.root.guid
 	}
 
+	/// Returns the version of the E57 format stored in the XML section as major and minor number.
+	pub fn version(&self) -> (i64, i64) {
+		(self.root.major_version, self.root.minor_version)
+	}
+
+	/// Returns the optional version string of the library that was used to write the file.
+	pub fn library_version(&self) -> Option<&str> {
+		self.root.library_version.as_deref()
+	}
+
 	/// Returns a list of all point clouds in the file.
 	pub fn pointclouds(&self) -> Vec<PointCloud> {
 		self.pointclouds.clone()
 	}
 
-	/// Returns an iterator for the requested point cloud.
-	pub fn pointcloud<F, Point>(&mut self, pc: &PointCloud, f: F) -> Result<PointCloudReader<Point>, Error>
+	/// Returns a list of all images in the file.
+	pub fn images(&self) -> Vec<Image> {
+		self.images.clone()
+	}
+
+	/// Copies the bytes of a blob, for example the image data of an [`Image`], into the writer.
+	///
+	/// Validates the header of the blob section and returns the number of bytes written.
+	pub fn blob(&self, blob: &Blob, out: &mut dyn Write) -> Result<u64, Error> {
+		blob.copy(&self.mmap, out)
+	}
+
+	/// Returns an iterator for the requested point cloud that decodes into a user-defined point type.
+	///
+	/// The closure `f` is called once for every record of the prototype, in prototype order, with:
+	/// - the name of the record,
+	/// - the data type of the record,
+	/// - the logical offset of the first data packet of the point cloud,
+	/// - the index of the record in the prototype, which is also the index of its bytestream,
+	/// - the memory map of the file.
+	///
+	/// It returns a [`PropertyReader`] that decodes the record into the point or `None` to skip the record.
+	/// The loaders must be created with the given offset, index and memory map.
+	/// For every point the iterator starts with `Point::default()` and calls all returned readers in prototype order.
+	pub fn pointcloud<F, Point>(&mut self, pc: &PointCloud, f: F) -> Result<PointCloudReader<'_, Point>, Error>
 	where
 		Point: Default,
-		F: Fn(
-			RecordName,
-			RecordDataType,
-			usize,
-			usize,
-			&memmap2::Mmap,
-		) -> Result<Option<Box<dyn PropertyReader<Point>>>, Error>,
+		F: Fn(RecordName, RecordDataType, usize, usize, &Mmap) -> Result<Option<Box<dyn PropertyReader<Point>>>, Error>,
 	{
 		PointCloudReader::new(pc, &self.mmap, f)
 	}
 
+	/// Returns an iterator for the requested point cloud that decodes into the built-in point type.
+	///
+	/// Intensities are normalized to `0.0..=1.0` with the intensity limits of the point cloud,
+	/// or with the minimum and maximum of the intensity record if the point cloud has no limits.
+	pub fn points(&mut self, pc: &PointCloud) -> Result<PointCloudReader<'_, Point>, Error> {
+		point_reader(pc, &PointOptions::default(), &self.mmap)
+	}
+
+	/// Returns an iterator over the built-in point type, decoded with the given options.
+	///
+	/// Overriding the normalization ranges corrects files with wrong or missing limits without modifying them.
+	pub fn points_with_options(
+		&mut self,
+		pc: &PointCloud,
+		options: PointOptions,
+	) -> Result<PointCloudReader<'_, Point>, Error> {
+		point_reader(pc, &options, &self.mmap)
+	}
+
+	/// Returns a parallel iterator over the built-in point type, decoded like with [`Reader::points`].
+	///
+	/// The data packets are split into ranges that are decoded independently on the rayon thread pool.
+	/// Ranges can only start at packets where all bytestreams start with a new value,
+	/// which is found with the packet headers alone, so an index packet is not required.
+	/// Point clouds where values span multiple packets are decoded by a single task.
+	/// Collecting the iterator keeps the order of the points in the file.
+	#[cfg(feature = "rayon")]
+	pub fn pointcloud_par(
+		&mut self,
+		pc: &PointCloud,
+	) -> Result<impl rayon::iter::ParallelIterator<Item = Result<Point, Error>> + '_, Error> {
+		parallel_points(pc, &self.mmap)
+	}
+
+	/// Returns an iterator over the built-in point type with the pose of the point cloud applied.
+	///
+	/// Every point is rotated and translated with [`PointCloud::transform`],
+	/// which converts the local coordinates of the point cloud to the file-level coordinate system.
+	/// Spherical coordinates are converted to Cartesian coordinates before the transformation.
+	/// The rotation is normalized first, because many producers store quaternions that are not exactly unit length.
+	/// Point clouds without a pose are returned unchanged, like with [`Reader::points`].
+	/// The unscaled integers in [`Point::scaled_position`] are never transformed.
+	pub fn pointcloud_transformed(&mut self, pc: &PointCloud) -> Result<PointCloudReader<'_, Point>, Error> {
+		let reader = point_reader(pc, &PointOptions::default(), &self.mmap)?;
+		Ok(match pose(pc) {
+			Some(transform) => reader.with_transform(move |p| transform.apply(p)),
+			None => reader,
+		})
+	}
+
+	/// Reads the Cartesian coordinates of all valid points of a point cloud.
+	///
+	/// Points marked with a non-zero Cartesian invalid state are skipped.
+	pub fn positions(&mut self, pc: &PointCloud) -> Result<Vec<[f64; 3]>, Error> {
+		check_cartesian(pc)?;
+		let mut positions = Vec::with_capacity(capacity(pc, &self.mmap));
+		for point in self.pointcloud(pc, position_reader)? {
+			let point = point?;
+			if !point.invalid {
+				positions.push(point.position);
+			}
+		}
+		Ok(positions)
+	}
+
+	/// Reads the Cartesian coordinates of all valid points with the pose of the point cloud applied.
+	///
+	/// The coordinates are rotated and translated like with [`Reader::pointcloud_transformed`].
+	/// Point clouds without a pose are returned unchanged, like with [`Reader::positions`].
+	pub fn positions_transformed(&mut self, pc: &PointCloud) -> Result<Vec<[f64; 3]>, Error> {
+		let mut positions = self.positions(pc)?;
+		if let Some(transform) = pose(pc) {
+			for position in positions.iter_mut() {
+				*position = transform.apply(*position);
+			}
+		}
+		Ok(positions)
+	}
+
+	/// Reads the Cartesian coordinates of all points of a point cloud in their original order.
+	///
+	/// Points marked with a non-zero Cartesian invalid state are returned as `None`,
+	/// which keeps a 1:1 correspondence between the result and the records in the file.
+	/// This preserves the layout of organized point clouds, where the position in the stream encodes row and column.
+	pub fn grid_positions(&mut self, pc: &PointCloud) -> Result<Vec<Option<[f64; 3]>>, Error> {
+		check_cartesian(pc)?;
+		let mut positions = Vec::with_capacity(capacity(pc, &self.mmap));
+		for point in self.pointcloud(pc, position_reader)? {
+			let point = point?;
+			positions.push((!point.invalid).then_some(point.position));
+		}
+		Ok(positions)
+	}
+
+	/// Reads the Cartesian coordinates and 8-bit colors of all valid points of a point cloud.
+	///
+	/// Point clouds with scaled integer coordinates and colors between 0 and 255 without any other records
+	/// are decoded with a specialized fast path, see the `colored_positions` benchmark.
+	/// Both variants perform the same checks of the point cloud and its section.
+	/// All other layouts use the generic readers, colors are normalized with their record limits and scaled to 0..=255.
+	/// Points without color records are returned with black color.
+	pub fn colored_positions(&mut self, pc: &PointCloud) -> Result<Vec<ColoredPosition>, Error> {
+		check_cartesian(pc)?;
+		let mut points = Vec::with_capacity(capacity(pc, &self.mmap));
+		let reader = PointCloudReader::with_fast_path(pc, &self.mmap, colored_reader, Some(ColoredFastPath::reader))?;
+		for point in reader {
+			let point = point?;
+			if !point.invalid {
+				points.push((point.position, point.color));
+			}
+		}
+		Ok(points)
+	}
+
+	/// Reads an organized point cloud into a grid with one cell per row and column.
+	///
+	/// The size of the grid is taken from the row and column index bounds.
+	/// Cartesian coordinates are used if available, otherwise the spherical coordinates are converted.
+	/// Cells without a point or with an invalid point are `None`.
+	/// Returns an error for point clouds without row and column indices or index bounds.
+	pub fn range_image(&mut self, pc: &PointCloud) -> Result<RangeImage, Error> {
+		let has = |name: RecordName| pc.prototype.iter().any(|r| r.name == name);
+		if !has(RecordName::RowIndex) || !has(RecordName::ColumnIndex) {
+			return Error::Invalid("Point cloud is not organized, it has no row and column indices".into()).throw();
+		}
+		let bounds = pc.index_bounds.clone().unwrap_or_default();
+		let (Some(row_min), Some(row_max), Some(column_min), Some(column_max)) =
+			(bounds.row_min, bounds.row_max, bounds.column_min, bounds.column_max)
+		else {
+			return Error::Invalid("Point cloud has no row and column index bounds".into()).throw();
+		};
+		let spherical = !has(RecordName::CartesianX) && has(RecordName::SphericalRange);
+		if !spherical {
+			check_cartesian(pc)?;
+		}
+
+		let extent = |min: i64, max: i64| {
+			let extent = max
+				.checked_sub(min)
+				.and_then(|e| e.checked_add(1))
+				.map(|e| e.max(0) as u64);
+			extent.and_then(|e| usize::try_from(e).ok())
+		};
+		let cell_count = extent(column_min, column_max)
+			.zip(extent(row_min, row_max))
+			.and_then(|(width, height)| width.checked_mul(height).map(|cells| (width, height, cells)));
+		let Some((width, height, cell_count)) = cell_count else {
+			return Error::Invalid(format!(
+				"Index bounds with rows {row_min} to {row_max} and columns {column_min} to {column_max} are too large"
+			))
+			.throw();
+		};
+		let mut cells = Vec::new();
+		if cells.try_reserve_exact(cell_count).is_err() {
+			return Error::Invalid(format!("Grid with {width} columns and {height} rows is too large")).throw();
+		}
+		cells.resize(cell_count, None);
+		for point in self.pointcloud(pc, grid_reader)? {
+			let point = point?;
+			let Some(position) = point.position(spherical) else {
+				continue;
+			};
+			let row = point.row - row_min;
+			let column = point.column - column_min;
+			if row < 0 || row >= height as i64 || column < 0 || column >= width as i64 {
+				return Error::Invalid(format!(
+					"Point with row {} and column {} is outside of the index bounds",
+					point.row, point.column
+				))
+				.throw();
+			}
+			cells[row as usize * width + column as usize] = Some(position);
+		}
+		Ok(RangeImage { width, height, cells })
+	}
+
+	/// Returns an iterator over the raw values of all points.
+	///
+	/// Every item contains one value per record in prototype order, without any conversion or filtering.
+	/// Scaled integers are not multiplied with their scale.
+	pub fn pointcloud_raw(&mut self, pc: &PointCloud) -> Result<RawValuesReader<'_>, Error> {
+		self.pointcloud(pc, raw_reader)
+	}
+
+	/// Returns an iterator over the raw values of all points paired with their records.
+	///
+	/// This is convenient for debugging and generic tools, but allocates a vector for every point.
+	/// Prefer [`Reader::for_each_raw`] which reuses a single buffer for all points.
+	pub fn typed_raw(&mut self, pc: &PointCloud) -> Result<TypedRawValues<'_>, Error> {
+		let reader = self.pointcloud(pc, raw_reader)?;
+		Ok(TypedRawValues::new(reader, pc.prototype.clone()))
+	}
+
+	/// Decodes the raw values of all points and passes them to the callback.
+	///
+	/// The callback receives the values of a single point in prototype order.
+	/// The same buffer is reused for all points, so the slice is only valid for the duration of the callback.
+	pub fn for_each_raw(&mut self, pc: &PointCloud, mut f: impl FnMut(&[RecordValue])) -> Result<(), Error> {
+		let mut reader = self.pointcloud(pc, raw_reader)?;
+		let mut values = RawValues::with_capacity(pc.prototype.len());
+		while let Some(result) = reader.read_into(&mut values) {
+			result?;
+			f(&values);
+			values.clear();
+		}
+		Ok(())
+	}
+
+	/// Reads the Cartesian coordinates of all valid points as packed half precision values.
+	///
+	/// The returned buffer contains the X, Y and Z coordinate of each point relative to the origin.
+	/// Half precision has only about three significant decimal digits,
+	/// so the result is only usable if the origin is close to the points, for example the centroid of the point cloud.
+	#[cfg(feature = "half")]
+	pub fn read_interleaved_f16(&mut self, pc: &PointCloud, origin: [f64; 3]) -> Result<Vec<half::f16>, Error> {
+		let mut buffer = Vec::with_capacity(capacity(pc, &self.mmap).saturating_mul(3));
+		for point in self.pointcloud(pc, position_reader)? {
+			let point = point?;
+			if !point.invalid {
+				for (value, origin) in point.position.iter().zip(origin) {
+					buffer.push(half::f16::from_f64(value - origin));
+				}
+			}
+		}
+		Ok(buffer)
+	}
+
+	/// Reads the Cartesian coordinates and colors of all valid points as packed half precision values.
+	///
+	/// The returned buffer contains six values per point, the X, Y and Z coordinate relative to the origin
+	/// followed by the red, green and blue color normalized to `0.0..=1.0`.
+	/// Points without a valid color are black.
+	/// The coordinates have the same limited precision as with [`Reader::read_interleaved_f16`].
+	#[cfg(feature = "half")]
+	pub fn read_interleaved_colored_f16(&mut self, pc: &PointCloud, origin: [f64; 3]) -> Result<Vec<half::f16>, Error> {
+		let mut buffer = Vec::with_capacity(capacity(pc, &self.mmap).saturating_mul(6));
+		for point in self.points(pc)? {
+			let point = point?;
+			if point.cartesian_invalid != 0 {
+				continue;
+			}
+			for (value, origin) in point.position.iter().zip(origin) {
+				buffer.push(half::f16::from_f64(value - origin));
+			}
+			buffer.extend(point.color.unwrap_or_default().map(half::f16::from_f32));
+		}
+		Ok(buffer)
+	}
+
+	/// Calculates the centroid and bounding box of all valid points in a single pass.
+	///
+	/// Points marked with a non-zero Cartesian invalid state are skipped.
+	/// Returns an error if the point cloud contains no valid points.
+	pub fn read_geometry_stats(&mut self, pc: &PointCloud) -> Result<GeometryStats, Error> {
+		let mut points = 0_u64;
+		let mut sum = [0.0; 3];
+		let mut min = [f64::INFINITY; 3];
+		let mut max = [f64::NEG_INFINITY; 3];
+		for point in self.pointcloud(pc, position_reader)? {
+			let point = point?;
+			if point.invalid {
+				continue;
+			}
+			points += 1;
+			for axis in 0..3 {
+				let value = point.position[axis];
+				sum[axis] += value;
+				min[axis] = min[axis].min(value);
+				max[axis] = max[axis].max(value);
+			}
+		}
+		if points == 0 {
+			return Error::Invalid("Point cloud contains no valid Cartesian coordinates".into()).throw();
+		}
+		let centroid = sum.map(|s| s / points as f64);
+		Ok(GeometryStats { points, centroid, min, max })
+	}
+
+	/// Calculates the mean of all valid points in a single pass.
+	///
+	/// Points marked with a non-zero Cartesian invalid state are skipped.
+	/// Use [`Reader::read_geometry_stats`] to get the bounding box in the same pass.
+	pub fn centroid(&mut self, pc: &PointCloud) -> Result<[f64; 3], Error> {
+		Ok(self.read_geometry_stats(pc)?.centroid)
+	}
+
+	/// Returns an iterator over the headers of all packets in the binary section of a point cloud.
+	///
+	/// Useful to analyze how the point data is distributed into packets and bytestreams.
+	pub fn packets(&self, pc: &PointCloud) -> Result<PacketIterator<'_>, Error> {
+		let section = SectionHeader::read(pc.file_offset, &self.mmap)?;
+		Ok(PacketIterator::new(&section, &self.mmap))
+	}
+
+	/// Decodes the raw values of all points in a single data packet of a point cloud, see [`decode_packet`].
+	///
+	/// The offset is the logical offset of the packet, as reported by [`Reader::packets`].
+	pub fn decode_packet(&self, pc: &PointCloud, logical_offset: usize) -> Result<Vec<RawValues>, Error> {
+		decode_packet(&self.mmap, pc, logical_offset)
+	}
+
+	/// Returns the memory map of the whole file.
+	pub(crate) fn mmap(&self) -> &Mmap {
+		&self.mmap
+	}
+
+	/// Returns the physical pages of the binary section of a point cloud.
+	///
+	/// Each page has 1024 bytes and includes the trailing CRC checksum,
+	/// which makes it possible to cache the raw pages and verify them later.
+	/// The first and last page can also contain data of neighboring sections.
+	pub fn section_pages(&self, pc: &PointCloud) -> Result<impl Iterator<Item = &[u8]>, Error> {
+		const PAGE_SIZE: usize = 1024;
+
+		let section = SectionHeader::read(pc.file_offset, &self.mmap)?;
+		let start = section.section_offset as usize / PAGE_SIZE * PAGE_SIZE;
+		let end = section.physical_end().div_ceil(PAGE_SIZE) * PAGE_SIZE;
+		if end > self.mmap.len() {
+			return Error::Invalid("Binary section of the point cloud extends past the end of the file".into()).throw();
+		}
+		Ok(self.mmap[start..end].chunks_exact(PAGE_SIZE))
+	}
+
+	/// Returns the XML namespaces declared for the root element as pairs of prefix and URI.
+	///
+	/// The default namespace has an empty prefix.
+	/// Other prefixes indicate extensions used by the file, for example `nor` for normals.
+	pub fn namespaces(&self) -> Vec<(String, String)> {
+		self.root.namespaces.clone()
+	}
+
 	/// Returns the optional coordinate system metadata.
 	///
 	/// This should contain a Coordinate Reference System that is specified by
@@ -87,6 +558,29 @@ impl Reader {
 	pub fn coordinate_metadata(&self) -> Option<&str> {
 		self.root.coordinate_metadata.as_deref()
 	}
+
+	/// Returns a short description of the file with the number of point clouds, images and points.
+	///
+	/// Uses only the already parsed XML section, no points are decoded.
+	pub fn summary(&self) -> FileSummary {
+		FileSummary {
+			format_name:             self.root.format.clone(),
+			guid:                    self.root.guid.clone(),
+			pointclouds:             self.pointclouds.len(),
+			images:                  self.images.len(),
+			points:                  self.pointclouds.iter().map(|pc| pc.records).sum(),
+			creation:                self.root.creation.clone(),
+			has_coordinate_metadata: self.root.coordinate_metadata.is_some(),
+		}
+	}
+
+	/// Returns the EPSG code of the coordinate reference system, if the coordinate metadata contains one.
+	///
+	/// The code is extracted with a simple pattern match on the `AUTHORITY["EPSG",...]` or `ID["EPSG",...]`
+	/// element of the WKT string, the WKT itself is not validated.
+	pub fn crs_epsg(&self) -> Option<u32> {
+		self.coordinate_metadata().and_then(epsg_from_wkt)
+	}
 }
 
 impl Reader {
@@ -95,4 +589,136 @@ impl Reader {
 		let file = File::open(path)?;
 		Self::new(file)
 	}
+
+	/// Reads only the header and the XML section of an E57 file without mapping the point data.
+	///
+	/// This is useful for scanning the metadata of many large files.
+	pub fn open_metadata_only(path: impl AsRef<Path>) -> Result<Metadata, Error> {
+		Metadata::read(path.as_ref())
+	}
+
+	/// Creates an E57 instance from a Path with the selected strategy for accessing the file contents.
+	///
+	/// Memory mapping can be much slower than a single buffered read on network file systems.
+	pub fn open_with_backing(path: impl AsRef<Path>, backing: Backing) -> Result<Self, Error> {
+		let mmap = backing.open(path.as_ref())?;
+		Self::from_mmap(mmap, false)
+	}
+
+	/// Creates an E57 instance from a gzip or zstd compressed E57 file.
+	///
+	/// The compression format is detected automatically and requires the `flate2` or `zstd` feature.
+	/// The whole file is decompressed into memory, during construction the decompressed data is held twice.
+	#[cfg(any(feature = "flate2", feature = "zstd"))]
+	pub fn from_reader_decompressed(mut reader: impl std::io::Read) -> Result<Self, Error> {
+		use std::io::Read;
+
+		let mut magic = [0_u8; 4];
+		reader.read_exact(&mut magic)?;
+		let reader = std::io::Cursor::new(magic).chain(reader);
+		let mut bytes = Vec::new();
+		match magic {
+			#[cfg(feature = "flate2")]
+			[0x1F, 0x8B, _, _] => flate2::read::GzDecoder::new(reader).read_to_end(&mut bytes)?,
+			#[cfg(feature = "zstd")]
+			[0x28, 0xB5, 0x2F, 0xFD] => zstd::stream::read::Decoder::new(reader)?.read_to_end(&mut bytes)?,
+			_ => return Error::Invalid("Unknown or unsupported compression format".into()).throw(),
+		};
+		Self::from_bytes(&bytes)
+	}
+
+	/// Creates an E57 instance from a Path and rejects files that violate the E57 structure.
+	///
+	/// In addition to the checks required for reading, the strict mode checks that
+	/// - the physical length in the header matches the file size,
+	/// - the format name is `ASTM E57 3D Imaging Data File` and the major version is 1,
+	/// - the root contains the `data3D` and `images2D` vectors,
+	/// - every `points` tag has a `codecs` child,
+	/// - the GUIDs of all point clouds are unique and different from the file GUID,
+	/// - every prototype contains at least one record and no record more than once,
+	/// - the Cartesian, spherical and color records are either all present or all missing,
+	/// - the binary sections do not overlap each other, the XML section or the header, see [`Reader::validate_sections`].
+	///
+	/// Returns [`Error::Invalid`] describing the first violation.
+	pub fn open_strict(path: impl AsRef<Path>) -> Result<Self, Error> {
+		let file = File::open(path)?;
+		let mmap = unsafe { memmap2::MmapOptions::new().map(&file)? };
+		Self::from_mmap(mmap, true)
+	}
+
+	/// Creates an E57 instance from a Path and validates the CRC checksums of all pages.
+	pub fn open_validated(path: impl AsRef<Path>) -> Result<Self, Error> {
+		let mut reader = Self::from_file(path)?;
+		reader.validate_crc()?;
+		Ok(reader)
+	}
+}
+
+/// Returns the pose of the point cloud with a normalized rotation.
+pub(crate) fn pose(pc: &PointCloud) -> Option<Transform> {
+	let mut transform = pc.transform.clone()?;
+	transform.rotation = transform.rotation.normalize();
+	Some(transform)
+}
+
+/// Returns the number of points to allocate memory for.
+///
+/// The record count of the XML section is limited by the number of points that fit into the binary section,
+/// so corrupt files cannot request arbitrary large allocations.
+fn capacity(pc: &PointCloud, mmap: &Mmap) -> usize {
+	let bits = pc.prototype.iter().map(|r| r.data_type.bit_width() as u64).sum::<u64>();
+	let section_bits = SectionHeader::read(pc.file_offset, mmap).map_or(0, |s| s.section_length.saturating_mul(8));
+	pc.records.min(section_bits.checked_div(bits).unwrap_or(0)) as usize
+}
+
+fn check_cartesian(pc: &PointCloud) -> Result<(), Error> {
+	let has_cartesian = [RecordName::CartesianX, RecordName::CartesianY, RecordName::CartesianZ]
+		.iter()
+		.all(|name| pc.prototype.iter().any(|r| r.name == *name));
+	if !has_cartesian {
+		return Error::Invalid("Point cloud does not contain Cartesian coordinates".into()).throw();
+	}
+	Ok(())
+}
+
+/// Returns the physical start and end offset of the XML section, which must be located inside the file.
+pub(crate) fn xml_range(header: &Header, file_length: usize) -> Result<(usize, usize), Error> {
+	// Some producers leave the length field empty or wrong, the actual file size is used instead
+	let xml_offset = header.phys_xml_offset as usize;
+	let xml_end = mmap_paged::to_logical(xml_offset)
+		.checked_add(header.xml_length as usize)
+		.filter(|end| *end <= file_length)
+		.map(mmap_paged::to_physical)
+		.filter(|end| *end <= file_length);
+	let (true, Some(xml_end)) = (mmap_paged::is_data_offset(xml_offset), xml_end) else {
+		return Error::Invalid(format!(
+			"XML section at offset {xml_offset} with length {} is not located inside the file, the file may be truncated",
+			header.xml_length
+		))
+		.throw();
+	};
+	Ok((xml_offset, xml_end))
+}
+
+/// Converts the logical XML data to a string.
+pub(crate) fn xml_to_string(mut xml_raw: Vec<u8>) -> Result<String, Error> {
+	// Some producers pad the XML section with NUL bytes or whitespace
+	let padding = xml_raw
+		.iter()
+		.rev()
+		.take_while(|b| **b == 0 || b.is_ascii_whitespace())
+		.count();
+	xml_raw.truncate(xml_raw.len() - padding);
+	Ok(String::from_utf8(xml_raw)?)
+}
+
+pub(crate) fn parse_document(xml: &str) -> Result<Document<'_>, Error> {
+	match Document::parse(xml) {
+		Ok(document) => Ok(document),
+		// Retry once without any trailing garbage after the last closing tag
+		Err(err) => match xml.rfind('>') {
+			Some(end) if end + 1 < xml.len() => Ok(Document::parse(&xml[..=end]).map_err(|_| err)?),
+			_ => Err(err.into()),
+		},
+	}
 }
@@ -1,18 +1,25 @@
+use crate::xml::location;
 use crate::Error;
 use roxmltree::Node;
 use std::error::Error as StdError;
 use std::fmt::{Debug, Display};
 use std::str::FromStr;
 
+/// Prefix and URI of the extension namespace for the standard deviation records.
+const STDEV_NAMESPACE: (&str, &str) = ("stdev", "urn:e57:extension:stdev");
+
 /// Describes a record inside a E57 file with name and data type.
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Record {
 	pub name:      RecordName,
 	pub data_type: RecordDataType,
 }
 
 /// Basic primtive E57 data types that are used for the different point attributes.
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
 pub enum RecordDataType {
 	/// 32-bit IEEE 754-2008 floating point value.
 	Single { min: Option<f32>, max: Option<f32> },
@@ -26,6 +33,7 @@ pub enum RecordDataType {
 
 /// Used to describe the prototype records with all attributes that exit in the point cloud.
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RecordName {
 	/// Cartesian X coordinate (in meters).
 	CartesianX,
@@ -78,12 +86,21 @@ pub enum RecordName {
 	/// Indicates whether the time stamp value is meaningful.
 	/// Can have the value 0 (valid) or 1 (invalid).
 	IsTimeStampInvalid,
+
+	/// Extension: Standard deviation (in meters) of the Cartesian X coordinate.
+	StdevX,
+	/// Extension: Standard deviation (in meters) of the Cartesian Y coordinate.
+	StdevY,
+	/// Extension: Standard deviation (in meters) of the Cartesian Z coordinate.
+	StdevZ,
 }
 
 /// Represents a raw value of records inside a point cloud.
 ///
 /// For scaled integers the record data type with the scale is needed to calulcate the actual f64 value.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum RecordValue {
 	Single(f32),
 	Double(f64),
@@ -91,6 +108,9 @@ pub enum RecordValue {
 	Integer(i64),
 }
 
+/// Raw values of all records of a single point in prototype order.
+pub type RawValues = Vec<RecordValue>;
+
 impl RecordName {
 	pub(crate) fn from_tag_name(value: &str) -> Result<Self, Error> {
 		Ok(match value {
@@ -114,17 +134,62 @@ impl RecordName {
 			"returnIndex" => RecordName::ReturnIndex,
 			"timeStamp" => RecordName::TimeStamp,
 			"isTimeStampInvalid" => RecordName::IsTimeStampInvalid,
+			"pointStdevX" | "stdevX" => RecordName::StdevX,
+			"pointStdevY" | "stdevY" => RecordName::StdevY,
+			"pointStdevZ" | "stdevZ" => RecordName::StdevZ,
 			name => return Error::Unimplemented(format!("Found unknown record name: '{name}'")).throw(),
 		})
 	}
+
+	/// Returns the XML tag name, records of extensions are prefixed with the prefix of their namespace.
+	pub(crate) fn tag_name(&self) -> &'static str {
+		match self {
+			RecordName::CartesianX => "cartesianX",
+			RecordName::CartesianY => "cartesianY",
+			RecordName::CartesianZ => "cartesianZ",
+			RecordName::CartesianInvalidState => "cartesianInvalidState",
+			RecordName::SphericalRange => "sphericalRange",
+			RecordName::SphericalAzimuth => "sphericalAzimuth",
+			RecordName::SphericalElevation => "sphericalElevation",
+			RecordName::SphericalInvalidState => "sphericalInvalidState",
+			RecordName::Intensity => "intensity",
+			RecordName::IsIntensityInvalid => "isIntensityInvalid",
+			RecordName::ColorRed => "colorRed",
+			RecordName::ColorGreen => "colorGreen",
+			RecordName::ColorBlue => "colorBlue",
+			RecordName::IsColorInvalid => "isColorInvalid",
+			RecordName::RowIndex => "rowIndex",
+			RecordName::ColumnIndex => "columnIndex",
+			RecordName::ReturnCount => "returnCount",
+			RecordName::ReturnIndex => "returnIndex",
+			RecordName::TimeStamp => "timeStamp",
+			RecordName::IsTimeStampInvalid => "isTimeStampInvalid",
+			RecordName::StdevX => "stdev:pointStdevX",
+			RecordName::StdevY => "stdev:pointStdevY",
+			RecordName::StdevZ => "stdev:pointStdevZ",
+		}
+	}
+
+	/// Returns the prefix and URI of the extension namespace that defines the record.
+	///
+	/// Standard records are part of the default E57 namespace and have no extension namespace.
+	pub(crate) fn namespace(&self) -> Option<(&'static str, &'static str)> {
+		match self {
+			RecordName::StdevX | RecordName::StdevY | RecordName::StdevZ => Some(STDEV_NAMESPACE),
+			_ => None,
+		}
+	}
 }
 
 impl RecordDataType {
 	pub(crate) fn from_node(node: &Node) -> Result<Self, Error> {
 		let tag_name = node.tag_name().name();
-		let type_name = node.attribute("type").ok_or(Error::Invalid(format!(
-			"Missing type attribute for XML tag '{tag_name}'"
-		)))?;
+		let type_name = node.attribute("type").ok_or_else(|| {
+			Error::Invalid(format!(
+				"Missing type attribute for XML tag '{tag_name}' at {}",
+				location(node)
+			))
+		})?;
 		Ok(match type_name {
 			"Float" => {
 				let precision = node.attribute("precision").unwrap_or("double");
@@ -138,7 +203,8 @@ impl RecordDataType {
 					RecordDataType::Single { min, max }
 				} else {
 					return Error::Invalid(format!(
-						"Float 'precision' attribute value '{precision}' for 'Float' type is unknown"
+						"Float 'precision' attribute value '{precision}' for 'Float' type is unknown at {}",
+						location(node)
 					))
 					.throw();
 				}
@@ -146,10 +212,12 @@ impl RecordDataType {
 			"Integer" => {
 				let min = required_attribute(node, "minimum", tag_name, type_name)?;
 				let max = required_attribute(node, "maximum", tag_name, type_name)?;
-				if max <= min {
+				// Constant values with a minimum equal to the maximum are stored with zero bits
+				if max < min {
 					return Error::Invalid(format!(
 						"Maximum value '{max}' and minimum value '{min}' of type '{type_name}' in XML tag \
-						 '{tag_name}' are inconsistent"
+						 '{tag_name}' are inconsistent at {}",
+						location(node)
 					))
 					.throw();
 				}
@@ -158,10 +226,12 @@ impl RecordDataType {
 			"ScaledInteger" => {
 				let min = required_attribute(node, "minimum", tag_name, type_name)?;
 				let max = required_attribute(node, "maximum", tag_name, type_name)?;
-				if max <= min {
+				// Constant values with a minimum equal to the maximum are stored with zero bits
+				if max < min {
 					return Error::Invalid(format!(
 						"Maximum value '{max}' and minimum value '{min}' of type '{type_name}' in XML tag \
-						 '{tag_name}' are inconsistent"
+						 '{tag_name}' are inconsistent at {}",
+						location(node)
 					))
 					.throw();
 				}
@@ -170,7 +240,8 @@ impl RecordDataType {
 			},
 			_ => {
 				return Error::Unimplemented(format!(
-					"Unsupported type '{type_name}' in XML tag '{tag_name}' detected"
+					"Unsupported type '{type_name}' in XML tag '{tag_name}' detected at {}",
+					location(node)
 				))
 				.throw()
 			},
@@ -178,7 +249,75 @@ impl RecordDataType {
 	}
 }
 
+impl RecordDataType {
+	/// Returns the number of bits used to store a single value of this type.
+	///
+	/// Integers and scaled integers use the minimal number of bits to represent the range between minimum and maximum.
+	pub fn bit_width(&self) -> u32 {
+		match self {
+			RecordDataType::Single { .. } => 32,
+			RecordDataType::Double { .. } => 64,
+			RecordDataType::ScaledInteger { min, max, .. } | RecordDataType::Integer { min, max } => {
+				integer_bits(*min, *max)
+			},
+		}
+	}
+
+	/// Returns true if a single value of this type fills a whole number of bytes.
+	pub fn byte_aligned(&self) -> bool {
+		self.bit_width().is_multiple_of(8)
+	}
+
+	/// Serializes the data type as XML tag with the given name.
+	pub(crate) fn xml_tag(&self, tag_name: &str) -> String {
+		let limit = |name: &str, value: Option<String>| value.map(|v| format!(" {name}=\"{v}\"")).unwrap_or_default();
+		match self {
+			RecordDataType::Single { min, max } => format!(
+				"<{tag_name} type=\"Float\" precision=\"single\"{}{}/>",
+				limit("minimum", min.map(|v| v.to_string())),
+				limit("maximum", max.map(|v| v.to_string()))
+			),
+			RecordDataType::Double { min, max } => format!(
+				"<{tag_name} type=\"Float\" precision=\"double\"{}{}/>",
+				limit("minimum", min.map(|v| v.to_string())),
+				limit("maximum", max.map(|v| v.to_string()))
+			),
+			RecordDataType::ScaledInteger { min, max, scale } => {
+				format!("<{tag_name} type=\"ScaledInteger\" minimum=\"{min}\" maximum=\"{max}\" scale=\"{scale}\"/>")
+			},
+			RecordDataType::Integer { min, max } => {
+				format!("<{tag_name} type=\"Integer\" minimum=\"{min}\" maximum=\"{max}\"/>")
+			},
+		}
+	}
+}
+
 impl RecordValue {
+	/// Returns the value as `f64`, scaled integers are multiplied with the scale of their data type.
+	///
+	/// Fails if the value does not match the data type.
+	pub fn as_f64_scaled(&self, dt: &RecordDataType) -> Result<f64, Error> {
+		match (self, dt) {
+			(RecordValue::Single(v), RecordDataType::Single { .. }) => Ok(*v as f64),
+			(RecordValue::Double(v), RecordDataType::Double { .. }) => Ok(*v),
+			(RecordValue::ScaledInteger(v), RecordDataType::ScaledInteger { scale, .. }) => Ok(*v as f64 * *scale),
+			(RecordValue::Integer(v), RecordDataType::Integer { .. }) => Ok(*v as f64),
+			_ => Error::Invalid(format!("Cannot convert value {self:?} with data type {dt:?} to f64")).throw(),
+		}
+	}
+
+	/// Returns the raw integer of integer and scaled integer values.
+	///
+	/// Fails for floating point values.
+	pub fn as_i64(&self) -> Result<i64, Error> {
+		match self {
+			RecordValue::ScaledInteger(v) | RecordValue::Integer(v) => Ok(*v),
+			RecordValue::Single(_) | RecordValue::Double(_) => {
+				Error::Invalid(format!("Cannot convert floating point value {self:?} to i64")).throw()
+			},
+		}
+	}
+
 	// pub fn to_f64(&self, dt: &RecordDataType) -> Result<f64, Error> {
 	// 	match self {
 	// 		RecordValue::Single(s) => Ok(*s as f64),
@@ -259,6 +398,81 @@ impl Display for RecordValue {
 	}
 }
 
+/// Differences between the records of two prototypes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PrototypeDiff {
+	/// Records that only exist in the second prototype.
+	pub added:   Vec<Record>,
+	/// Records that only exist in the first prototype.
+	pub removed: Vec<Record>,
+	/// Records that exist in both prototypes with different data types (name, first type, second type).
+	pub retyped: Vec<(RecordName, RecordDataType, RecordDataType)>,
+}
+
+impl PrototypeDiff {
+	/// Returns true if both prototypes contain the same records.
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty() && self.retyped.is_empty()
+	}
+}
+
+impl Display for PrototypeDiff {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if self.is_empty() {
+			return write!(f, "prototypes are identical");
+		}
+		let mut parts = Vec::new();
+		for record in &self.added {
+			parts.push(format!("added {:?}", record.name));
+		}
+		for record in &self.removed {
+			parts.push(format!("removed {:?}", record.name));
+		}
+		for (name, a, b) in &self.retyped {
+			parts.push(format!("{name:?} changed from {a:?} to {b:?}"));
+		}
+		write!(f, "{}", parts.join(", "))
+	}
+}
+
+/// Compares two prototypes and reports added, removed and retyped records.
+///
+/// The order of the records is ignored.
+pub fn prototype_diff(a: &[Record], b: &[Record]) -> PrototypeDiff {
+	let mut diff = PrototypeDiff::default();
+	for record in a {
+		match b.iter().find(|other| other.name == record.name) {
+			None => diff.removed.push(*record),
+			Some(other) if other.data_type != record.data_type => {
+				diff.retyped.push((record.name, record.data_type, other.data_type))
+			},
+			Some(_) => {},
+		}
+	}
+	for record in b {
+		if !a.iter().any(|other| other.name == record.name) {
+			diff.added.push(*record);
+		}
+	}
+	diff
+}
+
+/// Returns true if both prototypes contain the same records with the same data types.
+///
+/// Point clouds with compatible prototypes can be merged without converting any values.
+pub fn prototypes_compatible(a: &[Record], b: &[Record]) -> bool {
+	prototype_diff(a, b).is_empty()
+}
+
+/// Returns the number of bits required to store integers between minimum and maximum.
+///
+/// This is the single place for the bit width calculation used by the loaders and the data types.
+/// Returns zero for constant or empty ranges.
+pub(crate) fn integer_bits(min: i64, max: i64) -> u32 {
+	let range = (max as i128 - min as i128).max(0) as u128;
+	u128::BITS - range.leading_zeros()
+}
+
 fn optional_attribute<T>(node: &Node, attribute: &str, tag_name: &str, type_name: &str) -> Result<Option<T>, Error>
 where
 	T: FromStr,
@@ -269,8 +483,11 @@ where
 		let v = match parsed {
 			Err(_) => {
 				return Error::Invalid(format!(
-					"Failed to parse attribute '{}' for type '{}' in XML tag '{}'",
-					attribute, type_name, tag_name
+					"Failed to parse attribute '{}' for type '{}' in XML tag '{}' at {}",
+					attribute,
+					type_name,
+					tag_name,
+					location(node)
 				))
 				.throw()
 			},
@@ -287,10 +504,15 @@ where
 	T: FromStr,
 	T::Err: StdError + Send + Sync + 'static,
 {
-	optional_attribute(node, attribute, tag_name, type_name)?.ok_or(Error::Invalid(format!(
-		"Cannot find '{}' for type '{}' in XML tag '{}'",
-		attribute, type_name, tag_name
-	)))
+	optional_attribute(node, attribute, tag_name, type_name)?.ok_or_else(|| {
+		Error::Invalid(format!(
+			"Cannot find '{}' for type '{}' in XML tag '{}' at {}",
+			attribute,
+			type_name,
+			tag_name,
+			location(node)
+		))
+	})
 }
 
 impl RecordDataType {
@@ -303,6 +525,18 @@ impl RecordDataType {
 	pub const U8: RecordDataType = RecordDataType::Integer { min: 0, max: u8::MAX as i64 };
 
 	pub const U16: RecordDataType = RecordDataType::Integer { min: 0, max: u16::MAX as i64 };
+
+	/// Non-negative distance with millimeter precision stored in 32 bits.
+	pub const RANGE_MM: RecordDataType =
+		RecordDataType::ScaledInteger { min: 0, max: u32::MAX as i64, scale: 0.001 };
+
+	/// Angle between -PI and PI with microradian precision.
+	pub const AZIMUTH_MICRORAD: RecordDataType =
+		RecordDataType::ScaledInteger { min: -3_141_593, max: 3_141_593, scale: 0.000_001 };
+
+	/// Angle between -PI/2 and PI/2 with microradian precision.
+	pub const ELEVATION_MICRORAD: RecordDataType =
+		RecordDataType::ScaledInteger { min: -1_570_797, max: 1_570_797, scale: 0.000_001 };
 }
 
 impl Record {
@@ -336,6 +570,51 @@ impl Record {
 		data_type: RecordDataType::F64,
 	};
 
+	pub const SPHERICAL_RANGE_F32: Record = Record {
+		name:      RecordName::SphericalRange,
+		data_type: RecordDataType::F32,
+	};
+
+	pub const SPHERICAL_AZIMUTH_F32: Record = Record {
+		name:      RecordName::SphericalAzimuth,
+		data_type: RecordDataType::F32,
+	};
+
+	pub const SPHERICAL_ELEVATION_F32: Record = Record {
+		name:      RecordName::SphericalElevation,
+		data_type: RecordDataType::F32,
+	};
+
+	pub const SPHERICAL_RANGE_F64: Record = Record {
+		name:      RecordName::SphericalRange,
+		data_type: RecordDataType::F64,
+	};
+
+	pub const SPHERICAL_AZIMUTH_F64: Record = Record {
+		name:      RecordName::SphericalAzimuth,
+		data_type: RecordDataType::F64,
+	};
+
+	pub const SPHERICAL_ELEVATION_F64: Record = Record {
+		name:      RecordName::SphericalElevation,
+		data_type: RecordDataType::F64,
+	};
+
+	pub const SPHERICAL_RANGE_SCALED: Record = Record {
+		name:      RecordName::SphericalRange,
+		data_type: RecordDataType::RANGE_MM,
+	};
+
+	pub const SPHERICAL_AZIMUTH_SCALED: Record = Record {
+		name:      RecordName::SphericalAzimuth,
+		data_type: RecordDataType::AZIMUTH_MICRORAD,
+	};
+
+	pub const SPHERICAL_ELEVATION_SCALED: Record = Record {
+		name:      RecordName::SphericalElevation,
+		data_type: RecordDataType::ELEVATION_MICRORAD,
+	};
+
 	pub const COLOR_RED_U8: Record = Record {
 		name:      RecordName::ColorRed,
 		data_type: RecordDataType::U8,
@@ -376,3 +655,93 @@ impl Record {
 		data_type: RecordDataType::UNIT_F32,
 	};
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Counts the bits of the range one at a time, independent of [`integer_bits`].
+	fn reference_bits(min: i64, max: i64) -> u32 {
+		let mut range = (max as i128 - min as i128).max(0);
+		let mut bits = 0;
+		while range > 0 {
+			range >>= 1;
+			bits += 1;
+		}
+		bits
+	}
+
+	#[test]
+	fn bit_widths_agree() {
+		let mut bounds = vec![i64::MIN, i64::MIN + 1, -1, 0, 1, i64::MAX - 1, i64::MAX];
+		for shift in 0..63 {
+			let power = 1_i64 << shift;
+			bounds.extend([power - 1, power, power + 1, -power - 1, -power, -power + 1]);
+		}
+		for &min in &bounds {
+			for &max in &bounds {
+				let bits = integer_bits(min, max);
+				assert_eq!(bits, reference_bits(min, max), "Range from {min} to {max}");
+				assert_eq!(RecordDataType::Integer { min, max }.bit_width(), bits);
+				assert_eq!(RecordDataType::ScaledInteger { min, max, scale: 0.5 }.bit_width(), bits);
+
+				let range = (max as i128 - min as i128).max(0) as u128;
+				assert!(
+					range < 1 << bits,
+					"Range from {min} to {max} does not fit into {bits} bits"
+				);
+				if bits > 0 {
+					assert!(
+						range >= 1 << (bits - 1),
+						"Range from {min} to {max} fits into {} bits",
+						bits - 1
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn checked_conversions() {
+		let data_types = [
+			RecordDataType::Single { min: None, max: None },
+			RecordDataType::Double { min: None, max: None },
+			RecordDataType::ScaledInteger { min: -100, max: 100, scale: 0.5 },
+			RecordDataType::Integer { min: -100, max: 100 },
+		];
+		let values = [
+			RecordValue::Single(1.5),
+			RecordValue::Double(-2.25),
+			RecordValue::ScaledInteger(-7),
+			RecordValue::Integer(42),
+		];
+		let expected = [1.5, -2.25, -3.5, 42.0];
+		for (value_index, value) in values.iter().enumerate() {
+			for (type_index, data_type) in data_types.iter().enumerate() {
+				let converted = value.as_f64_scaled(data_type);
+				if value_index == type_index {
+					assert_eq!(converted.ok(), Some(expected[value_index]));
+				} else {
+					let message = format!("{converted:?}");
+					assert!(
+						message.starts_with("Err(Invalid("),
+						"{value:?} converted with {data_type:?}"
+					);
+					assert!(message.contains(&format!("{value:?}")), "{message}");
+					assert!(message.contains(&format!("{data_type:?}")), "{message}");
+				}
+			}
+		}
+
+		assert_eq!(RecordValue::ScaledInteger(-7).as_i64().expect("Failed to convert"), -7);
+		assert_eq!(
+			RecordValue::Integer(i64::MIN).as_i64().expect("Failed to convert"),
+			i64::MIN
+		);
+		for value in [RecordValue::Single(1.0), RecordValue::Double(1.0)] {
+			let message = format!("{:?}", value.as_i64());
+			assert!(message.starts_with("Err(Invalid("), "{value:?} converted to i64");
+			assert!(message.contains(&format!("{value:?}")), "{message}");
+		}
+	}
+}
@@ -1,6 +1,6 @@
 use crate::{
 	xml::{optional_string, required_integer, required_string},
-	Error,
+	DateTime, Error,
 };
 use roxmltree::Document;
 
@@ -14,6 +14,8 @@ pub struct Root {
 	pub minor_version:       i64,
 	pub library_version:     Option<String>,
 	pub coordinate_metadata: Option<String>,
+	pub creation:            Option<DateTime>,
+	pub namespaces:          Vec<(String, String)>,
 }
 
 impl Default for Root {
@@ -24,7 +26,9 @@ impl Default for Root {
 			major_version:       1,
 			minor_version:       0,
 			coordinate_metadata: None,
+			creation:            None,
 			library_version:     None,
+			namespaces:          Vec::new(),
 		}
 	}
 }
@@ -41,11 +45,22 @@ pub fn root_from_document(document: &Document) -> Result<Root, Error> {
 	let format = required_string(&root, "formatName")?;
 	let guid = required_string(&root, "guid")?;
 	let major_version = required_integer(&root, "versionMajor")?;
-	let minor_version = required_integer(&root, "versionMajor")?;
+	let minor_version = required_integer(&root, "versionMinor")?;
 
 	// Optional fields
 	let coordinate_metadata = optional_string(&root, "coordinateMetadata")?;
 	let library_version = optional_string(&root, "e57LibraryVersion")?;
+	let creation = match root.children().find(|n| n.has_tag_name("creationDateTime")) {
+		Some(node) => Some(DateTime::from_node(&node)?),
+		None => None,
+	};
+
+	// Declared namespaces, the default namespace has an empty prefix
+	let namespaces = root
+		.namespaces()
+		.filter(|ns| ns.name() != Some("xml"))
+		.map(|ns| (ns.name().unwrap_or("").to_string(), ns.uri().to_string()))
+		.collect();
 
 	Ok(Root {
 		format,
@@ -53,6 +68,57 @@ pub fn root_from_document(document: &Document) -> Result<Root, Error> {
 		major_version,
 		minor_version,
 		coordinate_metadata,
+		creation,
 		library_version,
+		namespaces,
 	})
 }
+
+/// Extracts the EPSG code of the outermost authority in a WKT string.
+///
+/// Supports the `AUTHORITY["EPSG","1234"]` syntax of WKT 1 and the `ID["EPSG",1234]` syntax of WKT 2.
+pub fn epsg_from_wkt(wkt: &str) -> Option<u32> {
+	let mut code = None;
+	for keyword in ["AUTHORITY[", "ID["] {
+		for (start, _) in wkt.match_indices(keyword) {
+			if start > 0 && wkt.as_bytes()[start - 1].is_ascii_alphabetic() {
+				continue;
+			}
+			let rest = &wkt[start + keyword.len()..];
+			let end = match rest.find(']') {
+				Some(end) => end,
+				None => continue,
+			};
+			let mut parts = rest[..end].split(',').map(|p| p.trim().trim_matches('"').trim());
+			if !parts.next().is_some_and(|p| p.eq_ignore_ascii_case("EPSG")) {
+				continue;
+			}
+			if let Some(parsed) = parts.next().and_then(|p| p.parse::<u32>().ok()) {
+				if code.is_none_or(|(position, _)| position < start) {
+					code = Some((start, parsed));
+				}
+			}
+		}
+	}
+	code.map(|(_, code)| code)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn minor_version() {
+		let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+			<e57Root type="Structure" xmlns="http://www.astm.org/COMMIT/E57/2010-e57-v1.0">
+				<formatName type="String">ASTM E57 3D Imaging Data File</formatName>
+				<guid type="String">{file}</guid>
+				<versionMajor type="Integer">1</versionMajor>
+				<versionMinor type="Integer">1</versionMinor>
+			</e57Root>"#;
+		let document = Document::parse(xml).expect("Failed to parse XML");
+		let root = root_from_document(&document).expect("Failed to read root");
+		assert_eq!(root.major_version, 1);
+		assert_eq!(root.minor_version, 1);
+	}
+}
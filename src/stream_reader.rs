@@ -0,0 +1,277 @@
+use crate::error::INTERNAL_ERROR;
+use crate::images::images_from_document;
+use crate::paged_reader::PagedReader;
+use crate::pc_reader::SectionHeader;
+use crate::pointcloud::pointclouds_from_document;
+use crate::reader::{parse_document, xml_range, xml_to_string};
+use crate::root::root_from_document;
+use crate::root::Root;
+use crate::Error;
+use crate::Header;
+use crate::Image;
+use crate::PointCloud;
+use crate::RawValues;
+use crate::Reader;
+use crate::Record;
+use crate::RecordDataType;
+use crate::RecordValue;
+use std::io::{Read, Seek};
+
+const DATA_PACKET_HEADER_SIZE: usize = 6;
+
+/// Reads E57 files from any seekable source without mapping them into memory.
+///
+/// Works for sources that cannot be mapped, for example files on some network file systems.
+/// Pages are read one at a time and their checksums are validated while reading.
+/// Only the XML metadata and the raw values of the point clouds are available,
+/// use [`Reader`] for all other features.
+pub struct StreamReader<T: Read + Seek> {
+	reader:      PagedReader<T>,
+	header:      Header,
+	root:        Root,
+	pointclouds: Vec<PointCloud>,
+	images:      Vec<Image>,
+}
+
+impl Reader {
+	/// Creates a streaming reader for an E57 file from any seekable source, see [`StreamReader`].
+	///
+	/// The points are decoded sequentially from the source, without a memory map.
+	pub fn from_reader_streaming<T: Read + Seek>(reader: T) -> Result<StreamReader<T>, Error> {
+		StreamReader::new(reader)
+	}
+}
+
+impl<T: Read + Seek> StreamReader<T> {
+	/// Reads the header and the XML section of an E57 file.
+	pub fn new(reader: T) -> Result<Self, Error> {
+		let mut reader = PagedReader::new(reader)?;
+		let mut header = [0_u8; 48];
+		reader.read_physical(0, &mut header)?;
+		let header = Header::read(&mut &header[..])?;
+		let (xml_offset, _) = xml_range(&header, reader.physical_length() as usize)?;
+
+		let mut xml_raw = vec![0_u8; header.xml_length as usize];
+		reader.read_physical(xml_offset as u64, &mut xml_raw)?;
+		let xml = xml_to_string(xml_raw)?;
+		let document = parse_document(&xml)?;
+		let root = root_from_document(&document)?;
+		let pointclouds = pointclouds_from_document(&document)?;
+		let images = images_from_document(&document)?;
+		Ok(Self { reader, header, root, pointclouds, images })
+	}
+
+	/// Returns the contents of the E57 binary file header structure.
+	pub fn header(&self) -> Header {
+		self.header.clone()
+	}
+
+	/// Returns the GUID of the E57 file.
+	pub fn guid(&self) -> &str {
+		&self.root.guid
+	}
+
+	/// Returns the metadata of all point clouds.
+	///
+	/// Point clouds without a record count in the XML section have a record count of zero,
+	/// their points are decoded until the end of the binary section.
+	pub fn pointclouds(&self) -> Vec<PointCloud> {
+		self.pointclouds.clone()
+	}
+
+	/// Returns the metadata of all images.
+	pub fn images(&self) -> Vec<Image> {
+		self.images.clone()
+	}
+
+	/// Returns an iterator over the raw values of all points in prototype order.
+	///
+	/// Produces the same values as [`Reader::pointcloud_raw`].
+	pub fn pointcloud_raw(&mut self, pc: &PointCloud) -> Result<StreamRawValues<'_, T>, Error> {
+		StreamRawValues::new(pc, &mut self.reader)
+	}
+
+	/// Returns the underlying source.
+	pub fn into_inner(self) -> T {
+		self.reader.into_inner()
+	}
+}
+
+/// Collects the bytestream of a single record from consecutive data packets.
+struct BitStream {
+	bytes: Vec<u8>,
+	bit:   usize,
+}
+
+impl BitStream {
+	fn available(&self) -> usize {
+		self.bytes.len() * 8 - self.bit
+	}
+
+	fn extend(&mut self, data: &[u8]) {
+		self.bytes.drain(..self.bit / 8);
+		self.bit %= 8;
+		self.bytes.extend_from_slice(data);
+	}
+
+	/// Takes the next value with up to 64 bits, starting with the least significant bit.
+	fn take(&mut self, bits: u32) -> u64 {
+		let start = self.bit / 8;
+		let end = std::cmp::min(start + 16, self.bytes.len());
+		let mut window = [0_u8; 16];
+		window[..(end - start)].copy_from_slice(&self.bytes[start..end]);
+		let shift = self.bit % 8;
+		self.bit += bits as usize;
+		let mask = u64::MAX.checked_shr(u64::BITS - bits).unwrap_or(0);
+		(u128::from_le_bytes(window) >> shift) as u64 & mask
+	}
+}
+
+/// Iterator over the raw values of all points, created by [`StreamReader::pointcloud_raw`].
+///
+/// Data packets are read one after another and split into the bytestreams of the records.
+pub struct StreamRawValues<'a, T: Read + Seek> {
+	reader:    &'a mut PagedReader<T>,
+	prototype: Vec<Record>,
+	bits:      Vec<u32>,
+	streams:   Vec<BitStream>,
+	offset:    u64,
+	end:       u64,
+	remaining: Option<u64>,
+}
+
+impl<'a, T: Read + Seek> StreamRawValues<'a, T> {
+	fn new(pc: &PointCloud, reader: &'a mut PagedReader<T>) -> Result<Self, Error> {
+		let bits = pc.prototype.iter().map(|r| r.data_type.bit_width()).collect::<Vec<_>>();
+		if pc.infer_records && bits.iter().all(|b| *b == 0) {
+			return Error::Invalid(
+				"Cannot decode point cloud without record count, all records have constant values".into(),
+			)
+			.throw();
+		}
+		let mut buffer = [0_u8; 32];
+		reader.read_physical(pc.file_offset, &mut buffer)?;
+		let section = SectionHeader::parse(pc.file_offset, &buffer, reader.physical_length() as usize)?;
+		Ok(Self {
+			reader,
+			prototype: pc.prototype.clone(),
+			bits,
+			streams: pc
+				.prototype
+				.iter()
+				.map(|_| BitStream { bytes: Vec::new(), bit: 0 })
+				.collect(),
+			offset: section.logical_data_offset() as u64,
+			end: section.logical_end() as u64,
+			remaining: (!pc.infer_records).then_some(pc.records),
+		})
+	}
+
+	/// Reads the next data packet and appends its bytestreams, returns false at the end of the section.
+	///
+	/// Index packets are located after the data packets and end the data of the section.
+	fn load_packet(&mut self) -> Result<bool, Error> {
+		while self.offset + DATA_PACKET_HEADER_SIZE as u64 <= self.end {
+			let mut header = [0_u8; DATA_PACKET_HEADER_SIZE];
+			self.reader.read_logical(self.offset, &mut header)?;
+			let length = u16::from_le_bytes(header[2..4].try_into().expect(INTERNAL_ERROR)) as u64 + 1;
+			if self.offset + length > self.end {
+				return Error::Invalid(format!(
+					"Packet at offset {} extends past the end of the compressed vector section",
+					self.offset
+				))
+				.throw();
+			}
+			match header[0] {
+				0 => return Ok(false),
+				1 => {},
+				2 => {
+					self.offset += length;
+					continue;
+				},
+				id => return Error::Invalid(format!("Found unknown packet type ({id})")).throw(),
+			}
+
+			let mut packet = vec![0_u8; length as usize];
+			self.reader.read_logical(self.offset, &mut packet)?;
+			self.offset += length;
+			let count = u16::from_le_bytes(header[4..6].try_into().expect(INTERNAL_ERROR)) as usize;
+			if count != self.prototype.len() {
+				return Error::Invalid(format!(
+					"Data packet contains {count} bytestreams, but the prototype has {} records",
+					self.prototype.len()
+				))
+				.throw();
+			}
+			let mut start = DATA_PACKET_HEADER_SIZE + count * 2;
+			for (index, stream) in self.streams.iter_mut().enumerate() {
+				let size_offset = DATA_PACKET_HEADER_SIZE + index * 2;
+				let size = u16::from_le_bytes(packet[size_offset..size_offset + 2].try_into().expect(INTERNAL_ERROR));
+				let end = start + size as usize;
+				if end > packet.len() {
+					return Error::Invalid(format!("Bytestream {index} extends past the end of its data packet"))
+						.throw();
+				}
+				stream.extend(&packet[start..end]);
+				start = end;
+			}
+			return Ok(true);
+		}
+		Ok(false)
+	}
+
+	fn read_point(&mut self) -> Result<Option<RawValues>, Error> {
+		let missing = |streams: &[BitStream], bits: &[u32]| {
+			streams
+				.iter()
+				.zip(bits)
+				.any(|(stream, bits)| stream.available() < *bits as usize)
+		};
+		while missing(&self.streams, &self.bits) {
+			if !self.load_packet()? {
+				return match self.remaining {
+					None => Ok(None),
+					Some(_) => Error::Invalid(
+						"Reached the end of the compressed vector section before all records were read, \
+						 the section contains fewer points than its 'recordCount'"
+							.into(),
+					)
+					.throw(),
+				};
+			}
+		}
+
+		let mut values = Vec::with_capacity(self.prototype.len());
+		for ((record, bits), stream) in self.prototype.iter().zip(&self.bits).zip(&mut self.streams) {
+			let value = match record.data_type {
+				RecordDataType::Single { .. } => RecordValue::Single(f32::from_bits(stream.take(32) as u32)),
+				RecordDataType::Double { .. } => RecordValue::Double(f64::from_bits(stream.take(64))),
+				RecordDataType::ScaledInteger { min, .. } => {
+					RecordValue::ScaledInteger(min.wrapping_add(stream.take(*bits) as i64))
+				},
+				RecordDataType::Integer { min, .. } => {
+					RecordValue::Integer(min.wrapping_add(stream.take(*bits) as i64))
+				},
+			};
+			values.push(value);
+		}
+		Ok(Some(values))
+	}
+}
+
+impl<T: Read + Seek> Iterator for StreamRawValues<'_, T> {
+	type Item = Result<RawValues, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.remaining == Some(0) {
+			return None;
+		}
+		let point = self.read_point().transpose()?;
+		match point {
+			Ok(_) => self.remaining = self.remaining.map(|r| r - 1),
+			// Stop after the first error, the position in the bytestreams is undefined
+			Err(_) => self.remaining = Some(0),
+		}
+		Some(point)
+	}
+}
@@ -0,0 +1,161 @@
+use crate::pc_reader::SectionHeader;
+use crate::reader::xml_range;
+use crate::root::Root;
+use crate::xml::location;
+use crate::{Error, Header, Image, PointCloud, RecordName};
+use roxmltree::Document;
+
+const FORMAT_NAME: &str = "ASTM E57 3D Imaging Data File";
+
+/// Record names that must either all exist or all be missing in a prototype.
+const RECORD_GROUPS: [(&str, [RecordName; 3]); 3] = [
+	(
+		"Cartesian",
+		[RecordName::CartesianX, RecordName::CartesianY, RecordName::CartesianZ],
+	),
+	(
+		"spherical",
+		[
+			RecordName::SphericalRange,
+			RecordName::SphericalAzimuth,
+			RecordName::SphericalElevation,
+		],
+	),
+	(
+		"color",
+		[RecordName::ColorRed, RecordName::ColorGreen, RecordName::ColorBlue],
+	),
+];
+
+/// Runs the additional structural checks of the strict mode, see [`crate::Reader::open_strict`].
+pub(crate) fn check_structure(
+	file_length: usize,
+	header: &Header,
+	document: &Document,
+	root: &Root,
+	pointclouds: &[PointCloud],
+) -> Result<(), Error> {
+	if header.phys_length != file_length as u64 {
+		return Error::Invalid(format!(
+			"Header declares a file length of {} bytes, but the file has {file_length} bytes",
+			header.phys_length
+		))
+		.throw();
+	}
+	if root.format != FORMAT_NAME {
+		return Error::Invalid(format!("Format name '{}' is not '{FORMAT_NAME}'", root.format)).throw();
+	}
+	if root.major_version != 1 {
+		return Error::Invalid(format!("Major version {} is not 1", root.major_version)).throw();
+	}
+
+	let root_node = document.root_element();
+	for tag_name in ["data3D", "images2D"] {
+		let found = root_node
+			.children()
+			.any(|n| n.has_tag_name(tag_name) && n.attribute("type") == Some("Vector"));
+		if !found {
+			return Error::Invalid(format!(
+				"Cannot find '{tag_name}' tag with type 'Vector' in 'e57Root' at {}",
+				location(&root_node)
+			))
+			.throw();
+		}
+	}
+	for points in root_node.descendants().filter(|n| n.has_tag_name("points")) {
+		if !points.children().any(|n| n.has_tag_name("codecs")) {
+			return Error::Invalid(format!(
+				"Cannot find 'codecs' child in 'points' tag at {}",
+				location(&points)
+			))
+			.throw();
+		}
+	}
+
+	for (index, pc) in pointclouds.iter().enumerate() {
+		if pc.guid == root.guid || pointclouds[..index].iter().any(|other| other.guid == pc.guid) {
+			return Error::Invalid(format!("GUID '{}' of point cloud {index} is not unique", pc.guid)).throw();
+		}
+		if pc.prototype.is_empty() {
+			return Error::Invalid(format!("Prototype of point cloud '{}' is empty", pc.guid)).throw();
+		}
+		for (i, record) in pc.prototype.iter().enumerate() {
+			if pc.prototype[..i].iter().any(|r| r.name == record.name) {
+				return Error::Invalid(format!(
+					"Prototype of point cloud '{}' contains the record {:?} more than once",
+					pc.guid, record.name
+				))
+				.throw();
+			}
+		}
+		for (group, names) in RECORD_GROUPS {
+			let count = names
+				.iter()
+				.filter(|name| pc.prototype.iter().any(|r| r.name == **name))
+				.count();
+			if count != 0 && count != names.len() {
+				return Error::Invalid(format!(
+					"Prototype of point cloud '{}' contains only some of the {group} records",
+					pc.guid
+				))
+				.throw();
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Checks that the header, the XML section, all binary sections of the point clouds and all image blobs do not overlap.
+///
+/// Point clouds without records and line groupings without groups are never read,
+/// so their sections are ignored.
+pub(crate) fn check_sections(
+	mmap: &memmap2::Mmap,
+	header: &Header,
+	pointclouds: &[PointCloud],
+	images: &[Image],
+) -> Result<(), Error> {
+	let (xml_offset, xml_end) = xml_range(header, mmap.len())?;
+	let mut ranges = vec![
+		(0, 48, String::from("file header")),
+		(xml_offset, xml_end, String::from("XML section")),
+	];
+	for pc in pointclouds {
+		if pc.records > 0 {
+			let section = SectionHeader::read(pc.file_offset, mmap)?;
+			ranges.push((
+				pc.file_offset as usize,
+				section.physical_end(),
+				format!("points of point cloud '{}'", pc.guid),
+			));
+		}
+		if let Some(grouping) = pc.line_grouping.as_ref().filter(|g| g.groups > 0) {
+			let section = SectionHeader::read(grouping.file_offset, mmap)?;
+			ranges.push((
+				grouping.file_offset as usize,
+				section.physical_end(),
+				format!("line groups of point cloud '{}'", pc.guid),
+			));
+		}
+	}
+	for image in images {
+		for (format, blob) in [("JPEG", &image.jpeg), ("PNG", &image.png)] {
+			if let Some(blob) = blob {
+				let (start, end) = blob.physical_range(mmap)?;
+				ranges.push((start, end, format!("{format} data of image '{}'", image.guid)));
+			}
+		}
+	}
+	ranges.sort_by_key(|(start, end, _)| (*start, *end));
+	for pair in ranges.windows(2) {
+		let (first_start, first_end, first) = &pair[0];
+		let (second_start, _, second) = &pair[1];
+		if second_start < first_end {
+			return Error::Invalid(format!(
+				"Binary section with the {second} at offset {second_start} overlaps the {first} from offset {first_start} to {first_end}"
+			))
+			.throw();
+		}
+	}
+	Ok(())
+}
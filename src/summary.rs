@@ -0,0 +1,24 @@
+use crate::DateTime;
+
+/// Short description of an E57 file, created by [`crate::Reader::summary`].
+///
+/// Contains only information from the XML section, no points are decoded.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct FileSummary {
+	/// Format name stored in the XML section.
+	pub format_name:             String,
+	/// GUID of the file.
+	pub guid:                    String,
+	/// Number of point clouds.
+	pub pointclouds:             usize,
+	/// Number of images.
+	pub images:                  usize,
+	/// Total number of points in all point clouds.
+	pub points:                  u64,
+	/// Optional date and time when the file was created.
+	pub creation:                Option<DateTime>,
+	/// The file contains metadata describing the coordinate reference system.
+	pub has_coordinate_metadata: bool,
+}
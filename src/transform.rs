@@ -3,7 +3,8 @@ use crate::Error;
 use roxmltree::Node;
 
 /// Describes the rotation of a point cloud.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quaternion {
 	/// The scalar part of the quaternion. Shall be nonnegative.
 	pub w: f64,
@@ -16,7 +17,8 @@ pub struct Quaternion {
 }
 
 /// Describes the translation of a point cloud.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Translation {
 	/// The X coordinate of the translation in meters.
 	pub x: f64,
@@ -27,7 +29,8 @@ pub struct Translation {
 }
 
 /// Describes a transformation of a point cloud with a rotation and translation component.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transform {
 	/// A unit quaternion representing the rotation of the transform.
 	pub rotation:    Quaternion,
@@ -35,13 +38,115 @@ pub struct Transform {
 	pub translation: Translation,
 }
 
+impl Quaternion {
+	/// Returns the identity rotation.
+	pub fn identity() -> Self {
+		Self { w: 1.0, x: 0.0, y: 0.0, z: 0.0 }
+	}
+
+	/// Scales the quaternion to unit length with a nonnegative scalar part, as required by the E57 standard.
+	///
+	/// A negated quaternion describes the same rotation, so the result rotates points identically.
+	/// Returns the identity rotation for quaternions with zero or non-finite length.
+	pub fn normalize(&self) -> Self {
+		let length = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+		if length == 0.0 || !length.is_finite() {
+			return Self::identity();
+		}
+		let length = if self.w < 0.0 { -length } else { length };
+		Self {
+			w: self.w / length,
+			x: self.x / length,
+			y: self.y / length,
+			z: self.z / length,
+		}
+	}
+
+	/// Rotates a point, the quaternion is expected to have unit length.
+	pub fn rotate_point(&self, point: [f64; 3]) -> [f64; 3] {
+		let cross = |a: [f64; 3], b: [f64; 3]| {
+			[
+				a[1] * b[2] - a[2] * b[1],
+				a[2] * b[0] - a[0] * b[2],
+				a[0] * b[1] - a[1] * b[0],
+			]
+		};
+		let q = [self.x, self.y, self.z];
+		let t = cross(q, point).map(|v| 2.0 * v);
+		let u = cross(q, t);
+		[
+			point[0] + self.w * t[0] + u[0],
+			point[1] + self.w * t[1] + u[1],
+			point[2] + self.w * t[2] + u[2],
+		]
+	}
+
+	/// Returns the rotation as row-major 3x3 matrix, the quaternion is expected to have unit length.
+	pub fn to_matrix(&self) -> [[f64; 3]; 3] {
+		let Self { w, x, y, z } = *self;
+		[
+			[
+				1.0 - 2.0 * (y * y + z * z),
+				2.0 * (x * y - w * z),
+				2.0 * (x * z + w * y),
+			],
+			[
+				2.0 * (x * y + w * z),
+				1.0 - 2.0 * (x * x + z * z),
+				2.0 * (y * z - w * x),
+			],
+			[
+				2.0 * (x * z - w * y),
+				2.0 * (y * z + w * x),
+				1.0 - 2.0 * (x * x + y * y),
+			],
+		]
+	}
+}
+
+impl Transform {
+	/// Rotates and then translates a point.
+	pub fn apply(&self, point: [f64; 3]) -> [f64; 3] {
+		let [x, y, z] = self.rotation.rotate_point(point);
+		[x + self.translation.x, y + self.translation.y, z + self.translation.z]
+	}
+
+	/// Returns the transformation as row-major 4x4 matrix for homogeneous coordinates.
+	pub fn to_matrix(&self) -> [[f64; 4]; 4] {
+		let [r0, r1, r2] = self.rotation.to_matrix();
+		[
+			[r0[0], r0[1], r0[2], self.translation.x],
+			[r1[0], r1[1], r1[2], self.translation.y],
+			[r2[0], r2[1], r2[2], self.translation.z],
+			[0.0, 0.0, 0.0, 1.0],
+		]
+	}
+}
+
+/// Converts spherical coordinates to Cartesian coordinates.
+///
+/// The coordinates are given as range, azimuth and elevation, with the angles in radians.
+/// The azimuth is measured in the XY plane from the X axis towards the Y axis,
+/// the elevation is measured from the XY plane towards the Z axis.
+/// Returns `None` if the range is negative or any coordinate is not finite.
+pub fn spherical_to_cartesian([range, azimuth, elevation]: [f64; 3]) -> Option<[f64; 3]> {
+	if range < 0.0 || !range.is_finite() || !azimuth.is_finite() || !elevation.is_finite() {
+		return None;
+	}
+	Some([
+		range * elevation.cos() * azimuth.cos(),
+		range * elevation.cos() * azimuth.sin(),
+		range * elevation.sin(),
+	])
+}
+
 pub fn transform_from_node(node: &Node) -> Result<Transform, Error> {
 	let translation = match node.children().find(|n| n.has_tag_name("translation")) {
 		Some(node) => translation_from_node(&node)?,
 		None => Translation { x: 0.0, y: 0.0, z: 0.0 },
 	};
 	let rotation = match node.children().find(|n| n.has_tag_name("rotation")) {
-		None => Quaternion { w: 1.0, x: 0.0, y: 0.0, z: 0.0 },
+		None => Quaternion::identity(),
 		Some(node) => quaternion_from_node(&node)?,
 	};
 	Ok(Transform { rotation, translation })
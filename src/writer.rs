@@ -0,0 +1,476 @@
+use crate::crc32::crc32c;
+use crate::paged_writer::PagedWriter;
+use crate::pc_writer::{point_reals, point_values, points_per_packet, write_section};
+use crate::CartesianBounds;
+use crate::Error;
+use crate::Header;
+use crate::Point;
+use crate::PointCloud;
+use crate::RawValues;
+use crate::Reader;
+use crate::Record;
+use crate::RecordDataType;
+use crate::RecordName;
+use crate::RecordValue;
+use std::collections::hash_map::RandomState;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const HEADER_SIZE: usize = 48;
+
+/// Main interface for creating E57 files.
+///
+/// The file is only valid after calling [`E57Writer::finalize`],
+/// which writes the XML section and the final header.
+/// Dropping the writer without finalizing leaves an incomplete file.
+///
+/// If writing the points of a point cloud fails, the already written packets cannot be removed again.
+/// The writer is poisoned in that case and all further calls return an error.
+pub struct E57Writer<T: Read + Write + Seek> {
+	writer:      PagedWriter<T>,
+	guid:        String,
+	pointclouds: Vec<PointCloud>,
+	poisoned:    bool,
+}
+
+impl E57Writer<File> {
+	/// Creates a new E57 file at the given path, an existing file is overwritten.
+	pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+		let file = File::options()
+			.read(true)
+			.write(true)
+			.create(true)
+			.truncate(true)
+			.open(path)?;
+		Self::new(file)
+	}
+}
+
+/// Writes a new E57 file with a single point cloud of Cartesian coordinates stored as doubles.
+///
+/// The file and the point cloud get random GUIDs and the bounds of the positions are written as metadata.
+/// An existing file is overwritten.
+pub fn write_xyz(path: impl AsRef<Path>, points: &[[f64; 3]]) -> Result<(), Error> {
+	let prototype = vec![
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+	];
+	let mut builder = PointCloud::builder(random_guid()).prototype(prototype);
+	if !points.is_empty() {
+		let mut bounds = CartesianBounds::default();
+		points.iter().for_each(|&position| extend_bounds(&mut bounds, position));
+		builder = builder.cartesian_bounds(bounds);
+	}
+	let mut writer = E57Writer::from_path(path)?;
+	let values = points.iter().map(|p| p.map(RecordValue::Double).to_vec());
+	writer.add_pointcloud(builder.build()?, values)?;
+	writer.finalize()?;
+	Ok(())
+}
+
+/// Writes the points into memory with [`E57Writer::write_points`], reads them back and compares them.
+///
+/// Useful to check if a prototype can represent the points with the required precision.
+/// All values are compared in the units of their records, with scaled integers multiplied with their scale:
+/// - Double and integer records must match exactly, so values of integer records must be integral.
+/// - Single records must match the value rounded to single precision.
+/// - Scaled integer records may differ by up to one scale step.
+/// - Normalized intensities and colors are mapped to the range of their records before comparing,
+///   with integer records they may differ by up to one step.
+///
+/// Fails if writing or reading fails or with the first value outside of the tolerance.
+pub fn verify_roundtrip(points: &[Point], prototype: &[Record]) -> Result<(), Error> {
+	let mut writer = E57Writer::new(Cursor::new(Vec::new()))?;
+	writer.write_points(prototype, points.iter().cloned())?;
+	let bytes = writer.finalize()?.into_inner();
+	let mut reader = Reader::from_bytes(&bytes)?;
+	let pc = reader.pointclouds()[0].clone();
+	let mut read = reader.pointcloud_raw(&pc)?;
+	for (index, point) in points.iter().enumerate() {
+		let values = match read.next() {
+			Some(values) => values?,
+			None => return Error::Invalid(format!("Read back only {index} of {} points", points.len())).throw(),
+		};
+		let expected = point_reals(point, prototype)?;
+		for ((record, value), expected) in prototype.iter().zip(&values).zip(expected) {
+			let actual = value.as_f64_scaled(&record.data_type)?;
+			let normalized = matches!(
+				record.name,
+				RecordName::Intensity | RecordName::ColorRed | RecordName::ColorGreen | RecordName::ColorBlue
+			);
+			let (expected, tolerance) = match record.data_type {
+				RecordDataType::Single { .. } => (expected as f32 as f64, 0.0),
+				RecordDataType::Double { .. } => (expected, 0.0),
+				RecordDataType::ScaledInteger { scale, .. } => (expected, scale),
+				RecordDataType::Integer { .. } if normalized => (expected, 1.0),
+				RecordDataType::Integer { .. } => (expected, 0.0),
+			};
+			let same = (actual - expected).abs() <= tolerance || (actual.is_nan() && expected.is_nan());
+			if !same {
+				return Error::Invalid(format!(
+					"Point {index} has the value {actual} for record {:?} instead of {expected}",
+					record.name
+				))
+				.throw();
+			}
+		}
+	}
+	if read.next().is_some() {
+		return Error::Invalid(format!("Read back more than {} points", points.len())).throw();
+	}
+	Ok(())
+}
+
+impl<T: Read + Write + Seek> E57Writer<T> {
+	/// Creates a new E57 writer with a random file GUID.
+	///
+	/// The writer must be readable and seekable, because already written pages are patched with the final offsets.
+	pub fn new(writer: T) -> Result<Self, Error> {
+		let mut writer = PagedWriter::new(writer);
+		// Placeholder, the actual header is written when finalizing
+		writer.write_all(&[0; HEADER_SIZE])?;
+		Ok(Self {
+			writer,
+			guid: random_guid(),
+			pointclouds: Vec::new(),
+			poisoned: false,
+		})
+	}
+
+	/// Writes a point cloud with the raw values of all points in prototype order.
+	///
+	/// The metadata of the point cloud is written to the XML section,
+	/// the file offset and the number of records are replaced with the written values.
+	/// Line groupings refer to a binary section of their source file and are not written.
+	///
+	/// Fails if the GUID is not unique, the prototype is invalid
+	/// or a value does not match the data type and range of its record.
+	/// An invalid value or an IO error poisons the writer, because the section is already partially written.
+	pub fn add_pointcloud(&mut self, pc: PointCloud, points: impl Iterator<Item = RawValues>) -> Result<(), Error> {
+		self.write_pointcloud(pc, points.map(Ok))?;
+		Ok(())
+	}
+
+	/// Writes a point cloud with a random GUID from points of the built-in point type.
+	///
+	/// The points are converted and packed while iterating, without buffering the whole point cloud.
+	/// The Cartesian bounds of all points with the Cartesian invalid state 0 are written as metadata.
+	/// Fails like [`E57Writer::add_pointcloud`] or if a point has no value for a record of the prototype.
+	pub fn write_points(&mut self, prototype: &[Record], points: impl Iterator<Item = Point>) -> Result<(), Error> {
+		let pc = PointCloud::builder(random_guid())
+			.prototype(prototype.to_vec())
+			.build()?;
+		let cartesian = prototype.iter().any(|r| r.name == RecordName::CartesianX);
+		let mut bounds = None;
+		let values = points.map(|point| {
+			if cartesian && point.cartesian_invalid == 0 {
+				extend_bounds(bounds.get_or_insert_with(CartesianBounds::default), point.position);
+			}
+			point_values(&point, prototype)
+		});
+		let pc = self.write_pointcloud(pc, values)?;
+		pc.cartesian_bounds = bounds;
+		Ok(())
+	}
+
+	fn write_pointcloud(
+		&mut self,
+		mut pc: PointCloud,
+		points: impl Iterator<Item = Result<RawValues, Error>>,
+	) -> Result<&mut PointCloud, Error> {
+		self.check_poisoned()?;
+		if pc.guid == self.guid || self.pointclouds.iter().any(|other| other.guid == pc.guid) {
+			return Error::Invalid(format!("Point cloud GUID '{}' is not unique", pc.guid)).throw();
+		}
+		pc.validate()?;
+		let points_per_packet = points_per_packet(&pc.prototype)?;
+		let (file_offset, records) = write_section(&mut self.writer, &pc.prototype, points_per_packet, points)
+			.inspect_err(|_| {
+				self.poisoned = true;
+			})?;
+		pc.file_offset = file_offset;
+		pc.records = records;
+		pc.infer_records = false;
+		pc.line_grouping = None;
+		self.pointclouds.push(pc);
+		Ok(self.pointclouds.last_mut().expect("Point cloud was just added"))
+	}
+
+	/// Writes the XML section and the header and returns the underlying writer.
+	///
+	/// Any IO error is returned, in contrast to errors while dropping an unfinished writer.
+	/// The header and the XML section are read back and compared with the written data,
+	/// including the checksums of the XML pages. Point cloud sections are not read back.
+	pub fn finalize(self) -> Result<T, Error> {
+		let xml = self.xml();
+		self.finalize_with_xml(&xml)
+	}
+
+	/// Creates a writer that appends sections to the existing pages of a file with the given GUID.
+	pub(crate) fn resume(writer: T, guid: String, physical_length: u64) -> Result<Self, Error> {
+		Ok(Self {
+			writer: PagedWriter::resume(writer, physical_length)?,
+			guid,
+			pointclouds: Vec::new(),
+			poisoned: false,
+		})
+	}
+
+	/// Writes the given XML section and the header, like [`E57Writer::finalize`].
+	pub(crate) fn finalize_with_xml(mut self, xml: &str) -> Result<T, Error> {
+		self.check_poisoned()?;
+		self.writer.align(4)?;
+		let xml_offset = self.writer.physical_position();
+		self.writer.write_all(xml.as_bytes())?;
+		let header = Header::new(self.writer.physical_length(), xml_offset, xml.len() as u64);
+		self.writer.write_at(0, &header.to_bytes())?;
+		let mut writer = self.writer.finish()?;
+		verify(&mut writer, &header, xml.as_bytes())?;
+		Ok(writer)
+	}
+
+	fn check_poisoned(&self) -> Result<(), Error> {
+		if self.poisoned {
+			return Error::Invalid("Writer is poisoned by a previous error while writing a point cloud".into()).throw();
+		}
+		Ok(())
+	}
+
+	/// Returns the prefixes and URIs of the extension namespaces used by the written point clouds.
+	pub(crate) fn namespaces(&self) -> Vec<(&'static str, &'static str)> {
+		let mut namespaces = Vec::new();
+		for record in self.pointclouds.iter().flat_map(|pc| &pc.prototype) {
+			if let Some(namespace) = record.name.namespace() {
+				if !namespaces.contains(&namespace) {
+					namespaces.push(namespace);
+				}
+			}
+		}
+		namespaces
+	}
+
+	/// Serializes the written point clouds as children of the `data3D` vector.
+	pub(crate) fn data3d_xml(&self) -> String {
+		let mut xml = String::new();
+		for pc in &self.pointclouds {
+			xml += "<vectorChild type=\"Structure\">";
+			xml += &pointcloud_xml(pc);
+			xml += &format!(
+				"<points type=\"CompressedVector\" fileOffset=\"{}\" recordCount=\"{}\">",
+				pc.file_offset, pc.records
+			);
+			xml += "<prototype type=\"Structure\">";
+			for record in &pc.prototype {
+				xml += &record.data_type.xml_tag(record.name.tag_name());
+			}
+			xml += "</prototype>";
+			xml += "<codecs type=\"Vector\" allowHeterogeneousChildren=\"1\"/>";
+			xml += "</points>";
+			xml += "</vectorChild>";
+		}
+		xml
+	}
+
+	fn xml(&self) -> String {
+		let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+		xml += "<e57Root type=\"Structure\" xmlns=\"http://www.astm.org/COMMIT/E57/2010-e57-v1.0\"";
+		for (prefix, uri) in self.namespaces() {
+			xml += &format!(" xmlns:{prefix}=\"{uri}\"");
+		}
+		xml += ">";
+		xml += "<formatName type=\"String\">ASTM E57 3D Imaging Data File</formatName>";
+		xml += &format!("<guid type=\"String\">{}</guid>", escape(&self.guid));
+		xml += "<versionMajor type=\"Integer\">1</versionMajor>";
+		xml += "<versionMinor type=\"Integer\">0</versionMinor>";
+		xml += &format!(
+			"<e57LibraryVersion type=\"String\">e57 {}</e57LibraryVersion>",
+			env!("CARGO_PKG_VERSION")
+		);
+		xml += "<data3D type=\"Vector\" allowHeterogeneousChildren=\"1\">";
+		xml += &self.data3d_xml();
+		xml += "</data3D>";
+		xml += "<images2D type=\"Vector\" allowHeterogeneousChildren=\"1\"/>";
+		xml += "</e57Root>";
+		xml
+	}
+}
+
+/// Serializes the metadata of a point cloud, without the points and their prototype.
+fn pointcloud_xml(pc: &PointCloud) -> String {
+	let mut xml = string_tag("guid", Some(&pc.guid));
+	xml += &string_tag("name", pc.name.as_ref());
+	xml += &string_tag("description", pc.description.as_ref());
+	xml += &string_tag("sensorVendor", pc.sensor_vendor.as_ref());
+	xml += &string_tag("sensorModel", pc.sensor_model.as_ref());
+	xml += &string_tag("sensorSerialNumber", pc.sensor_serial.as_ref());
+	xml += &string_tag("sensorHardwareVersion", pc.sensor_hw_version.as_ref());
+	xml += &string_tag("sensorSoftwareVersion", pc.sensor_sw_version.as_ref());
+	xml += &string_tag("sensorFirmwareVersion", pc.sensor_fw_version.as_ref());
+	xml += &float_tag("temperature", pc.temperature);
+	xml += &float_tag("relativeHumidity", pc.humidity);
+	xml += &float_tag("atmosphericPressure", pc.atmospheric_pressure);
+	for (tag, time) in [
+		("acquisitionStart", &pc.acquisition_start),
+		("acquisitionEnd", &pc.acquisition_end),
+	] {
+		if let Some(time) = time {
+			xml += &format!("<{tag} type=\"Structure\">");
+			xml += &float_tag("dateTimeValue", Some(time.gps_time));
+			xml += &integer_tag("isAtomicClockReferenced", Some(time.atomic_reference as i64));
+			xml += &format!("</{tag}>");
+		}
+	}
+	if let Some(transform) = &pc.transform {
+		let (r, t) = (&transform.rotation, &transform.translation);
+		xml += "<pose type=\"Structure\">";
+		xml += "<rotation type=\"Structure\">";
+		xml += &float_tag("w", Some(r.w));
+		xml += &float_tag("x", Some(r.x));
+		xml += &float_tag("y", Some(r.y));
+		xml += &float_tag("z", Some(r.z));
+		xml += "</rotation>";
+		xml += "<translation type=\"Structure\">";
+		xml += &float_tag("x", Some(t.x));
+		xml += &float_tag("y", Some(t.y));
+		xml += &float_tag("z", Some(t.z));
+		xml += "</translation>";
+		xml += "</pose>";
+	}
+	if let Some(b) = &pc.cartesian_bounds {
+		xml += "<cartesianBounds type=\"Structure\">";
+		xml += &float_tag("xMinimum", b.x_min);
+		xml += &float_tag("xMaximum", b.x_max);
+		xml += &float_tag("yMinimum", b.y_min);
+		xml += &float_tag("yMaximum", b.y_max);
+		xml += &float_tag("zMinimum", b.z_min);
+		xml += &float_tag("zMaximum", b.z_max);
+		xml += "</cartesianBounds>";
+	}
+	if let Some(b) = &pc.spherical_bounds {
+		xml += "<sphericalBounds type=\"Structure\">";
+		xml += &float_tag("rangeMinimum", b.range_min);
+		xml += &float_tag("rangeMaximum", b.range_max);
+		xml += &float_tag("elevationMinimum", b.elevation_min);
+		xml += &float_tag("elevationMaximum", b.elevation_max);
+		xml += &float_tag("azimuthStart", b.azimuth_start);
+		xml += &float_tag("azimuthEnd", b.azimuth_end);
+		xml += "</sphericalBounds>";
+	}
+	if let Some(b) = &pc.index_bounds {
+		xml += "<indexBounds type=\"Structure\">";
+		xml += &integer_tag("rowMinimum", b.row_min);
+		xml += &integer_tag("rowMaximum", b.row_max);
+		xml += &integer_tag("columnMinimum", b.column_min);
+		xml += &integer_tag("columnMaximum", b.column_max);
+		xml += &integer_tag("returnMinimum", b.return_min);
+		xml += &integer_tag("returnMaximum", b.return_max);
+		xml += "</indexBounds>";
+	}
+	if let Some(l) = &pc.intensity_limits {
+		xml += "<intensityLimits type=\"Structure\">";
+		xml += &float_tag("intensityMinimum", l.intensity_min);
+		xml += &float_tag("intensityMaximum", l.intensity_max);
+		xml += "</intensityLimits>";
+	}
+	xml
+}
+
+fn string_tag(tag: &str, value: Option<&String>) -> String {
+	value
+		.map(|v| format!("<{tag} type=\"String\">{}</{tag}>", escape(v)))
+		.unwrap_or_default()
+}
+
+fn float_tag(tag: &str, value: Option<f64>) -> String {
+	value
+		.map(|v| format!("<{tag} type=\"Float\">{v}</{tag}>"))
+		.unwrap_or_default()
+}
+
+fn integer_tag(tag: &str, value: Option<i64>) -> String {
+	value
+		.map(|v| format!("<{tag} type=\"Integer\">{v}</{tag}>"))
+		.unwrap_or_default()
+}
+
+/// Reads back the header and the XML section of a finished file and compares them with the written data.
+fn verify<T: Read + Seek>(reader: &mut T, header: &Header, xml: &[u8]) -> Result<(), Error> {
+	let length = reader.seek(SeekFrom::End(0))?;
+	if length != header.phys_length {
+		return Error::Invalid(format!(
+			"Written file has {length} bytes, but the header declares {} bytes",
+			header.phys_length
+		))
+		.throw();
+	}
+	reader.seek(SeekFrom::Start(0))?;
+	if Header::read(reader)? != *header {
+		return Error::Invalid("Written header does not match the expected header".into()).throw();
+	}
+
+	let page_size = header.page_size;
+	let logical_page_size = (page_size - 4) as usize;
+	let mut page = vec![0_u8; page_size as usize];
+	let mut index = header.phys_xml_offset / page_size;
+	let mut start = (header.phys_xml_offset % page_size) as usize;
+	let mut read = Vec::with_capacity(xml.len());
+	while read.len() < xml.len() {
+		reader.seek(SeekFrom::Start(index * page_size))?;
+		reader.read_exact(&mut page)?;
+		let (data, crc) = page.split_at(logical_page_size);
+		if crc32c(data).to_be_bytes() != crc {
+			return Error::Invalid(format!("Written page {index} has an invalid checksum")).throw();
+		}
+		let available = std::cmp::min(xml.len() - read.len(), logical_page_size - start);
+		read.extend_from_slice(&data[start..(start + available)]);
+		index += 1;
+		start = 0;
+	}
+	reader.seek(SeekFrom::End(0))?;
+	if read != xml {
+		return Error::Invalid("Written XML section does not match the expected XML".into()).throw();
+	}
+	Ok(())
+}
+
+/// Extends the bounds to contain the position, the bounds are either complete or empty.
+fn extend_bounds(bounds: &mut CartesianBounds, [x, y, z]: [f64; 3]) {
+	let extend = |min: &mut Option<f64>, max: &mut Option<f64>, value: f64| {
+		*min = Some(min.map_or(value, |min| min.min(value)));
+		*max = Some(max.map_or(value, |max| max.max(value)));
+	};
+	extend(&mut bounds.x_min, &mut bounds.x_max, x);
+	extend(&mut bounds.y_min, &mut bounds.y_max, y);
+	extend(&mut bounds.z_min, &mut bounds.z_max, z);
+}
+
+/// Escapes the special characters of XML text content.
+fn escape(text: &str) -> String {
+	text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Creates a random GUID without any additional dependencies.
+fn random_guid() -> String {
+	let random = || {
+		let mut hasher = RandomState::new().build_hasher();
+		hasher.write_u128(
+			std::time::SystemTime::now()
+				.duration_since(std::time::UNIX_EPOCH)
+				.map(|d| d.as_nanos())
+				.unwrap_or_default(),
+		);
+		hasher.finish()
+	};
+	let (a, b) = (random(), random());
+	format!(
+		"{{{:08X}-{:04X}-4{:03X}-{:04X}-{:012X}}}",
+		a >> 32,
+		(a >> 16) & 0xFFFF,
+		a & 0x0FFF,
+		(b >> 48) & 0x3FFF | 0x8000,
+		b & 0xFFFF_FFFF_FFFF
+	)
+}
@@ -2,20 +2,32 @@ use crate::{transform::transform_from_node, Error, Transform};
 use roxmltree::Node;
 use std::str::FromStr;
 
+/// Describes the position of a node inside the XML document for error messages.
+pub fn location(node: &Node) -> String {
+	let position = node.document().text_pos_at(node.range().start);
+	format!("line {}, column {}", position.row, position.col)
+}
+
 pub fn optional_string(parent_node: &Node, tag_name: &str) -> Result<Option<String>, Error> {
 	if let Some(tag) = parent_node.children().find(|n| n.has_tag_name(tag_name)) {
 		let expected_type = "String";
 		if let Some(found_type) = tag.attribute("type") {
 			if found_type != expected_type {
 				return Error::Invalid(format!(
-					"Found XML tag '{tag_name}' with type '{found_type}' instead of '{expected_type}'"
+					"Found XML tag '{tag_name}' with type '{found_type}' instead of '{expected_type}' at {}",
+					location(&tag)
 				))
 				.throw();
 			}
 		} else {
-			return Error::Invalid(format!("XML tag '{tag_name}' has no 'type' attribute")).throw();
+			return Error::Invalid(format!(
+				"XML tag '{tag_name}' has no 'type' attribute at {}",
+				location(&tag)
+			))
+			.throw();
 		}
-		let text = tag.text().unwrap_or("");
+		// Pretty-printed XML can contain indentation and line breaks around the value
+		let text = tag.text().unwrap_or("").trim();
 		Ok(Some(text.to_string()))
 	} else {
 		Ok(None)
@@ -23,9 +35,7 @@ pub fn optional_string(parent_node: &Node, tag_name: &str) -> Result<Option<Stri
 }
 
 pub fn required_string(parent_node: &Node, tag_name: &str) -> Result<String, Error> {
-	optional_string(parent_node, tag_name)?.ok_or(Error::Invalid(format!(
-		"XML tag '{tag_name}' was not found"
-	)))
+	optional_string(parent_node, tag_name)?.ok_or_else(|| not_found(parent_node, tag_name))
 }
 
 fn optional_number<T: FromStr + Sync + Send>(
@@ -38,23 +48,29 @@ fn optional_number<T: FromStr + Sync + Send>(
 		None => return Ok(None),
 	};
 
-	
 	if let Some(found_type) = tag.attribute("type") {
 		if found_type != expected_type {
 			return Error::Invalid(format!(
-				"Found XML tag '{tag_name}' with type '{found_type}' instead of '{expected_type}'"
+				"Found XML tag '{tag_name}' with type '{found_type}' instead of '{expected_type}' at {}",
+				location(&tag)
 			))
 			.throw();
 		}
 	} else {
-		return Error::Invalid(format!("XML tag '{tag_name}' has no 'type' attribute")).throw();
+		return Error::Invalid(format!(
+			"XML tag '{tag_name}' has no 'type' attribute at {}",
+			location(&tag)
+		))
+		.throw();
 	}
-	let text = tag.text().unwrap_or("0");
+	// Pretty-printed XML can contain indentation and line breaks around the value
+	let text = tag.text().map(str::trim).filter(|t| !t.is_empty()).unwrap_or("0");
 	if let Ok(parsed) = text.parse::<T>() {
 		Ok(Some(parsed))
 	} else {
 		Error::Invalid(format!(
-			"Cannot parse value '{text}' of XML tag '{tag_name}' as '{expected_type}'"
+			"Cannot parse value '{text}' of XML tag '{tag_name}' as '{expected_type}' at {}",
+			location(&tag)
 		))
 		.throw()
 	}
@@ -65,9 +81,35 @@ pub fn optional_double(parent_node: &Node, tag_name: &str) -> Result<Option<f64>
 }
 
 pub fn required_double(parent_node: &Node, tag_name: &str) -> Result<f64, Error> {
-	optional_number(parent_node, tag_name, "Float")?.ok_or(Error::Invalid(format!(
-		"XML tag '{tag_name}' was not found"
-	)))
+	optional_number(parent_node, tag_name, "Float")?.ok_or_else(|| not_found(parent_node, tag_name))
+}
+
+/// Reads a numeric value stored as `Float`, `Integer` or `ScaledInteger` and converts it to `f64`.
+///
+/// Scaled integers are multiplied with their `scale` attribute and shifted by their `offset` attribute.
+pub fn optional_real(parent_node: &Node, tag_name: &str) -> Result<Option<f64>, Error> {
+	let tag = match parent_node.children().find(|n| n.has_tag_name(tag_name)) {
+		Some(tag) => tag,
+		None => return Ok(None),
+	};
+	match tag.attribute("type") {
+		Some("Integer") => Ok(optional_integer::<i64>(parent_node, tag_name)?.map(|v| v as f64)),
+		Some("ScaledInteger") => {
+			let attribute = |name: &str, default: f64| match tag.attribute(name) {
+				Some(text) => text.trim().parse::<f64>().map_err(|_| {
+					Error::Invalid(format!(
+						"Cannot parse attribute '{name}' with value '{text}' of XML tag '{tag_name}' at {}",
+						location(&tag)
+					))
+				}),
+				None => Ok(default),
+			};
+			let scale = attribute("scale", 1.0)?;
+			let offset = attribute("offset", 0.0)?;
+			Ok(optional_number::<i64>(parent_node, tag_name, "ScaledInteger")?.map(|v| v as f64 * scale + offset))
+		},
+		_ => optional_double(parent_node, tag_name),
+	}
 }
 
 pub fn optional_integer<T: FromStr + Sync + Send>(parent_node: &Node, tag_name: &str) -> Result<Option<T>, Error> {
@@ -75,9 +117,7 @@ pub fn optional_integer<T: FromStr + Sync + Send>(parent_node: &Node, tag_name:
 }
 
 pub fn required_integer<T: FromStr + Send + Sync>(parent_node: &Node, tag_name: &str) -> Result<T, Error> {
-	optional_number(parent_node, tag_name, "Integer")?.ok_or(Error::Invalid(format!(
-		"XML tag '{tag_name}' was not found"
-	)))
+	optional_number(parent_node, tag_name, "Integer")?.ok_or_else(|| not_found(parent_node, tag_name))
 }
 
 pub fn optional_transform(parent_node: &Node, tag_name: &str) -> Result<Option<Transform>, Error> {
@@ -88,3 +128,11 @@ pub fn optional_transform(parent_node: &Node, tag_name: &str) -> Result<Option<T
 		Ok(None)
 	}
 }
+
+fn not_found(parent_node: &Node, tag_name: &str) -> Error {
+	Error::Invalid(format!(
+		"XML tag '{tag_name}' was not found in '{}' at {}",
+		parent_node.tag_name().name(),
+		location(parent_node)
+	))
+}
@@ -0,0 +1,35 @@
+use e57::SphericalBounds;
+use std::f64::consts::{PI, TAU};
+
+fn azimuth(start: f64, end: f64) -> SphericalBounds {
+	SphericalBounds {
+		azimuth_start: Some(start),
+		azimuth_end: Some(end),
+		..Default::default()
+	}
+}
+
+#[test]
+fn azimuth_span_without_wrap() {
+	assert_eq!(azimuth(-PI, PI).azimuth_span(), Some(TAU));
+	assert_eq!(azimuth(-1.0, 2.0).azimuth_span(), Some(3.0));
+	assert_eq!(azimuth(0.5, 0.5).azimuth_span(), Some(0.0));
+}
+
+#[test]
+fn azimuth_span_with_wrap() {
+	// Scan from 170 to -170 degrees covers 20 degrees across the -PI/PI boundary
+	let span = azimuth(170_f64.to_radians(), (-170_f64).to_radians())
+		.azimuth_span()
+		.expect("Missing span");
+	assert!((span - 20_f64.to_radians()).abs() < 1e-12, "Wrong span {span}");
+	let span = azimuth(1.0, -1.0).azimuth_span().expect("Missing span");
+	assert!((span - (TAU - 2.0)).abs() < 1e-12, "Wrong span {span}");
+}
+
+#[test]
+fn azimuth_span_without_limits() {
+	assert_eq!(SphericalBounds::default().azimuth_span(), None);
+	let start_only = SphericalBounds { azimuth_start: Some(1.0), ..Default::default() };
+	assert_eq!(start_only.azimuth_span(), None);
+}
@@ -0,0 +1,235 @@
+#![allow(dead_code)]
+
+use e57::{E57Writer, PointCloud, RawValues, Reader, Record};
+use std::io::Cursor;
+
+const PAGE_SIZE: usize = 1024;
+const LOGICAL_PAGE_SIZE: usize = 1020;
+
+/// Writes a file with a single point cloud into memory.
+pub fn write(prototype: &[Record], points: Vec<RawValues>) -> Vec<u8> {
+	let mut writer = E57Writer::new(Cursor::new(Vec::new())).expect("Failed to create writer");
+	let pc = PointCloud::builder("{pc}")
+		.prototype(prototype.to_vec())
+		.build()
+		.expect("Invalid point cloud");
+	writer
+		.add_pointcloud(pc, points.into_iter())
+		.expect("Failed to write point cloud");
+	writer.finalize().expect("Failed to finalize file").into_inner()
+}
+
+/// Opens a file from memory.
+pub fn open(bytes: &[u8]) -> Reader {
+	Reader::from_bytes(bytes).expect("Failed to open file")
+}
+
+/// Writes a file with a single point cloud and opens it again.
+pub fn roundtrip(prototype: &[Record], points: Vec<RawValues>) -> Reader {
+	open(&write(prototype, points))
+}
+
+/// Replaces the XML section of a file, which must be the last section.
+///
+/// The header and the checksums of all pages are updated.
+pub fn rewrite_xml(bytes: &[u8], f: impl FnOnce(String) -> String) -> Vec<u8> {
+	let mut logical = logical(bytes);
+	let xml = String::from_utf8(take_xml(&mut logical)).expect("Invalid XML");
+	append_xml(logical, f(xml).as_bytes())
+}
+
+/// Modifies the logical bytes of the 48 byte file header and updates the checksum of the first page.
+pub fn patch_header(bytes: &[u8], f: impl FnOnce(&mut [u8])) -> Vec<u8> {
+	let mut logical = logical(bytes);
+	f(&mut logical[..48]);
+	paged(&logical)
+}
+
+/// Modifies the logical bytes of the 32 byte section header of the first point cloud and updates the checksums.
+pub fn patch_section(bytes: &[u8], f: impl FnOnce(&mut [u8])) -> Vec<u8> {
+	let pc = open(bytes).pointclouds()[0].clone();
+	let section = to_logical(pc.file_offset as usize);
+	let mut logical = logical(bytes);
+	f(&mut logical[section..section + 32]);
+	paged(&logical)
+}
+
+/// Adds an index packet with one entry per data packet to the section of the first point cloud.
+///
+/// The section must be directly followed by the XML section, like in all files of the writer.
+pub fn add_index(bytes: &[u8]) -> Vec<u8> {
+	let reader = open(bytes);
+	let pc = reader.pointclouds()[0].clone();
+	let mut entries = Vec::new();
+	let mut record = 0;
+	for packet in reader.packets(&pc).expect("Failed to read packets") {
+		let packet = packet.expect("Failed to read packet");
+		entries.push((record, to_physical(packet.offset as usize) as u64));
+		record += pc
+			.prototype
+			.iter()
+			.zip(&packet.bytestream_sizes)
+			.filter_map(|(r, size)| (size * 8).checked_div(r.data_type.bit_width() as u64))
+			.min()
+			.unwrap_or(0);
+	}
+
+	let mut logical = logical(bytes);
+	let xml = take_xml(&mut logical);
+	let index_offset = logical.len();
+	let length = 16 + entries.len() * 16;
+	logical.extend_from_slice(&[0, 0]);
+	logical.extend_from_slice(&((length - 1) as u16).to_le_bytes());
+	logical.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+	logical.extend_from_slice(&[0; 10]);
+	for (record, offset) in entries {
+		logical.extend_from_slice(&record.to_le_bytes());
+		logical.extend_from_slice(&offset.to_le_bytes());
+	}
+
+	let section = to_logical(pc.file_offset as usize);
+	logical[section + 24..section + 32].copy_from_slice(&(to_physical(index_offset) as u64).to_le_bytes());
+	finish_section(&mut logical, section);
+	append_xml(logical, &xml)
+}
+
+/// Inserts unused logical bytes between the file header and the section of the first point cloud.
+///
+/// The file must contain a single point cloud without index packets, like all files of the writer.
+pub fn add_header_gap(bytes: &[u8], gap: usize) -> Vec<u8> {
+	let pc = open(bytes).pointclouds()[0].clone();
+	let mut logical = logical(bytes);
+	let xml = String::from_utf8(take_xml(&mut logical)).expect("Invalid XML");
+	let shift = |physical: u64| to_physical(to_logical(physical as usize) + gap) as u64;
+
+	let section = to_logical(pc.file_offset as usize);
+	let data_offset = shift(u64_at(&logical, section + 16));
+	logical[section + 16..section + 24].copy_from_slice(&data_offset.to_le_bytes());
+	logical.splice(section..section, std::iter::repeat_n(0, gap));
+	let xml = xml.replace(
+		&format!("fileOffset=\"{}\"", pc.file_offset),
+		&format!("fileOffset=\"{}\"", shift(pc.file_offset)),
+	);
+	append_xml(logical, xml.as_bytes())
+}
+
+/// Adds a blob section with the data before the XML section and returns the new file and the physical blob offset.
+pub fn add_blob(bytes: &[u8], data: &[u8]) -> (Vec<u8>, u64) {
+	let mut logical = logical(bytes);
+	let xml = take_xml(&mut logical);
+	let section = logical.len();
+	let section_length = (16 + data.len()).next_multiple_of(4);
+	logical.extend_from_slice(&[0; 8]);
+	logical.extend_from_slice(&(section_length as u64).to_le_bytes());
+	logical.extend_from_slice(data);
+	logical.resize(section + section_length, 0);
+	(append_xml(logical, &xml), to_physical(section) as u64)
+}
+
+/// Replaces all packets of the first point cloud with data packets containing the given bytestreams.
+///
+/// The section must be directly followed by the XML section, like in all files of the writer.
+pub fn replace_packets(bytes: &[u8], packets: &[Vec<Vec<u8>>]) -> Vec<u8> {
+	let reader = open(bytes);
+	let pc = reader.pointclouds()[0].clone();
+	let info = pc.section_info(&reader).expect("Failed to read section");
+	let mut logical = logical(bytes);
+	let xml = take_xml(&mut logical);
+	logical.truncate(to_logical(info.data_offset as usize));
+	for streams in packets {
+		let mut packet = vec![1, 0, 0, 0];
+		packet.extend_from_slice(&(streams.len() as u16).to_le_bytes());
+		for stream in streams {
+			packet.extend_from_slice(&(stream.len() as u16).to_le_bytes());
+		}
+		for stream in streams {
+			packet.extend_from_slice(stream);
+		}
+		packet.resize(packet.len().next_multiple_of(4), 0);
+		let length = (packet.len() - 1) as u16;
+		packet[2..4].copy_from_slice(&length.to_le_bytes());
+		logical.extend_from_slice(&packet);
+	}
+	finish_section(&mut logical, to_logical(pc.file_offset as usize));
+	append_xml(logical, &xml)
+}
+
+/// Removes the XML section from the end of the logical bytes and returns it.
+fn take_xml(logical: &mut Vec<u8>) -> Vec<u8> {
+	let xml_offset = to_logical(u64_at(logical, 24) as usize);
+	let xml_length = u64_at(logical, 32) as usize;
+	let xml = logical[xml_offset..xml_offset + xml_length].to_vec();
+	logical.truncate(xml_offset);
+	xml
+}
+
+/// Updates the length of the section at the logical offset, which ends at the end of the logical bytes.
+fn finish_section(logical: &mut [u8], section: usize) {
+	let section_length = (logical.len() - section) as u64;
+	logical[section + 8..section + 16].copy_from_slice(&section_length.to_le_bytes());
+}
+
+/// Appends the XML section and updates the header.
+fn append_xml(mut logical: Vec<u8>, xml: &[u8]) -> Vec<u8> {
+	let xml_offset = to_physical(logical.len()) as u64;
+	logical.extend_from_slice(xml);
+	let phys_length = logical.len().div_ceil(LOGICAL_PAGE_SIZE) * PAGE_SIZE;
+	logical[16..24].copy_from_slice(&(phys_length as u64).to_le_bytes());
+	logical[24..32].copy_from_slice(&xml_offset.to_le_bytes());
+	logical[32..40].copy_from_slice(&(xml.len() as u64).to_le_bytes());
+	paged(&logical)
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+	u64::from_le_bytes(bytes[offset..offset + 8].try_into().expect("Invalid offset"))
+}
+
+fn to_logical(physical: usize) -> usize {
+	physical - physical / PAGE_SIZE * 4
+}
+
+fn to_physical(logical: usize) -> usize {
+	logical + logical / LOGICAL_PAGE_SIZE * 4
+}
+
+fn logical(bytes: &[u8]) -> Vec<u8> {
+	bytes
+		.chunks(PAGE_SIZE)
+		.flat_map(|page| &page[..LOGICAL_PAGE_SIZE])
+		.copied()
+		.collect()
+}
+
+fn paged(logical: &[u8]) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	for chunk in logical.chunks(LOGICAL_PAGE_SIZE) {
+		let mut page = chunk.to_vec();
+		page.resize(LOGICAL_PAGE_SIZE, 0);
+		let crc = crc32c(&page);
+		bytes.extend_from_slice(&page);
+		bytes.extend_from_slice(&crc.to_be_bytes());
+	}
+	bytes
+}
+
+fn crc32c(data: &[u8]) -> u32 {
+	let mut crc = !0_u32;
+	for byte in data {
+		crc ^= *byte as u32;
+		for _ in 0..8 {
+			crc = if crc & 1 != 0 {
+				(crc >> 1) ^ 0x82F6_3B78
+			} else {
+				crc >> 1
+			};
+		}
+	}
+	!crc
+}
+
+/// Asserts that two positions are equal within a small tolerance.
+pub fn assert_close(actual: [f64; 3], expected: [f64; 3]) {
+	for (a, e) in actual.iter().zip(expected.iter()) {
+		assert!((a - e).abs() < 1e-9, "Expected {expected:?}, got {actual:?}");
+	}
+}
@@ -0,0 +1,32 @@
+use e57::DateTime;
+
+fn gps(gps_time: f64) -> DateTime {
+	DateTime { gps_time, atomic_reference: true }
+}
+
+#[test]
+fn known_gps_times() {
+	assert_eq!(gps(0.0).to_unix_seconds(), 315_964_800.0);
+	// 2011-09-14 01:46:25 UTC, after 15 leap seconds
+	assert_eq!(gps(1_000_000_000.0).to_unix_seconds(), 1_315_964_785.0);
+	assert_eq!(gps(1_000_000_000.25).to_unix_seconds(), 1_315_964_785.25);
+}
+
+#[test]
+fn leap_second_boundary() {
+	// 2016-12-31 23:59:59 UTC, followed by the leap second 23:59:60 and 2017-01-01 00:00:00 UTC
+	assert_eq!(gps(1_167_264_016.0).to_unix_seconds(), 1_483_228_799.0);
+	assert_eq!(gps(1_167_264_017.0).to_unix_seconds(), 1_483_228_800.0);
+	assert_eq!(gps(1_167_264_018.0).to_unix_seconds(), 1_483_228_800.0);
+	assert_eq!(gps(1_167_264_019.0).to_unix_seconds(), 1_483_228_801.0);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn to_chrono() {
+	let utc = |text: &str| text.parse::<chrono::DateTime<chrono::Utc>>().expect("Invalid date");
+	assert_eq!(gps(1_000_000_000.0).to_chrono(), Some(utc("2011-09-14T01:46:25Z")));
+	assert_eq!(gps(1_167_264_016.5).to_chrono(), Some(utc("2016-12-31T23:59:59.5Z")));
+	assert_eq!(gps(1_167_264_018.0).to_chrono(), Some(utc("2017-01-01T00:00:00Z")));
+	assert_eq!(gps(f64::NAN).to_chrono(), None);
+}
@@ -0,0 +1,500 @@
+mod common;
+
+use common::{assert_close, open, rewrite_xml, roundtrip, write};
+use e57::{
+	CartesianBounds, ColorConverter, ColorSpace, IntensityLimits, InvalidPolicy, Point, PointOptions,
+	PropertyConverter, Quaternion, Record, RecordDataType, RecordName, RecordValue, Transform, Translation,
+	UnitIntConverter,
+};
+
+fn cartesian(points: &[[f64; 3]]) -> (Vec<Record>, Vec<Vec<RecordValue>>) {
+	let prototype = vec![
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+	];
+	let values = points
+		.iter()
+		.map(|p| p.iter().map(|v| RecordValue::Double(*v)).collect())
+		.collect();
+	(prototype, values)
+}
+
+fn spherical(points: &[[f64; 3]]) -> (Vec<Record>, Vec<Vec<RecordValue>>) {
+	let prototype = vec![
+		Record::SPHERICAL_RANGE_F64,
+		Record::SPHERICAL_AZIMUTH_F64,
+		Record::SPHERICAL_ELEVATION_F64,
+	];
+	let values = points
+		.iter()
+		.map(|p| p.iter().map(|v| RecordValue::Double(*v)).collect())
+		.collect();
+	(prototype, values)
+}
+
+#[test]
+fn pose_normalizes_rotation() {
+	let (prototype, values) = cartesian(&[[1.0, 0.0, 0.0], [0.0, 2.0, 3.0]]);
+	let mut reader = roundtrip(&prototype, values);
+	let mut pc = reader.pointclouds()[0].clone();
+	// 90 degrees around Z, scaled to a length of two
+	let half = std::f64::consts::FRAC_PI_4;
+	pc.transform = Some(Transform {
+		rotation:    Quaternion { w: 2.0 * half.cos(), x: 0.0, y: 0.0, z: 2.0 * half.sin() },
+		translation: Translation { x: 10.0, y: 20.0, z: 30.0 },
+	});
+	let points = reader
+		.pointcloud_transformed(&pc)
+		.expect("Failed to create reader")
+		.collect::<Result<Vec<Point>, _>>()
+		.expect("Failed to read points");
+	assert_close(points[0].position, [10.0, 21.0, 30.0]);
+	assert_close(points[1].position, [8.0, 20.0, 33.0]);
+}
+
+#[test]
+fn spherical_converted_before_clamp() {
+	let (prototype, values) = spherical(&[[10.0, 0.0, 0.0], [1.0, std::f64::consts::FRAC_PI_2, 0.0]]);
+	let mut reader = roundtrip(&prototype, values);
+	let mut pc = reader.pointclouds()[0].clone();
+	pc.cartesian_bounds = Some(CartesianBounds { x_max: Some(5.0), ..Default::default() });
+	let points = reader
+		.points(&pc)
+		.expect("Failed to create reader")
+		.with_clamp_to_bounds()
+		.collect::<Result<Vec<Point>, _>>()
+		.expect("Failed to read points");
+	assert_close(points[0].position, [5.0, 0.0, 0.0]);
+	assert_close(points[1].position, [0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn spherical_converted_before_bounds_check() {
+	let (prototype, values) = spherical(&[[10.0, 0.0, 0.0], [1.0, 0.0, 0.0]]);
+	let mut reader = roundtrip(&prototype, values);
+	let mut pc = reader.pointclouds()[0].clone();
+	pc.cartesian_bounds = Some(CartesianBounds { x_max: Some(5.0), ..Default::default() });
+	let mut points = reader.points(&pc).expect("Failed to create reader").with_bounds_check();
+	points.by_ref().for_each(drop);
+	let violations = points.bounds_violations().expect("Bounds check not enabled");
+	assert_eq!(violations.count, 1);
+	assert_eq!(violations.indices, vec![0]);
+}
+
+fn read_points(reader: &mut e57::Reader) -> Vec<Point> {
+	let pc = reader.pointclouds()[0].clone();
+	reader
+		.points(&pc)
+		.expect("Failed to create reader")
+		.collect::<Result<Vec<_>, _>>()
+		.expect("Failed to read points")
+}
+
+fn spherical_with_state(points: &[[f64; 3]], states: &[i64]) -> (Vec<Record>, Vec<Vec<RecordValue>>) {
+	let (mut prototype, values) = spherical(points);
+	prototype.push(Record {
+		name:      RecordName::SphericalInvalidState,
+		data_type: RecordDataType::Integer { min: 0, max: 2 },
+	});
+	let values = values
+		.into_iter()
+		.zip(states)
+		.map(|(mut v, state)| {
+			v.push(RecordValue::Integer(*state));
+			v
+		})
+		.collect();
+	(prototype, values)
+}
+
+#[test]
+fn spherical_values_and_invalid_state() {
+	let (prototype, values) = spherical_with_state(&[[2.0, 0.5, 0.25], [3.0, 0.0, 0.0]], &[0, 2]);
+	let points = read_points(&mut roundtrip(&prototype, values));
+	assert_eq!(points[0].spherical, Some([2.0, 0.5, 0.25]));
+	assert_eq!(points[0].spherical_invalid, 0);
+	assert_eq!(points[0].cartesian_invalid, 0);
+	assert_eq!(points[1].spherical, Some([3.0, 0.0, 0.0]));
+	assert_eq!(points[1].spherical_invalid, 2);
+	assert_eq!(points[1].cartesian_invalid, 2);
+}
+
+#[test]
+fn spherical_no_return() {
+	let (prototype, values) = spherical_with_state(
+		&[
+			[2.0, 0.0, 0.0],
+			[0.0, std::f64::consts::FRAC_PI_2, 0.0],
+			[0.0, 0.0, 0.0],
+		],
+		&[0, 1, 2],
+	);
+	let mut reader = roundtrip(&prototype, values);
+	let points = read_points(&mut reader);
+	assert_close(points[1].position, [0.0, 1.0, 0.0]);
+	assert_eq!(points[1].cartesian_invalid, 1);
+	assert_eq!(points[1].spherical, None);
+	// A zero range with another invalid state is no sentinel
+	assert_eq!(points[2].spherical, Some([0.0; 3]));
+	assert_eq!(points[2].cartesian_invalid, 2);
+
+	let pc = reader.pointclouds()[0].clone();
+	let kept = reader
+		.points(&pc)
+		.expect("Failed to create reader")
+		.with_invalid_policy(InvalidPolicy::DropPoint)
+		.collect::<Result<Vec<_>, _>>()
+		.expect("Failed to read points");
+	assert_eq!(kept.len(), 2);
+	assert_eq!(kept[0].spherical, Some([2.0, 0.0, 0.0]));
+	assert_eq!(kept[1].spherical, Some([0.0; 3]));
+}
+
+#[test]
+fn spherical_next_to_cartesian() {
+	let (mut prototype, mut values) = cartesian(&[[1.0, 2.0, 3.0]]);
+	prototype.push(Record::SPHERICAL_RANGE_F64);
+	values[0].push(RecordValue::Double(7.0));
+	let points = read_points(&mut roundtrip(&prototype, values));
+	assert_eq!(points[0].position, [1.0, 2.0, 3.0]);
+	assert_eq!(points[0].spherical, Some([7.0, 0.0, 0.0]));
+	let (prototype, values) = cartesian(&[[1.0, 2.0, 3.0]]);
+	let points = read_points(&mut roundtrip(&prototype, values));
+	assert_eq!(points[0].spherical, None);
+}
+
+#[test]
+fn constant_intensity_and_color() {
+	let (mut prototype, mut values) = cartesian(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+	let constant = RecordDataType::Integer { min: 7, max: 7 };
+	for name in [
+		RecordName::Intensity,
+		RecordName::ColorRed,
+		RecordName::ColorGreen,
+		RecordName::ColorBlue,
+	] {
+		prototype.push(Record { name, data_type: constant });
+	}
+	for v in values.iter_mut() {
+		v.extend(std::iter::repeat_n(RecordValue::Integer(7), 4));
+	}
+	let points = read_points(&mut roundtrip(&prototype, values));
+	for p in points {
+		assert_eq!(p.intensity, Some(1.0));
+		assert_eq!(p.color, Some([1.0; 3]));
+	}
+}
+
+fn with_intensity(values: &[i64]) -> (Vec<Record>, Vec<Vec<RecordValue>>) {
+	let (mut prototype, mut points) = cartesian(&vec![[0.0; 3]; values.len()]);
+	prototype.push(Record::INTENSITY_U16);
+	for (p, v) in points.iter_mut().zip(values) {
+		p.push(RecordValue::Integer(*v));
+	}
+	(prototype, points)
+}
+
+#[test]
+fn intensity_limits_and_record_range() {
+	let (prototype, values) = with_intensity(&[0, 13107, 26214, 65535]);
+	let mut reader = roundtrip(&prototype, values);
+	let intensities = |reader: &mut e57::Reader, pc: &e57::PointCloud| {
+		reader
+			.points(pc)
+			.expect("Failed to create reader")
+			.map(|p| p.expect("Failed to read point").intensity.expect("Missing intensity"))
+			.collect::<Vec<_>>()
+	};
+
+	// Without limits the whole range of the record is used
+	let mut pc = reader.pointclouds()[0].clone();
+	assert_eq!(intensities(&mut reader, &pc), [0.0, 0.2, 0.4, 1.0]);
+
+	// Narrower limits stretch the values and clamp everything outside
+	pc.intensity_limits = Some(IntensityLimits { intensity_min: Some(13107.0), intensity_max: Some(26214.0) });
+	assert_eq!(intensities(&mut reader, &pc), [0.0, 0.0, 1.0, 1.0]);
+}
+
+#[test]
+fn unit_int_converter_clamps() {
+	let converter = UnitIntConverter { min: 10, max: 20 };
+	assert_eq!(converter.convert(5), 0.0);
+	assert_eq!(converter.convert(15), 0.5);
+	assert_eq!(converter.convert(25), 1.0);
+	let converter = ColorConverter {
+		min:         0,
+		max:         255,
+		color_space: ColorSpace::Srgb,
+	};
+	assert_eq!(converter.convert(300), 1.0);
+	assert_eq!(converter.convert(-1), 0.0);
+}
+
+#[test]
+fn intensity_data_types() {
+	let cases = [
+		(RecordDataType::Integer { min: 0, max: 4 }, RecordValue::Integer(1)),
+		(
+			RecordDataType::ScaledInteger { min: 0, max: 1000, scale: 0.01 },
+			RecordValue::ScaledInteger(250),
+		),
+		(
+			RecordDataType::Single { min: Some(0.0), max: Some(2.0) },
+			RecordValue::Single(0.5),
+		),
+		(
+			RecordDataType::Double { min: None, max: None },
+			RecordValue::Double(0.25),
+		),
+	];
+	for (data_type, value) in cases {
+		let (mut prototype, mut values) = cartesian(&[[1.0, 2.0, 3.0]]);
+		prototype.push(Record { name: RecordName::Intensity, data_type });
+		values[0].push(value);
+		let points = read_points(&mut roundtrip(&prototype, values));
+		assert_eq!(points[0].intensity, Some(0.25), "{data_type:?}");
+	}
+
+	let (prototype, values) = cartesian(&[[1.0, 2.0, 3.0]]);
+	let points = read_points(&mut roundtrip(&prototype, values));
+	assert_eq!(points[0].intensity, None);
+}
+
+#[test]
+fn stdev_records() {
+	let (mut prototype, mut values) = cartesian(&[[1.0, 2.0, 3.0]]);
+	for name in [RecordName::StdevX, RecordName::StdevY, RecordName::StdevZ] {
+		prototype.push(Record {
+			name,
+			data_type: RecordDataType::Single { min: None, max: None },
+		});
+	}
+	values[0].extend([0.5, 0.25, 0.125].map(RecordValue::Single));
+	let bytes = write(&prototype, values);
+	let points = read_points(&mut open(&bytes));
+	assert_eq!(points[0].stdev, Some([0.5, 0.25, 0.125]));
+
+	let renamed = rewrite_xml(&bytes, |xml| xml.replace("pointStdev", "stdev"));
+	let points = read_points(&mut open(&renamed));
+	assert_eq!(points[0].stdev, Some([0.5, 0.25, 0.125]));
+
+	let (prototype, values) = cartesian(&[[1.0, 2.0, 3.0]]);
+	let points = read_points(&mut roundtrip(&prototype, values));
+	assert_eq!(points[0].stdev, None);
+}
+
+#[test]
+fn intensity_as_color() {
+	let (prototype, values) = with_intensity(&[0, 13107]);
+	let mut reader = roundtrip(&prototype, values);
+	let pc = reader.pointclouds()[0].clone();
+	let colors = reader
+		.points(&pc)
+		.expect("Failed to create reader")
+		.with_intensity_as_color()
+		.map(|p| p.expect("Failed to read point").color)
+		.collect::<Vec<_>>();
+	assert_eq!(colors, [Some([0.0; 3]), Some([0.2; 3])]);
+	let points = read_points(&mut reader);
+	assert_eq!(points[1].color, None);
+
+	let (mut prototype, mut values) = with_intensity(&[13107]);
+	prototype.extend([Record::COLOR_RED_U8, Record::COLOR_GREEN_U8, Record::COLOR_BLUE_U8]);
+	values[0].extend([
+		RecordValue::Integer(255),
+		RecordValue::Integer(0),
+		RecordValue::Integer(0),
+	]);
+	let mut reader = roundtrip(&prototype, values);
+	let pc = reader.pointclouds()[0].clone();
+	let point = reader
+		.points(&pc)
+		.expect("Failed to create reader")
+		.with_intensity_as_color()
+		.next()
+		.expect("Missing point")
+		.expect("Failed to read point");
+	assert_eq!(point.color, Some([1.0, 0.0, 0.0]));
+}
+
+#[test]
+fn raw_12_bit_colors() {
+	let (mut prototype, mut values) = cartesian(&[[0.0; 3], [0.0; 3]]);
+	let data_type = RecordDataType::Integer { min: 0, max: 4095 };
+	for name in [RecordName::ColorRed, RecordName::ColorGreen, RecordName::ColorBlue] {
+		prototype.push(Record { name, data_type });
+	}
+	prototype.push(Record {
+		name:      RecordName::IsColorInvalid,
+		data_type: RecordDataType::Integer { min: 0, max: 1 },
+	});
+	values[0].extend([4095, 2049, 1, 0].map(RecordValue::Integer));
+	values[1].extend([7, 8, 9, 1].map(RecordValue::Integer));
+	let mut reader = roundtrip(&prototype, values);
+	let pc = reader.pointclouds()[0].clone();
+	let points = reader
+		.points(&pc)
+		.expect("Failed to create reader")
+		.with_color_raw()
+		.collect::<Result<Vec<_>, _>>()
+		.expect("Failed to read points");
+	assert_eq!(points[0].color_raw, Some([4095, 2049, 1]));
+	assert_eq!(points[0].color, Some([1.0, 2049.0 / 4095.0, 1.0 / 4095.0]));
+	assert_eq!(points[1].color_raw, None);
+
+	let points = read_points(&mut reader);
+	assert_eq!(points[0].color_raw, None);
+}
+
+#[test]
+fn override_normalization_ranges() {
+	let (mut prototype, mut values) = with_intensity(&[100, 200]);
+	prototype.extend([Record::COLOR_RED_U8, Record::COLOR_GREEN_U8, Record::COLOR_BLUE_U8]);
+	values[0].extend([0, 50, 100].map(RecordValue::Integer));
+	values[1].extend([100, 150, 255].map(RecordValue::Integer));
+	let mut reader = roundtrip(&prototype, values);
+	let mut pc = reader.pointclouds()[0].clone();
+	pc.intensity_limits = Some(IntensityLimits { intensity_min: Some(0.0), intensity_max: Some(400.0) });
+	let mut read = |options: PointOptions| {
+		reader
+			.points_with_options(&pc, options)
+			.expect("Failed to create reader")
+			.map(|p| p.expect("Failed to read point"))
+			.map(|p| (p.intensity.expect("Missing intensity"), p.color.expect("Missing color")))
+			.collect::<Vec<_>>()
+	};
+
+	// Limits of the point cloud take precedence over the record range
+	let points = read(PointOptions::default());
+	assert_eq!(points[0].0, 0.25);
+	assert_eq!(points[1].1, [100.0 / 255.0, 150.0 / 255.0, 1.0]);
+
+	// Overrides take precedence over the limits and the record range
+	let points = read(
+		PointOptions::default()
+			.override_intensity_range(100.0, 200.0)
+			.override_color_range(0.0, 200.0),
+	);
+	assert_eq!(points[0], (0.0, [0.0, 0.25, 0.5]));
+	assert_eq!(points[1], (1.0, [0.5, 0.75, 1.0]));
+}
+
+#[test]
+fn linear_color_space() {
+	let (mut prototype, mut values) = cartesian(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+	prototype.extend([Record::COLOR_RED_U8, Record::COLOR_GREEN_U8, Record::COLOR_BLUE_U8]);
+	values[0].extend([0, 10, 255].map(RecordValue::Integer));
+	values[1].extend([128, 200, 64].map(RecordValue::Integer));
+	let mut reader = roundtrip(&prototype, values);
+	let pc = reader.pointclouds()[0].clone();
+	let mut read = |options: PointOptions| {
+		reader
+			.points_with_options(&pc, options)
+			.expect("Failed to create reader")
+			.map(|p| p.expect("Failed to read point").color.expect("Missing color"))
+			.collect::<Vec<_>>()
+	};
+
+	let srgb = read(PointOptions::default());
+	assert_eq!(srgb, read(PointOptions::default().color_space(ColorSpace::Srgb)));
+	let linear = read(PointOptions::default().color_space(ColorSpace::Linear));
+	for (srgb, linear) in srgb.iter().flatten().zip(linear.iter().flatten()) {
+		assert_eq!(*linear, ColorSpace::Linear.from_srgb(*srgb));
+	}
+	assert_eq!(linear[0][0], 0.0);
+	assert_eq!(linear[0][2], 1.0);
+	assert!((linear[1][0] - 0.2158605).abs() < 1e-6, "{linear:?}");
+	assert!((linear[0][1] - 10.0 / 255.0 / 12.92).abs() < 1e-6, "{linear:?}");
+}
+
+#[test]
+fn rgba() {
+	let (mut prototype, mut values) = with_intensity(&[13107, 26214]);
+	prototype.extend([Record::COLOR_RED_U8, Record::COLOR_GREEN_U8, Record::COLOR_BLUE_U8]);
+	values[0].extend([255, 0, 51].map(RecordValue::Integer));
+	values[1].extend([0, 255, 0].map(RecordValue::Integer));
+	let points = read_points(&mut roundtrip(&prototype, values));
+	assert_eq!(points[0].rgba(), [1.0, 0.0, 0.2, 0.2]);
+	assert_eq!(points[1].rgba(), [0.0, 1.0, 0.0, 0.4]);
+
+	let (prototype, values) = with_intensity(&[13107]);
+	let points = read_points(&mut roundtrip(&prototype, values));
+	assert_eq!(points[0].rgba(), [0.2; 4]);
+
+	let (mut prototype, mut values) = cartesian(&[[0.0; 3]]);
+	prototype.extend([Record::COLOR_RED_U8, Record::COLOR_GREEN_U8, Record::COLOR_BLUE_U8]);
+	values[0].extend([255, 0, 51].map(RecordValue::Integer));
+	let points = read_points(&mut roundtrip(&prototype, values));
+	assert_eq!(points[0].rgba(), [1.0, 0.0, 0.2, 1.0]);
+}
+
+#[test]
+fn unsupported_by_point() {
+	let (mut prototype, mut values) = cartesian(&[[1.0, 2.0, 3.0]]);
+	prototype.push(Record {
+		name:      RecordName::RowIndex,
+		data_type: RecordDataType::Double { min: None, max: None },
+	});
+	prototype.push(Record {
+		name:      RecordName::ColumnIndex,
+		data_type: RecordDataType::Integer { min: 0, max: 10 },
+	});
+	values[0].extend([RecordValue::Double(4.0), RecordValue::Integer(5)]);
+	let mut reader = roundtrip(&prototype, values);
+	assert_eq!(reader.pointclouds()[0].unsupported_by_point(), [RecordName::RowIndex]);
+	let points = read_points(&mut reader);
+	assert_eq!(points[0].row, None);
+	assert_eq!(points[0].column, Some(5));
+}
+
+#[test]
+fn transparent_invalid_colors() {
+	let (mut prototype, mut values) = with_intensity(&[13107, 26214]);
+	prototype.extend([Record::COLOR_RED_U8, Record::COLOR_GREEN_U8, Record::COLOR_BLUE_U8]);
+	prototype.push(Record {
+		name:      RecordName::IsColorInvalid,
+		data_type: RecordDataType::Integer { min: 0, max: 1 },
+	});
+	values[0].extend([255, 0, 51, 0].map(RecordValue::Integer));
+	values[1].extend([255, 255, 255, 1].map(RecordValue::Integer));
+	let mut reader = roundtrip(&prototype, values);
+	let pc = reader.pointclouds()[0].clone();
+	let points = reader
+		.points(&pc)
+		.expect("Failed to create reader")
+		.with_invalid_policy(InvalidPolicy::Transparent)
+		.collect::<Result<Vec<_>, _>>()
+		.expect("Failed to read points");
+	assert_eq!(points[0].color, Some([1.0, 0.0, 0.2]));
+	assert_eq!(points[0].rgba(), [1.0, 0.0, 0.2, 0.2]);
+	assert_eq!(points[1].color, Some([0.0; 3]));
+	assert_eq!(points[1].rgba(), [0.0; 4]);
+	assert_eq!(points[1].intensity, Some(0.4));
+
+	let points = read_points(&mut reader);
+	assert_eq!(points[1].color, None);
+
+	// Selecting a policy again replaces the previous one instead of adding to it
+	let mut read = |policies: &[InvalidPolicy]| {
+		let mut points = reader.points(&pc).expect("Failed to create reader");
+		for policy in policies {
+			points = points.with_invalid_policy(*policy);
+		}
+		points.collect::<Result<Vec<_>, _>>().expect("Failed to read points")
+	};
+	let transparent = read(&[InvalidPolicy::Transparent]);
+	assert_eq!(
+		read(&[InvalidPolicy::Transparent, InvalidPolicy::Transparent]),
+		transparent
+	);
+	assert_eq!(
+		read(&[InvalidPolicy::DropPoint, InvalidPolicy::Transparent]),
+		transparent
+	);
+	let points = read(&[InvalidPolicy::Transparent, InvalidPolicy::NullAttribute]);
+	assert_eq!(points[1].color, None);
+	assert_eq!(points[1].rgba(), [0.4, 0.4, 0.4, 0.4]);
+	let points = read(&[InvalidPolicy::Transparent, InvalidPolicy::DropPoint]);
+	assert_eq!(points.len(), 1);
+}
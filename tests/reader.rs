@@ -0,0 +1,973 @@
+mod common;
+
+use common::assert_close;
+use common::{
+	add_blob, add_header_gap, add_index, open, patch_header, patch_section, replace_packets, rewrite_xml, write,
+};
+use e57::{
+	Error, F64Loader, IndexBounds, Mmap, MultiReader, PacketType, PropertyLoader, PropertyReader, Quaternion, Reader,
+	Record, RecordDataType, RecordName, RecordValue, Transform, Translation,
+};
+use std::io::Cursor;
+
+fn xyz(count: usize) -> (Vec<Record>, Vec<Vec<RecordValue>>) {
+	let prototype = vec![
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+	];
+	let values = (0..count)
+		.map(|i| {
+			vec![
+				RecordValue::Double(i as f64),
+				RecordValue::Double(0.5),
+				RecordValue::Double(-1.0),
+			]
+		})
+		.collect();
+	(prototype, values)
+}
+
+fn without_record_count(xml: String) -> String {
+	let start = xml.find(" recordCount=\"").expect("Missing record count");
+	let end = xml[start + 14..].find('"').expect("Invalid record count") + start + 15;
+	format!("{}{}", &xml[..start], &xml[end..])
+}
+
+#[test]
+fn infer_record_count() {
+	let (prototype, values) = xyz(10_000);
+	let bytes = write(&prototype, values);
+	let indexed = add_index(&bytes);
+	let reader = open(&indexed);
+	let pc = &reader.pointclouds()[0];
+	assert_ne!(
+		pc.section_info(&reader).expect("Failed to read section").index_offset,
+		0
+	);
+	assert!(reader.packets(pc).expect("Failed to read packets").count() > 3);
+	for bytes in [bytes, indexed] {
+		let bytes = rewrite_xml(&bytes, without_record_count);
+		let mut reader = open(&bytes);
+		let pc = reader.pointclouds()[0].clone();
+		assert_eq!(pc.records, 10_000);
+		let points = reader.points(&pc).expect("Failed to create reader").count();
+		assert_eq!(points, 10_000);
+	}
+}
+
+#[test]
+fn infer_record_count_of_constant_prototype() {
+	let prototype = [Record {
+		name:      RecordName::Intensity,
+		data_type: RecordDataType::Integer { min: 3, max: 3 },
+	}];
+	let values = vec![vec![RecordValue::Integer(3)]; 5];
+	for bytes in [write(&prototype, values.clone()), add_index(&write(&prototype, values))] {
+		let bytes = rewrite_xml(&bytes, without_record_count);
+		let mut reader = open(&bytes);
+		let pc = reader.pointclouds()[0].clone();
+		assert_eq!(pc.records, 0);
+		assert!(reader.points(&pc).is_err());
+	}
+}
+
+fn decode_all(reader: &Reader) -> Vec<Result<Vec<Vec<RecordValue>>, e57::Error>> {
+	let pc = &reader.pointclouds()[0];
+	reader
+		.packets(pc)
+		.expect("Failed to read packets")
+		.map(|p| p.expect("Failed to read packet"))
+		.filter(|p| p.packet_type == PacketType::Data)
+		.map(|p| reader.decode_packet(pc, p.offset as usize))
+		.collect()
+}
+
+#[test]
+fn decode_packet_rejects_split_values() {
+	let prototype = [Record {
+		name:      RecordName::Intensity,
+		data_type: RecordDataType::Integer { min: 0, max: 6 },
+	}];
+	let values = (0..16).map(|i| vec![RecordValue::Integer(i % 7)]).collect::<Vec<_>>();
+	let packed = (0..16).fold(0_u64, |acc, i| acc | ((i % 7) << (3 * i)));
+	let packed = &packed.to_le_bytes()[..6];
+	let bytes = write(&prototype, values.clone());
+
+	// The first packet ends after 8 values in the middle of the second byte
+	let aligned = replace_packets(&bytes, &[vec![packed[..3].to_vec()], vec![packed[3..].to_vec()]]);
+	let decoded = decode_all(&open(&aligned));
+	assert_eq!(decoded[0].as_ref().expect("Failed to decode"), &values[..8]);
+	assert_eq!(decoded[1].as_ref().expect("Failed to decode"), &values[8..]);
+
+	// The first packet ends after 10 values and 2 bits of the next value
+	let split = replace_packets(&bytes, &[vec![packed[..4].to_vec()], vec![packed[4..].to_vec()]]);
+	let decoded = decode_all(&open(&split));
+	assert_eq!(decoded[0].as_ref().expect("Failed to decode"), &values[..10]);
+	assert!(decoded[1].is_err());
+}
+
+#[test]
+fn decode_packet_of_constant_prototype() {
+	let prototype = [Record {
+		name:      RecordName::Intensity,
+		data_type: RecordDataType::Integer { min: 3, max: 3 },
+	}];
+	let values = vec![vec![RecordValue::Integer(3)]; 5];
+	let bytes = write(&prototype, values.clone());
+	for bytes in [bytes.clone(), add_index(&bytes)] {
+		let decoded = decode_all(&open(&bytes));
+		assert_eq!(decoded.len(), 1);
+		assert_eq!(decoded[0].as_ref().expect("Failed to decode"), &values);
+	}
+
+	let ambiguous = replace_packets(&bytes, &[vec![vec![]], vec![vec![]]]);
+	assert!(decode_all(&open(&ambiguous)).iter().all(Result::is_err));
+}
+
+#[test]
+fn decode_packet_of_constant_prototype_with_huge_record_count() {
+	let prototype = [Record {
+		name:      RecordName::Intensity,
+		data_type: RecordDataType::Integer { min: 3, max: 3 },
+	}];
+	let bytes = write(&prototype, vec![vec![RecordValue::Integer(3)]; 5]);
+	let huge = |xml: String| xml.replace("recordCount=\"5\"", &format!("recordCount=\"{}\"", u64::MAX / 2));
+	for bytes in [bytes.clone(), add_index(&bytes)] {
+		let decoded = decode_all(&open(&rewrite_xml(&bytes, huge)));
+		assert_eq!(decoded.len(), 1);
+		assert!(matches!(decoded[0], Err(Error::Invalid(_))));
+	}
+}
+
+#[test]
+fn positions_transformed() {
+	let (prototype, values) = xyz(3);
+	let mut reader = open(&write(&prototype, values));
+	let mut pc = reader.pointclouds()[0].clone();
+	assert_eq!(
+		reader.positions_transformed(&pc).expect("Failed to read"),
+		reader.positions(&pc).expect("Failed to read")
+	);
+	// 180 degrees around Z, scaled to a length of two
+	pc.transform = Some(Transform {
+		rotation:    Quaternion { w: 0.0, x: 0.0, y: 0.0, z: 2.0 },
+		translation: Translation { x: 1.0, y: 2.0, z: 3.0 },
+	});
+	let positions = reader.positions_transformed(&pc).expect("Failed to read");
+	assert_eq!(positions.len(), 3);
+	for (i, position) in positions.into_iter().enumerate() {
+		assert_close(position, [1.0 - i as f64, 1.5, 2.0]);
+	}
+}
+
+#[test]
+fn positions_of_huge_record_count() {
+	let (prototype, values) = xyz(3);
+	let bytes = rewrite_xml(&write(&prototype, values), |xml| {
+		xml.replace("recordCount=\"3\"", &format!("recordCount=\"{}\"", u64::MAX / 2))
+	});
+	let mut reader = open(&bytes);
+	let pc = reader.pointclouds()[0].clone();
+	assert_eq!(pc.records, u64::MAX / 2);
+	assert!(reader.positions(&pc).is_err());
+	assert!(reader.grid_positions(&pc).is_err());
+}
+
+#[test]
+fn range_image_of_absurd_index_bounds() {
+	let index = RecordDataType::Integer { min: 0, max: 1 };
+	let (mut prototype, values) = xyz(4);
+	prototype.push(Record { name: RecordName::RowIndex, data_type: index });
+	prototype.push(Record { name: RecordName::ColumnIndex, data_type: index });
+	let values = values
+		.into_iter()
+		.enumerate()
+		.map(|(i, mut v)| {
+			v.extend([RecordValue::Integer(i as i64 / 2), RecordValue::Integer(i as i64 % 2)]);
+			v
+		})
+		.collect();
+	let mut reader = open(&write(&prototype, values));
+	let mut pc = reader.pointclouds()[0].clone();
+	let bounds = |rows: (i64, i64), columns: (i64, i64)| IndexBounds {
+		row_min: Some(rows.0),
+		row_max: Some(rows.1),
+		column_min: Some(columns.0),
+		column_max: Some(columns.1),
+		..Default::default()
+	};
+
+	pc.index_bounds = Some(bounds((0, 1), (0, 1)));
+	let image = reader.range_image(&pc).expect("Failed to read range image");
+	assert_eq!((image.width, image.height), (2, 2));
+	assert!(image.cells.iter().all(Option::is_some));
+
+	for (rows, columns) in [
+		((i64::MIN, i64::MAX), (0, 1)),
+		((0, 1), (-1, i64::MAX)),
+		((0, u32::MAX as i64), (0, u32::MAX as i64)),
+		((0, i32::MAX as i64), (0, i32::MAX as i64)),
+	] {
+		pc.index_bounds = Some(bounds(rows, columns));
+		assert!(
+			reader.range_image(&pc).is_err(),
+			"Accepted rows {rows:?} and columns {columns:?}"
+		);
+	}
+}
+
+/// Reads the X coordinate without support for restarting.
+struct XReader(F64Loader);
+
+impl PropertyReader<f64> for XReader {
+	fn read(&mut self, mmap: &Mmap, point: &mut f64, at_end: bool) -> Result<(), Error> {
+		*point = self.0.load(mmap, at_end)?;
+		Ok(())
+	}
+}
+
+fn x_reader(
+	name: RecordName,
+	_: RecordDataType,
+	offset: usize,
+	index: usize,
+	mmap: &Mmap,
+) -> Result<Option<Box<dyn PropertyReader<f64>>>, Error> {
+	if name != RecordName::CartesianX {
+		return Ok(None);
+	}
+	Ok(Some(Box::new(XReader(F64Loader::new(offset, index, mmap)?))))
+}
+
+#[test]
+fn seek_point_matches_sequential() {
+	let (prototype, values) = xyz(10_000);
+	let bytes = write(&prototype, values);
+	for bytes in [bytes.clone(), add_index(&bytes)] {
+		let mut reader = open(&bytes);
+		let pc = reader.pointclouds()[0].clone();
+		let sequential = reader
+			.points(&pc)
+			.expect("Failed to create reader")
+			.map(|p| p.expect("Failed to read point").position)
+			.collect::<Vec<_>>();
+
+		let mut points = reader.points(&pc).expect("Failed to create reader");
+		for index in [5_000, 9_999, 0, 3_000, 3_001, 7_777, 10] {
+			points.seek_point(index).expect("Failed to seek");
+			let point = points.next().expect("Missing point").expect("Failed to read point");
+			assert_eq!(point.position, sequential[index as usize]);
+		}
+		drop(points);
+
+		let mut xs = reader.pointcloud(&pc, x_reader).expect("Failed to create reader");
+		for index in [10, 3_000, 3_001, 7_777, 9_999] {
+			xs.seek_point(index).expect("Failed to seek");
+			let x = xs.next().expect("Missing point").expect("Failed to read point");
+			assert_eq!(x, sequential[index as usize][0]);
+		}
+		assert!(xs.seek_point(0).is_err());
+		assert!(xs.next().is_none());
+	}
+}
+
+#[test]
+fn integers_with_more_than_56_bits() {
+	for bits in [57, 60, 63] {
+		let max = (1_i64 << (bits - 1)) - 1 + (1_i64 << (bits - 1));
+		for min in [0, -(1_i64 << (bits - 1))] {
+			let prototype = [Record {
+				name:      RecordName::Intensity,
+				data_type: RecordDataType::Integer { min, max: min + max },
+			}];
+			assert_eq!(prototype[0].data_type.bit_width(), bits);
+			let values = (0..20_000_i64)
+				.map(|i| {
+					let offset = match i % 4 {
+						0 => 0,
+						1 => max,
+						2 => i.wrapping_mul(0x0123_4567_89AB_CDEF) & max,
+						_ => max - i,
+					};
+					vec![RecordValue::Integer(min + offset)]
+				})
+				.collect::<Vec<_>>();
+			let mut reader = open(&write(&prototype, values.clone()));
+			let pc = reader.pointclouds()[0].clone();
+			assert!(reader.packets(&pc).expect("Failed to read packets").count() > 1);
+			let read = reader
+				.pointcloud_raw(&pc)
+				.expect("Failed to create reader")
+				.collect::<Result<Vec<_>, _>>()
+				.expect("Failed to read points");
+			assert!(read == values, "Failed to decode {bits} bit integers starting at {min}");
+		}
+	}
+}
+
+#[test]
+fn compression_ratio() {
+	let prototype = [Record {
+		name:      RecordName::Intensity,
+		data_type: RecordDataType::Integer { min: 0, max: 4095 },
+	}];
+	let values = (0..10_000)
+		.map(|i| vec![RecordValue::Integer(i % 4096)])
+		.collect::<Vec<_>>();
+	let bytes = write(&prototype, values);
+	let reader = open(&bytes);
+	let pc = &reader.pointclouds()[0];
+	// Two bytes per 12 bit value compared to one and a half bytes plus packet overhead
+	let ratio = pc.compression_ratio(&reader).expect("Failed to calculate ratio");
+	assert!(ratio > 1.3 && ratio < 4.0 / 3.0, "Unexpected ratio {ratio}");
+
+	let empty = patch_section(&bytes, |section| section[8..16].fill(0));
+	let reader = open(&empty);
+	assert!(reader.pointclouds()[0].compression_ratio(&reader).is_err());
+}
+
+fn with_images(xml: String, blobs: &[(u64, u64)]) -> String {
+	let images = blobs
+		.iter()
+		.enumerate()
+		.map(|(i, (offset, length))| {
+			format!(
+				"<vectorChild type=\"Structure\"><guid type=\"String\">{{image{i}}}</guid>\
+				<pinholeRepresentation type=\"Structure\">\
+				<jpegImage type=\"Blob\" fileOffset=\"{offset}\" length=\"{length}\"/>\
+				</pinholeRepresentation></vectorChild>"
+			)
+		})
+		.collect::<String>();
+	xml.replace(
+		"<images2D type=\"Vector\" allowHeterogeneousChildren=\"1\"/>",
+		&format!("<images2D type=\"Vector\" allowHeterogeneousChildren=\"1\">{images}</images2D>"),
+	)
+}
+
+#[test]
+fn validate_sections_with_blobs() {
+	let (prototype, values) = xyz(10);
+	let (bytes, offset) = add_blob(&write(&prototype, values), &[0xAB; 100]);
+	let pc_offset = open(&bytes).pointclouds()[0].file_offset;
+
+	let valid = rewrite_xml(&bytes, |xml| with_images(xml, &[(offset, 100)]));
+	let reader = open(&valid);
+	reader.validate_sections().expect("Failed to validate sections");
+	let mut data = Vec::new();
+	reader
+		.blob(&reader.images()[0].jpeg.expect("Missing blob"), &mut data)
+		.expect("Failed to read blob");
+	assert_eq!(data, [0xAB; 100]);
+
+	let shared = rewrite_xml(&bytes, |xml| with_images(xml, &[(offset, 100), (offset, 10)]));
+	assert!(open(&shared).validate_sections().is_err());
+
+	// Section header of the point cloud starts with ID 1 instead of 0
+	let points = rewrite_xml(&bytes, |xml| with_images(xml, &[(pc_offset, 10)]));
+	assert!(open(&points).validate_sections().is_err());
+}
+
+#[test]
+fn wrong_header_file_length() {
+	let (prototype, values) = xyz(10);
+	let bytes = write(&prototype, values);
+	assert_eq!(open(&bytes).file_length_mismatch(), None);
+	for length in [0, bytes.len() as u64 * 2, u64::MAX] {
+		let patched = patch_header(&bytes, |header| header[16..24].copy_from_slice(&length.to_le_bytes()));
+		let mut reader = open(&patched);
+		assert_eq!(reader.file_length_mismatch(), Some(length));
+		let pc = reader.pointclouds()[0].clone();
+		assert_eq!(reader.positions(&pc).expect("Failed to read positions").len(), 10);
+	}
+
+	let truncated = patch_header(&bytes, |header| {
+		header[32..40].copy_from_slice(&(bytes.len() as u64).to_le_bytes())
+	});
+	assert!(Reader::from_bytes(&truncated).is_err());
+	let overflow = patch_header(&bytes, |header| header[32..40].copy_from_slice(&u64::MAX.to_le_bytes()));
+	assert!(Reader::from_bytes(&overflow).is_err());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn pointcloud_par_matches_sequential() {
+	use rayon::iter::ParallelIterator;
+
+	let mut prototype = vec![
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F32,
+		Record::CARTESIAN_Z_F64,
+	];
+	prototype.extend([
+		Record::INTENSITY_U16,
+		Record::COLOR_RED_U8,
+		Record::COLOR_GREEN_U8,
+		Record::COLOR_BLUE_U8,
+	]);
+	let values = (0..50_000_i64)
+		.map(|i| {
+			vec![
+				RecordValue::Double(i as f64 * 0.5),
+				RecordValue::Single(-(i as f32)),
+				RecordValue::Double(1.0 / (i + 1) as f64),
+				RecordValue::Integer(i % 65_536),
+				RecordValue::Integer(i % 256),
+				RecordValue::Integer((i * 7) % 256),
+				RecordValue::Integer(255 - i % 256),
+			]
+		})
+		.collect::<Vec<_>>();
+	let bytes = write(&prototype, values.clone());
+	let single = write(&prototype, values[..10].to_vec());
+	for bytes in [add_index(&bytes), bytes, single] {
+		let mut reader = open(&bytes);
+		let pc = reader.pointclouds()[0].clone();
+		let sequential = reader
+			.points(&pc)
+			.expect("Failed to create reader")
+			.collect::<Result<Vec<_>, _>>()
+			.expect("Failed to read points");
+		let parallel = reader
+			.pointcloud_par(&pc)
+			.expect("Failed to create parallel reader")
+			.collect::<Result<Vec<_>, _>>()
+			.expect("Failed to read points in parallel");
+		assert_eq!(parallel.len(), pc.records as usize);
+		assert!(parallel == sequential);
+	}
+}
+
+#[test]
+fn colored_positions_fast_path_is_validated() {
+	let xyz = RecordDataType::ScaledInteger { min: -1000, max: 1000, scale: 0.01 };
+	let rgb = RecordDataType::Integer { min: 0, max: 255 };
+	let names = [RecordName::CartesianX, RecordName::CartesianY, RecordName::CartesianZ];
+	let mut prototype = names.map(|name| Record { name, data_type: xyz }).to_vec();
+	let names = [RecordName::ColorRed, RecordName::ColorGreen, RecordName::ColorBlue];
+	prototype.extend(names.map(|name| Record { name, data_type: rgb }));
+	let values = (0..5000_i64)
+		.map(|i| {
+			let mut values = [i % 1000, -(i % 7), 3].map(RecordValue::ScaledInteger).to_vec();
+			values.extend([i % 256, 0, 255].map(RecordValue::Integer));
+			values
+		})
+		.collect::<Vec<_>>();
+	let bytes = write(&prototype, values);
+	let expected = (0..5000_i64)
+		.map(|i| {
+			(
+				[(i % 1000) as f64 * 0.01, -(i % 7) as f64 * 0.01, 0.03],
+				[(i % 256) as u8, 0, 255],
+			)
+		})
+		.collect::<Vec<_>>();
+	for bytes in [bytes.clone(), rewrite_xml(&add_index(&bytes), without_record_count)] {
+		let mut reader = open(&bytes);
+		let pc = reader.pointclouds()[0].clone();
+		assert_eq!(reader.colored_positions(&pc).expect("Failed to read points"), expected);
+	}
+
+	let missing = rewrite_xml(&bytes, |xml| {
+		xml.replace("recordCount=\"5000\"", "recordCount=\"5001\"")
+	});
+	let mut reader = open(&missing);
+	let pc = reader.pointclouds()[0].clone();
+	let Err(Error::Invalid(message)) = reader.colored_positions(&pc) else {
+		panic!("Missing points were not detected");
+	};
+	assert!(message.contains("contains only 5000 of the 5001 points"), "{message}");
+}
+
+#[test]
+fn intensity_limits_from_xml() {
+	let prototype = [
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+		Record::INTENSITY_U16,
+	];
+	let values = [0, 2000, 4000]
+		.map(|i| {
+			[0.0, 0.0, 0.0]
+				.map(RecordValue::Double)
+				.into_iter()
+				.chain([RecordValue::Integer(i)])
+				.collect()
+		})
+		.to_vec();
+	let limits = "<intensityLimits type=\"Structure\">\
+		<intensityMinimum type=\"Integer\">0</intensityMinimum>\
+		<intensityMaximum type=\"Integer\">4000</intensityMaximum>\
+		</intensityLimits>";
+	let bytes = rewrite_xml(&write(&prototype, values), |xml| {
+		xml.replacen("<points ", &format!("{limits}<points "), 1)
+	});
+	let mut reader = open(&bytes);
+	let pc = reader.pointclouds()[0].clone();
+	let limits = pc.intensity_limits.clone().expect("Missing intensity limits");
+	assert_eq!(limits.intensity_min, Some(0.0));
+	assert_eq!(limits.intensity_max, Some(4000.0));
+	let intensities = reader
+		.points(&pc)
+		.expect("Failed to create reader")
+		.map(|p| p.expect("Failed to read point").intensity)
+		.collect::<Vec<_>>();
+	assert_eq!(intensities, [Some(0.0), Some(0.5), Some(1.0)]);
+}
+
+#[cfg(feature = "half")]
+#[test]
+fn interleaved_colored_f16() {
+	use half::f16;
+
+	let mut prototype = vec![
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+	];
+	prototype.extend([Record::COLOR_RED_U8, Record::COLOR_GREEN_U8, Record::COLOR_BLUE_U8]);
+	let point = |x: f64, rgb: [i64; 3]| {
+		let mut values = vec![
+			RecordValue::Double(x),
+			RecordValue::Double(101.0),
+			RecordValue::Double(-100.5),
+		];
+		values.extend(rgb.map(RecordValue::Integer));
+		values
+	};
+	let values = vec![point(100.0, [0, 255, 51]), point(100.25, [255, 0, 0])];
+	let mut reader = open(&write(&prototype, values));
+	let pc = reader.pointclouds()[0].clone();
+	let origin = [100.0, 100.0, -100.0];
+
+	let positions = reader
+		.read_interleaved_f16(&pc, origin)
+		.expect("Failed to read positions");
+	let colored = reader
+		.read_interleaved_colored_f16(&pc, origin)
+		.expect("Failed to read colored positions");
+	let expected = [[0.0, 1.0, -0.5, 0.0, 1.0, 0.2], [0.25, 1.0, -0.5, 1.0, 0.0, 0.0]].concat();
+	assert_eq!(colored, expected.iter().map(|v| f16::from_f64(*v)).collect::<Vec<_>>());
+	assert_eq!(positions, [&colored[0..3], &colored[6..9]].concat());
+}
+
+#[test]
+fn stream_reader_matches_mmap_reader() {
+	let (prototype, values) = xyz(20_000);
+	let bytes = write(&prototype, values);
+	let wide = [Record {
+		name:      RecordName::Intensity,
+		data_type: RecordDataType::Integer { min: -(1 << 62), max: 1 << 62 },
+	}];
+	let wide_values = (0..20_000_i64).map(|i| vec![RecordValue::Integer(i.wrapping_mul(0x0123_4567_89AB_CDEF) >> 2)]);
+	let files = [
+		bytes.clone(),
+		add_index(&bytes),
+		rewrite_xml(&add_index(&bytes), without_record_count),
+		write(&wide, wide_values.collect()),
+	];
+	for bytes in files {
+		let mut reader = open(&bytes);
+		let pc = reader.pointclouds()[0].clone();
+		let expected = reader
+			.pointcloud_raw(&pc)
+			.expect("Failed to create reader")
+			.collect::<Result<Vec<_>, _>>()
+			.expect("Failed to read points");
+		let mut stream = Reader::from_reader_streaming(Cursor::new(&bytes)).expect("Failed to open stream");
+		assert_eq!(stream.pointclouds().len(), 1);
+		let pc = stream.pointclouds()[0].clone();
+		let read = stream
+			.pointcloud_raw(&pc)
+			.expect("Failed to create stream reader")
+			.collect::<Result<Vec<_>, _>>()
+			.expect("Failed to stream points");
+		assert_eq!(read.len(), 20_000);
+		assert!(read == expected);
+	}
+
+	let truncated = rewrite_xml(&bytes, |xml| {
+		xml.replace("recordCount=\"20000\"", "recordCount=\"20001\"")
+	});
+	let mut stream = Reader::from_reader_streaming(Cursor::new(&truncated)).expect("Failed to open stream");
+	let pc = stream.pointclouds()[0].clone();
+	let read = stream
+		.pointcloud_raw(&pc)
+		.expect("Failed to create stream reader")
+		.collect::<Vec<_>>();
+	assert_eq!(read.len(), 20_001);
+	assert!(matches!(read.last(), Some(Err(Error::Invalid(_)))));
+
+	let mut corrupted = bytes.clone();
+	corrupted[2048 + 10] ^= 0xFF;
+	let mut stream = Reader::from_reader_streaming(Cursor::new(&corrupted)).expect("Failed to open stream");
+	let pc = stream.pointclouds()[0].clone();
+	let read = stream
+		.pointcloud_raw(&pc)
+		.expect("Failed to create stream reader")
+		.collect::<Vec<_>>();
+	assert!(read.last().expect("Missing points").is_err());
+}
+
+#[test]
+fn integers_with_extreme_limits() {
+	let values = [i64::MIN, i64::MAX, 0, -1, 1, i64::MIN + 1, i64::MAX - 1];
+	let prototype = [
+		Record {
+			name:      RecordName::Intensity,
+			data_type: RecordDataType::Integer { min: i64::MIN, max: i64::MAX },
+		},
+		Record {
+			name:      RecordName::TimeStamp,
+			data_type: RecordDataType::ScaledInteger { min: i64::MIN, max: i64::MAX, scale: 0.5 },
+		},
+	];
+	assert_eq!(prototype[0].data_type.bit_width(), 64);
+	let values = values
+		.iter()
+		.map(|v| vec![RecordValue::Integer(*v), RecordValue::ScaledInteger(v.wrapping_neg())])
+		.collect::<Vec<_>>();
+	let mut reader = open(&write(&prototype, values.clone()));
+	let pc = reader.pointclouds()[0].clone();
+	let read = reader
+		.pointcloud_raw(&pc)
+		.expect("Failed to create reader")
+		.collect::<Result<Vec<_>, _>>()
+		.expect("Failed to read points");
+	assert_eq!(read, values);
+}
+
+#[test]
+fn nul_padded_xml() {
+	let (prototype, values) = xyz(10);
+	let bytes = write(&prototype, values);
+	for padding in ["\0\0\0\0", "\0\n \0\t", "\0\0garbage\0"] {
+		let padded = rewrite_xml(&bytes, |xml| format!("{xml}{padding}"));
+		let reader = Reader::from_bytes(&padded).expect("Failed to open padded file");
+		assert_eq!(reader.pointclouds().len(), 1);
+		assert_eq!(reader.pointclouds()[0].records, 10);
+	}
+}
+
+#[test]
+fn scaled_integer_cartesian_bounds() {
+	let (prototype, values) = xyz(10);
+	let bounds = "<cartesianBounds type=\"Structure\">\
+		<xMinimum type=\"ScaledInteger\" minimum=\"-10000\" maximum=\"10000\" scale=\"0.001\">-1500</xMinimum>\
+		<xMaximum type=\"ScaledInteger\" minimum=\"-10000\" maximum=\"10000\" scale=\"0.001\">2500</xMaximum>\
+		<yMinimum type=\"ScaledInteger\" scale=\"0.5\" offset=\"10\">-4</yMinimum>\
+		<yMaximum type=\"Integer\">12</yMaximum>\
+		<zMaximum type=\"Float\">0.25</zMaximum>\
+		</cartesianBounds>";
+	let bytes = rewrite_xml(&write(&prototype, values), |xml| {
+		xml.replacen("<points ", &format!("{bounds}<points "), 1)
+	});
+	let pc = open(&bytes).pointclouds()[0].clone();
+	let bounds = pc.cartesian_bounds.expect("Missing Cartesian bounds");
+	assert_eq!(bounds.x_min, Some(-1.5));
+	assert_eq!(bounds.x_max, Some(2.5));
+	assert_eq!(bounds.y_min, Some(8.0));
+	assert_eq!(bounds.y_max, Some(12.0));
+	assert_eq!(bounds.z_min, None);
+	assert_eq!(bounds.z_max, Some(0.25));
+}
+
+#[test]
+fn truncated_file() {
+	let (prototype, values) = xyz(20_000);
+	let bytes = write(&prototype, values);
+
+	// Interrupted download, the header declares more bytes than available
+	let truncated = &bytes[..bytes.len() / 2 / 1024 * 1024];
+	let Err(Error::Invalid(message)) = Reader::from_bytes(truncated) else {
+		panic!("Truncated file was not detected");
+	};
+	assert!(message.contains("truncated"), "{message}");
+
+	// Section that extends past the end of the file
+	let truncated = patch_section(&bytes, |section| {
+		let length = u64::from_le_bytes(section[8..16].try_into().expect("Invalid section"));
+		section[8..16].copy_from_slice(&(length + bytes.len() as u64).next_multiple_of(4).to_le_bytes());
+	});
+	let mut reader = open(&truncated);
+	let pc = reader.pointclouds()[0].clone();
+	let Err(Error::Invalid(message)) = reader.points(&pc) else {
+		panic!("Truncated section was not detected");
+	};
+	assert!(message.contains("truncated"), "{message}");
+}
+
+#[test]
+fn large_header_gap() {
+	let (prototype, values) = xyz(20_000);
+	let bytes = write(&prototype, values.clone());
+	for gap in [4, 1016, 1020, 5000, 100_000] {
+		let gapped = add_header_gap(&bytes, gap);
+		let mut reader = open(&gapped);
+		let pc = reader.pointclouds()[0].clone();
+		assert!(pc.file_offset >= gap as u64);
+		let read = reader
+			.pointcloud_raw(&pc)
+			.expect("Failed to create reader")
+			.collect::<Result<Vec<_>, _>>()
+			.expect("Failed to read points");
+		assert!(read == values, "Failed to read points after a gap of {gap} bytes");
+	}
+}
+
+#[test]
+fn offsets_inside_page_checksums() {
+	let (prototype, values) = xyz(10);
+	let bytes = add_header_gap(&write(&prototype, values), 2000);
+
+	let xml_in_checksum = patch_header(&bytes, |header| header[24..32].copy_from_slice(&1022_u64.to_le_bytes()));
+	assert!(matches!(Reader::from_bytes(&xml_in_checksum), Err(Error::Invalid(_))));
+
+	let file_offset = open(&bytes).pointclouds()[0].file_offset;
+	let section_in_checksum = rewrite_xml(&bytes, |xml| {
+		xml.replace(&format!("fileOffset=\"{file_offset}\""), "fileOffset=\"2045\"")
+	});
+	let Err(Error::Invalid(message)) = Reader::from_bytes(&section_in_checksum).and_then(|mut reader| {
+		let pc = reader.pointclouds()[0].clone();
+		reader.points(&pc).map(|_| ())
+	}) else {
+		panic!("Section offset inside a checksum was not detected");
+	};
+	assert!(message.contains("checksum"), "{message}");
+}
+
+#[test]
+fn clamp_injected_out_of_range_value() {
+	let (prototype, mut values) = xyz(100);
+	values[50][0] = RecordValue::Double(1e12);
+	values[60][2] = RecordValue::Double(-1e12);
+	let bounds = "<cartesianBounds type=\"Structure\">\
+		<xMinimum type=\"Float\">0</xMinimum><xMaximum type=\"Float\">99</xMaximum>\
+		<zMinimum type=\"Float\">-1</zMinimum><zMaximum type=\"Float\">-1</zMaximum>\
+		</cartesianBounds>";
+	let bytes = rewrite_xml(&write(&prototype, values), |xml| {
+		xml.replacen("<points ", &format!("{bounds}<points "), 1)
+	});
+	let mut reader = open(&bytes);
+	let pc = reader.pointclouds()[0].clone();
+	let positions = |reader: &mut Reader, clamp: bool| {
+		let points = reader.points(&pc).expect("Failed to create reader");
+		let points = if clamp { points.with_clamp_to_bounds() } else { points };
+		points
+			.map(|p| p.expect("Failed to read point").position)
+			.collect::<Vec<_>>()
+	};
+
+	let raw = positions(&mut reader, false);
+	assert_eq!(raw[50], [1e12, 0.5, -1.0]);
+	assert_eq!(raw[60], [60.0, 0.5, -1e12]);
+	let clamped = positions(&mut reader, true);
+	assert_eq!(clamped[50], [99.0, 0.5, -1.0]);
+	assert_eq!(clamped[60], [60.0, 0.5, -1.0]);
+	for (i, (raw, clamped)) in raw.iter().zip(&clamped).enumerate() {
+		if i != 50 && i != 60 {
+			assert_eq!(raw, clamped);
+		}
+	}
+}
+
+#[test]
+fn intensity_limits_with_different_type() {
+	let prototype = [
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+		Record {
+			name:      RecordName::Intensity,
+			data_type: RecordDataType::ScaledInteger { min: 0, max: 100_000, scale: 0.01 },
+		},
+	];
+	let values = [0, 25_000, 50_000, 100_000]
+		.map(|i| {
+			[0.0, 0.0, 0.0]
+				.map(RecordValue::Double)
+				.into_iter()
+				.chain([RecordValue::ScaledInteger(i)])
+				.collect()
+		})
+		.to_vec();
+	// Limits in the scaled unit of the intensities, stored as floating point values
+	let limits = "<intensityLimits type=\"Structure\">\
+		<intensityMinimum type=\"Float\">0</intensityMinimum>\
+		<intensityMaximum type=\"Float\">500</intensityMaximum>\
+		</intensityLimits>";
+	let bytes = rewrite_xml(&write(&prototype, values), |xml| {
+		xml.replacen("<points ", &format!("{limits}<points "), 1)
+	});
+	let mut reader = open(&bytes);
+	let pc = reader.pointclouds()[0].clone();
+	let intensities = reader
+		.points(&pc)
+		.expect("Failed to create reader")
+		.map(|p| p.expect("Failed to read point").intensity)
+		.collect::<Vec<_>>();
+	assert_eq!(intensities, [Some(0.0), Some(0.5), Some(1.0), Some(1.0)]);
+}
+
+#[test]
+fn integer_encoded_environment() {
+	let (prototype, values) = xyz(10);
+	let environment = "<temperature type=\"Integer\">-5</temperature>\
+		<relativeHumidity type=\"Integer\">40</relativeHumidity>\
+		<atmosphericPressure type=\"Float\">101325.5</atmosphericPressure>";
+	let bytes = write(&prototype, values);
+	let bytes = rewrite_xml(&bytes, |xml| {
+		xml.replacen("<points ", &format!("{environment}<points "), 1)
+	});
+	let pc = open(&bytes).pointclouds()[0].clone();
+	assert_eq!(pc.temperature, Some(-5.0));
+	assert_eq!(pc.humidity, Some(40.0));
+	assert_eq!(pc.atmospheric_pressure, Some(101_325.5));
+}
+
+#[test]
+fn image_pose() {
+	let (prototype, values) = xyz(10);
+	let images = "<vectorChild type=\"Structure\">\
+		<guid type=\"String\">{posed}</guid>\
+		<associatedData3DGuid type=\"String\">{pc}</associatedData3DGuid>\
+		<pose type=\"Structure\">\
+		<rotation type=\"Structure\">\
+		<w type=\"Float\">0.5</w><x type=\"Float\">0.5</x><y type=\"Float\">-0.5</y><z type=\"Float\">0.5</z>\
+		</rotation>\
+		<translation type=\"Structure\">\
+		<x type=\"Float\">1.5</x><y type=\"Float\">-2</y><z type=\"Float\">100</z>\
+		</translation>\
+		</pose>\
+		</vectorChild>\
+		<vectorChild type=\"Structure\"><guid type=\"String\">{unposed}</guid></vectorChild>";
+	let bytes = rewrite_xml(&write(&prototype, values), |xml| {
+		xml.replace(
+			"<images2D type=\"Vector\" allowHeterogeneousChildren=\"1\"/>",
+			&format!("<images2D type=\"Vector\" allowHeterogeneousChildren=\"1\">{images}</images2D>"),
+		)
+	});
+	let reader = open(&bytes);
+	let images = reader.images();
+	assert_eq!(images.len(), 2);
+	assert_eq!(images[0].guid, "{posed}");
+	assert_eq!(
+		images[0].pointcloud_guid.as_deref(),
+		Some(reader.pointclouds()[0].guid.as_str())
+	);
+	let pose = images[0].transform.clone().expect("Missing image pose");
+	assert_eq!(pose.rotation, Quaternion { w: 0.5, x: 0.5, y: -0.5, z: 0.5 });
+	assert_eq!(pose.translation, Translation { x: 1.5, y: -2.0, z: 100.0 });
+	assert_eq!(images[1].guid, "{unposed}");
+	assert!(images[1].transform.is_none());
+}
+
+#[test]
+fn return_count_and_index() {
+	let prototype = [
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+		Record {
+			name:      RecordName::ReturnIndex,
+			data_type: RecordDataType::Integer { min: 0, max: 3 },
+		},
+		Record {
+			name:      RecordName::ReturnCount,
+			data_type: RecordDataType::Integer { min: 1, max: 4 },
+		},
+	];
+	// Pulses with one to four returns, each return is stored as its own point
+	let returns = (1..=4)
+		.flat_map(|count| (0..count).map(move |index| (index, count)))
+		.collect::<Vec<_>>();
+	let values = returns
+		.iter()
+		.map(|(index, count)| {
+			let mut values = [0.0, 0.0, 0.0].map(RecordValue::Double).to_vec();
+			values.extend([RecordValue::Integer(*index), RecordValue::Integer(*count)]);
+			values
+		})
+		.collect();
+	let bounds = "<indexBounds type=\"Structure\">\
+		<returnMinimum type=\"Integer\">0</returnMinimum><returnMaximum type=\"Integer\">3</returnMaximum>\
+		</indexBounds>";
+	let bytes = rewrite_xml(&write(&prototype, values), |xml| {
+		xml.replacen("<points ", &format!("{bounds}<points "), 1)
+	});
+	let mut reader = open(&bytes);
+	let pc = reader.pointclouds()[0].clone();
+	let bounds = pc.index_bounds.clone().expect("Missing index bounds");
+	let return_min = bounds.return_min.expect("Missing return minimum");
+	let return_max = bounds.return_max.expect("Missing return maximum");
+
+	let points = reader
+		.points(&pc)
+		.expect("Failed to create reader")
+		.collect::<Result<Vec<_>, _>>()
+		.expect("Failed to read points");
+	assert_eq!(points.len(), returns.len());
+	for (point, (index, count)) in points.iter().zip(&returns) {
+		assert_eq!(point.return_index, Some(*index));
+		assert_eq!(point.return_count, Some(*count));
+		assert!((return_min..=return_max).contains(index));
+		assert!(*count <= return_max - return_min + 1);
+	}
+	assert_eq!(points.iter().filter_map(|p| p.return_index).max(), Some(return_max));
+	assert_eq!(points.iter().filter_map(|p| p.return_index).min(), Some(return_min));
+}
+
+#[test]
+fn multi_reader_normalizes_pose() {
+	let (prototype, values) = xyz(3);
+	// 180 degrees around Z, scaled to a length of two
+	let pose = "<pose type=\"Structure\">\
+		<rotation type=\"Structure\">\
+		<w type=\"Float\">0</w><x type=\"Float\">0</x><y type=\"Float\">0</y><z type=\"Float\">2</z>\
+		</rotation>\
+		<translation type=\"Structure\"><x type=\"Float\">1</x><y type=\"Float\">2</y><z type=\"Float\">3</z></translation>\
+		</pose>";
+	let bytes = rewrite_xml(&write(&prototype, values), |xml| {
+		xml.replacen("<points ", &format!("{pose}<points "), 1)
+	});
+	let path = std::env::temp_dir().join(format!("e57_multi_reader_pose_{}.e57", std::process::id()));
+	std::fs::write(&path, &bytes).expect("Failed to write file");
+	let positions = MultiReader::new([&path]).positions().collect::<Result<Vec<_>, _>>();
+	std::fs::remove_file(&path).expect("Failed to remove file");
+
+	let positions = positions.expect("Failed to read positions");
+	assert_eq!(positions.len(), 3);
+	for (i, position) in positions.iter().enumerate() {
+		assert_close(*position, [1.0 - i as f64, 1.5, 2.0]);
+	}
+	let mut reader = open(&bytes);
+	let pc = reader.pointclouds()[0].clone();
+	assert_eq!(
+		positions,
+		reader.positions_transformed(&pc).expect("Failed to read positions")
+	);
+}
+
+#[test]
+fn version_of_reader_and_metadata() {
+	let (prototype, values) = xyz(3);
+	let bytes = rewrite_xml(&write(&prototype, values), |xml| {
+		xml.replace(
+			"<versionMinor type=\"Integer\">0</versionMinor>",
+			"<versionMinor type=\"Integer\">1</versionMinor>",
+		)
+	});
+	let path = std::env::temp_dir().join(format!("e57_version_{}.e57", std::process::id()));
+	std::fs::write(&path, &bytes).expect("Failed to write file");
+	let metadata = Reader::open_metadata_only(&path);
+	std::fs::remove_file(&path).expect("Failed to remove file");
+
+	let metadata = metadata.expect("Failed to read metadata");
+	let reader = open(&bytes);
+	assert_eq!(reader.version(), (1, 1));
+	assert_eq!(metadata.version(), reader.version());
+	assert!(reader.library_version().is_some_and(|v| v.starts_with("e57 ")));
+	assert_eq!(metadata.library_version(), reader.library_version());
+}
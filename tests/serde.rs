@@ -0,0 +1,49 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use common::roundtrip;
+use e57::{CartesianBounds, Header, PointCloud, Quaternion, Record, RecordValue, Transform, Translation};
+
+fn xyz() -> (Vec<Record>, Vec<Vec<RecordValue>>) {
+	let prototype = vec![
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+	];
+	let values = vec![
+		vec![
+			RecordValue::Double(1.0),
+			RecordValue::Double(2.0),
+			RecordValue::Double(3.0)
+		];
+		3
+	];
+	(prototype, values)
+}
+
+#[test]
+fn roundtrip_metadata() {
+	let (prototype, values) = xyz();
+	let reader = roundtrip(&prototype, values);
+	let header = reader.header();
+	let json = serde_json::to_string(&header).expect("Failed to serialize header");
+	assert_eq!(
+		serde_json::from_str::<Header>(&json).expect("Failed to deserialize header"),
+		header
+	);
+
+	let mut pc = reader.pointclouds()[0].clone();
+	pc.name = Some("Scan".into());
+	pc.transform = Some(Transform {
+		rotation:    Quaternion { w: 0.0, x: 1.0, y: 0.0, z: 0.0 },
+		translation: Translation { x: 1.0, y: 2.0, z: 3.0 },
+	});
+	pc.cartesian_bounds = Some(CartesianBounds { x_min: Some(-1.0), x_max: Some(1.0), ..Default::default() });
+	let json = serde_json::to_string(&pc).expect("Failed to serialize point cloud");
+	assert!(!json.contains("infer_records"));
+	assert_eq!(
+		serde_json::from_str::<PointCloud>(&json).expect("Failed to deserialize point cloud"),
+		pc
+	);
+}
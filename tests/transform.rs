@@ -0,0 +1,54 @@
+mod common;
+
+use common::assert_close;
+use e57::{spherical_to_cartesian, Quaternion, Transform, Translation};
+use std::f64::consts::FRAC_PI_4;
+
+fn multiply(matrix: [[f64; 3]; 3], point: [f64; 3]) -> [f64; 3] {
+	matrix.map(|row| row[0] * point[0] + row[1] * point[1] + row[2] * point[2])
+}
+
+#[test]
+fn rotate_90_degrees_about_each_axis() {
+	let (s, c) = FRAC_PI_4.sin_cos();
+	let x = [1.0, 0.0, 0.0];
+	let y = [0.0, 1.0, 0.0];
+	let z = [0.0, 0.0, 1.0];
+	let cases = [
+		(Quaternion { w: c, x: s, y: 0.0, z: 0.0 }, [x, z, [0.0, -1.0, 0.0]]),
+		(Quaternion { w: c, x: 0.0, y: s, z: 0.0 }, [[0.0, 0.0, -1.0], y, x]),
+		(Quaternion { w: c, x: 0.0, y: 0.0, z: s }, [y, [-1.0, 0.0, 0.0], z]),
+	];
+	for (rotation, expected) in cases {
+		let matrix = rotation.to_matrix();
+		for (point, expected) in [x, y, z].into_iter().zip(expected) {
+			assert_close(rotation.rotate_point(point), expected);
+			assert_close(multiply(matrix, point), expected);
+		}
+		// The columns of the matrix are the rotated axes
+		for (column, expected) in expected.iter().enumerate() {
+			assert_close(matrix.map(|row| row[column]), *expected);
+		}
+
+		let transform = Transform {
+			rotation,
+			translation: Translation { x: 1.0, y: 2.0, z: 3.0 },
+		};
+		let matrix = transform.to_matrix();
+		assert_eq!(matrix[3], [0.0, 0.0, 0.0, 1.0]);
+		for point in [x, y, z, [1.0, -2.0, 3.0]] {
+			let homogeneous = matrix.map(|row| row[0] * point[0] + row[1] * point[1] + row[2] * point[2] + row[3]);
+			assert_close([homogeneous[0], homogeneous[1], homogeneous[2]], transform.apply(point));
+		}
+	}
+}
+
+#[test]
+fn spherical_at_45_degrees() {
+	let convert = |coordinates| spherical_to_cartesian(coordinates).expect("Failed to convert");
+	assert_close(convert([2.0, FRAC_PI_4, 0.0]), [2.0_f64.sqrt(), 2.0_f64.sqrt(), 0.0]);
+	assert_close(convert([2.0, 0.0, FRAC_PI_4]), [2.0_f64.sqrt(), 0.0, 2.0_f64.sqrt()]);
+	assert_close(convert([2.0, FRAC_PI_4, FRAC_PI_4]), [1.0, 1.0, 2.0_f64.sqrt()]);
+	assert_close(convert([2.0, -FRAC_PI_4, -FRAC_PI_4]), [1.0, -1.0, -(2.0_f64.sqrt())]);
+	assert!(spherical_to_cartesian([-1.0, FRAC_PI_4, FRAC_PI_4]).is_none());
+}
@@ -0,0 +1,544 @@
+mod common;
+
+use common::{open, rewrite_xml, roundtrip, write};
+use e57::{
+	verify_roundtrip, write_xyz, CartesianBounds, DateTime, E57Appender, E57Writer, IndexBounds, IntensityLimits,
+	Point, PointCloud, Quaternion, RawValues, Reader, Record, RecordDataType, RecordName, RecordValue, SphericalBounds,
+	Transform, Translation,
+};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+/// Creates point cloud metadata without validating the prototype.
+fn cloud(guid: &str, prototype: &[Record]) -> PointCloud {
+	let mut pc = PointCloud::default();
+	pc.guid = guid.into();
+	pc.prototype = prototype.to_vec();
+	pc
+}
+
+#[test]
+fn roundtrip_large_integers() {
+	let prototype = [
+		Record {
+			name:      RecordName::CartesianX,
+			data_type: RecordDataType::ScaledInteger { min: -1_000_000, max: 1_000_000, scale: 0.001 },
+		},
+		Record {
+			name:      RecordName::CartesianY,
+			data_type: RecordDataType::Integer { min: 0, max: i64::MAX },
+		},
+		Record {
+			name:      RecordName::CartesianZ,
+			data_type: RecordDataType::Integer { min: i64::MIN, max: i64::MAX },
+		},
+	];
+	let mut state = 0x0123_4567_89AB_CDEF_u64;
+	let mut next = move || {
+		state = state
+			.wrapping_mul(6_364_136_223_846_793_005)
+			.wrapping_add(1_442_695_040_888_963_407);
+		state
+	};
+	let mut values = (0..200_000)
+		.map(|_| {
+			let random = next();
+			vec![
+				RecordValue::ScaledInteger((random % 2_000_001) as i64 - 1_000_000),
+				RecordValue::Integer((random >> 1) as i64),
+				RecordValue::Integer(next() as i64),
+			]
+		})
+		.collect::<Vec<RawValues>>();
+	values[0] = vec![
+		RecordValue::ScaledInteger(-1_000_000),
+		RecordValue::Integer(0),
+		RecordValue::Integer(i64::MIN),
+	];
+	values[1] = vec![
+		RecordValue::ScaledInteger(1_000_000),
+		RecordValue::Integer(i64::MAX),
+		RecordValue::Integer(i64::MAX),
+	];
+
+	let mut reader = roundtrip(&prototype, values.clone());
+	let pc = reader.pointclouds()[0].clone();
+	assert_eq!(pc.records, 200_000);
+	assert!(reader.packets(&pc).expect("Failed to read packets").count() > 1);
+	let read = reader
+		.pointcloud_raw(&pc)
+		.expect("Failed to create reader")
+		.collect::<Result<Vec<_>, _>>()
+		.expect("Failed to read points");
+	assert!(read == values);
+}
+
+#[test]
+fn failed_pointcloud_poisons_writer() {
+	let prototype = [
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+	];
+	let point = vec![
+		RecordValue::Double(1.0),
+		RecordValue::Double(2.0),
+		RecordValue::Double(3.0),
+	];
+	let mut writer = E57Writer::new(Cursor::new(Vec::new())).expect("Failed to create writer");
+	writer
+		.add_pointcloud(cloud("{a}", &prototype), std::iter::once(point.clone()))
+		.expect("Failed to write point cloud");
+
+	// Errors before any data is written keep the writer usable
+	assert!(writer
+		.add_pointcloud(cloud("{a}", &prototype), std::iter::empty())
+		.is_err());
+	let invalid = [Record {
+		name:      RecordName::Intensity,
+		data_type: RecordDataType::Integer { min: 1, max: 0 },
+	}];
+	assert!(writer
+		.add_pointcloud(cloud("{b}", &invalid), std::iter::empty())
+		.is_err());
+	writer
+		.add_pointcloud(cloud("{b}", &prototype), std::iter::once(point.clone()))
+		.expect("Failed to write point cloud");
+
+	let mut points = vec![point.clone(); 10_000];
+	points.push(vec![RecordValue::Double(1.0)]);
+	assert!(writer
+		.add_pointcloud(cloud("{c}", &prototype), points.into_iter())
+		.is_err());
+	assert!(writer
+		.add_pointcloud(cloud("{d}", &prototype), std::iter::once(point))
+		.is_err());
+	assert!(writer.finalize().is_err());
+}
+
+/// Writer that flips a bit of every byte written to one physical offset.
+struct Corrupting {
+	inner:  Cursor<Vec<u8>>,
+	offset: u64,
+}
+
+impl Read for Corrupting {
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+		self.inner.read(buf)
+	}
+}
+
+impl Seek for Corrupting {
+	fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+		self.inner.seek(pos)
+	}
+}
+
+impl Write for Corrupting {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		let start = self.inner.position();
+		let written = self.inner.write(buf)?;
+		if (start..start + written as u64).contains(&self.offset) {
+			self.inner.get_mut()[self.offset as usize] ^= 1;
+		}
+		Ok(written)
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		self.inner.flush()
+	}
+}
+
+#[test]
+fn finalize_verifies_written_data() {
+	let prototype = [
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+	];
+	let point = vec![
+		RecordValue::Double(1.0),
+		RecordValue::Double(2.0),
+		RecordValue::Double(3.0),
+	];
+	let write = |offset: u64| {
+		let inner = Cursor::new(Vec::new());
+		let mut writer = E57Writer::new(Corrupting { inner, offset }).expect("Failed to create writer");
+		writer
+			.add_pointcloud(cloud("{pc}", &prototype), vec![point.clone(); 1000].into_iter())
+			.expect("Failed to write point cloud");
+		writer.finalize()
+	};
+
+	let bytes = write(u64::MAX).expect("Failed to finalize file").inner.into_inner();
+	let xml_offset = u64::from_le_bytes(bytes[24..32].try_into().expect("Invalid header"));
+	// Signature, physical length and the first byte of the XML section
+	for offset in [0, 16, xml_offset] {
+		assert!(write(offset).is_err(), "Accepted corrupt byte at offset {offset}");
+	}
+}
+
+#[test]
+fn roundtrip_pointcloud_metadata() {
+	let prototype = vec![
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+		Record {
+			name:      RecordName::StdevX,
+			data_type: RecordDataType::F64,
+		},
+	];
+	let pc = PointCloud::builder("{meta}")
+		.prototype(prototype)
+		.name("Scan <1> & more".into())
+		.description("Description".into())
+		.cartesian_bounds(CartesianBounds {
+			x_min: Some(-1.5),
+			x_max: Some(2.5),
+			y_min: Some(0.1),
+			y_max: None,
+			z_min: None,
+			z_max: Some(1e-9),
+		})
+		.spherical_bounds(SphericalBounds {
+			range_min:     Some(0.0),
+			range_max:     Some(100.0),
+			elevation_min: Some(-1.0),
+			elevation_max: Some(1.0),
+			azimuth_start: Some(3.0),
+			azimuth_end:   Some(-3.0),
+		})
+		.index_bounds(IndexBounds {
+			row_min:    Some(0),
+			row_max:    Some(i64::MAX),
+			column_min: Some(i64::MIN),
+			column_max: Some(10),
+			return_min: None,
+			return_max: None,
+		})
+		.intensity_limits(IntensityLimits { intensity_min: Some(0.0), intensity_max: Some(4095.0) })
+		.transform(Transform {
+			rotation:    Quaternion { w: 0.5, x: 0.5, y: 0.5, z: 0.5 },
+			translation: Translation { x: 1.0, y: -2.0, z: 0.1 },
+		})
+		.acquisition_start(DateTime { gps_time: 1_000_000_000.25, atomic_reference: true })
+		.acquisition_end(DateTime { gps_time: 1_000_000_100.5, atomic_reference: false })
+		.sensor_vendor("Vendor".into())
+		.sensor_model("Model".into())
+		.sensor_serial("1234".into())
+		.sensor_hw_version("hw 1".into())
+		.sensor_sw_version("sw 2".into())
+		.sensor_fw_version("fw 3".into())
+		.temperature(21.5)
+		.humidity(45.0)
+		.atmospheric_pressure(101_325.0)
+		.build()
+		.expect("Invalid point cloud");
+	let point = [1.0, 2.0, 3.0, 0.5].map(RecordValue::Double).to_vec();
+	let mut writer = E57Writer::new(Cursor::new(Vec::new())).expect("Failed to create writer");
+	writer
+		.add_pointcloud(pc.clone(), std::iter::once(point))
+		.expect("Failed to write point cloud");
+	let bytes = writer.finalize().expect("Failed to finalize file").into_inner();
+
+	let reader = open(&bytes);
+	let read = reader.pointclouds()[0].clone();
+	assert_eq!(read.records, 1);
+	let mut expected = pc;
+	expected.file_offset = read.file_offset;
+	expected.records = read.records;
+	assert_eq!(read, expected);
+	assert!(reader.namespaces().iter().any(|(prefix, _)| prefix == "stdev"));
+}
+
+#[test]
+fn read_indented_xml() {
+	let pc = PointCloud::builder("{indented}")
+		.prototype(vec![
+			Record::CARTESIAN_X_F64,
+			Record::CARTESIAN_Y_F64,
+			Record::CARTESIAN_Z_F64,
+		])
+		.name("Indented scan".into())
+		.index_bounds(IndexBounds {
+			row_min:    Some(-5),
+			row_max:    Some(5),
+			column_min: None,
+			column_max: None,
+			return_min: None,
+			return_max: None,
+		})
+		.intensity_limits(IntensityLimits { intensity_min: Some(0.0), intensity_max: Some(4095.0) })
+		.acquisition_start(DateTime { gps_time: 1_000_000_000.25, atomic_reference: true })
+		.temperature(21.5)
+		.build()
+		.expect("Invalid point cloud");
+	let point = [1.0, 2.0, 3.0].map(RecordValue::Double).to_vec();
+	let mut writer = E57Writer::new(Cursor::new(Vec::new())).expect("Failed to create writer");
+	writer
+		.add_pointcloud(pc.clone(), std::iter::once(point))
+		.expect("Failed to write point cloud");
+	let bytes = writer.finalize().expect("Failed to finalize file").into_inner();
+
+	// Pretty-printed XML with CRLF line breaks and indentation around all values
+	let indented = rewrite_xml(&bytes, |xml| {
+		let xml = xml.replace('>', ">\r\n\t").replace("</", "\r\n</");
+		assert!(xml.contains("<temperature type=\"Float\">\r\n\t21.5\r\n</temperature>"));
+		xml
+	});
+	let read = open(&indented).pointclouds()[0].clone();
+	let mut expected = pc;
+	expected.file_offset = read.file_offset;
+	expected.records = read.records;
+	assert_eq!(read, expected);
+}
+
+#[test]
+fn write_points_from_reader() {
+	let prototype = [
+		Record {
+			name:      RecordName::CartesianX,
+			data_type: RecordDataType::ScaledInteger { min: -100_000, max: 100_000, scale: 0.001 },
+		},
+		Record::CARTESIAN_Y_F32,
+		Record::CARTESIAN_Z_F64,
+		Record::INTENSITY_U16,
+		Record::COLOR_RED_U8,
+		Record::COLOR_GREEN_U8,
+		Record::COLOR_BLUE_U8,
+		Record {
+			name:      RecordName::RowIndex,
+			data_type: RecordDataType::Integer { min: 0, max: 99 },
+		},
+	];
+	let values = (0..5000_i64)
+		.map(|i| {
+			vec![
+				RecordValue::ScaledInteger(i * 37 % 200_001 - 100_000),
+				RecordValue::Single(i as f32 * 0.5),
+				RecordValue::Double(-(i as f64) / 3.0),
+				RecordValue::Integer(i * 13 % 65_536),
+				RecordValue::Integer(i % 256),
+				RecordValue::Integer(i * 7 % 256),
+				RecordValue::Integer(255 - i % 256),
+				RecordValue::Integer(i % 100),
+			]
+		})
+		.collect::<Vec<_>>();
+	let mut source = roundtrip(&prototype, values.clone());
+	let pc = source.pointclouds()[0].clone();
+
+	let mut writer = E57Writer::new(Cursor::new(Vec::new())).expect("Failed to create writer");
+	let points = source.points(&pc).expect("Failed to create reader");
+	writer
+		.write_points(&prototype, points.map(|p| p.expect("Failed to read point")))
+		.expect("Failed to write points");
+	let mut copy = open(&writer.finalize().expect("Failed to finalize file").into_inner());
+	let copy_pc = copy.pointclouds()[0].clone();
+	let read = copy
+		.pointcloud_raw(&copy_pc)
+		.expect("Failed to create reader")
+		.collect::<Result<Vec<_>, _>>()
+		.expect("Failed to read points");
+	assert!(read == values);
+
+	let positions = source.positions(&pc).expect("Failed to read positions");
+	let bounds = copy_pc.cartesian_bounds.expect("Missing bounds");
+	let min = |axis: usize| positions.iter().map(|p| p[axis]).fold(f64::INFINITY, f64::min);
+	let max = |axis: usize| positions.iter().map(|p| p[axis]).fold(f64::NEG_INFINITY, f64::max);
+	assert_eq!(
+		[bounds.x_min, bounds.y_min, bounds.z_min],
+		[0, 1, 2].map(|axis| Some(min(axis)))
+	);
+	assert_eq!(
+		[bounds.x_max, bounds.y_max, bounds.z_max],
+		[0, 1, 2].map(|axis| Some(max(axis)))
+	);
+}
+
+#[test]
+fn write_invalid_states() {
+	let state = |name: RecordName, max: i64| Record { name, data_type: RecordDataType::Integer { min: 0, max } };
+	let prototype = [
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+		state(RecordName::CartesianInvalidState, 2),
+		Record::INTENSITY_U16,
+		state(RecordName::IsIntensityInvalid, 1),
+		Record::COLOR_RED_U8,
+		Record::COLOR_GREEN_U8,
+		Record::COLOR_BLUE_U8,
+		state(RecordName::IsColorInvalid, 1),
+	];
+	let mut valid = Point::default();
+	valid.position = [1.0, 2.0, 3.0];
+	valid.intensity = Some(1.0);
+	valid.color = Some([1.0, 0.0, 1.0]);
+	let mut invalid = Point::default();
+	invalid.position = [4.0, 5.0, 6.0];
+	invalid.cartesian_invalid = 2;
+
+	let mut writer = E57Writer::new(Cursor::new(Vec::new())).expect("Failed to create writer");
+	writer
+		.write_points(&prototype, [valid, invalid].into_iter())
+		.expect("Failed to write points");
+	let mut reader = open(&writer.finalize().expect("Failed to finalize file").into_inner());
+	let pc = reader.pointclouds()[0].clone();
+	let raw = reader
+		.pointcloud_raw(&pc)
+		.expect("Failed to create reader")
+		.collect::<Result<Vec<_>, _>>()
+		.expect("Failed to read points");
+	let states = |values: &RawValues| [3, 5, 9].map(|i| values[i].clone());
+	assert_eq!(states(&raw[0]), [0, 0, 0].map(RecordValue::Integer));
+	assert_eq!(states(&raw[1]), [2, 1, 1].map(RecordValue::Integer));
+
+	assert_eq!(
+		reader.positions(&pc).expect("Failed to read positions"),
+		[[1.0, 2.0, 3.0]]
+	);
+	let points = reader
+		.points(&pc)
+		.expect("Failed to create reader")
+		.collect::<Result<Vec<_>, _>>()
+		.expect("Failed to read points");
+	assert_eq!(
+		(points[0].intensity, points[0].color),
+		(Some(1.0), Some([1.0, 0.0, 1.0]))
+	);
+	assert_eq!((points[1].intensity, points[1].color), (None, None));
+}
+
+#[test]
+fn write_xyz_roundtrip() {
+	let points = (0..3000)
+		.map(|i| [i as f64 * 0.1, -(i as f64), 1e6 + i as f64])
+		.collect::<Vec<_>>();
+	let path = std::env::temp_dir().join(format!("e57_write_xyz_{}.e57", std::process::id()));
+	write_xyz(&path, &points).expect("Failed to write file");
+	let mut reader = Reader::from_file(&path).expect("Failed to open file");
+	std::fs::remove_file(&path).expect("Failed to remove file");
+
+	let pc = reader.pointclouds()[0].clone();
+	assert_eq!(reader.positions(&pc).expect("Failed to read positions"), points);
+	let bounds = pc.cartesian_bounds.expect("Missing bounds");
+	assert_eq!([bounds.x_min, bounds.x_max], [Some(0.0), Some(2999.0 * 0.1)]);
+	assert_eq!([bounds.y_min, bounds.y_max], [Some(-2999.0), Some(0.0)]);
+	assert_eq!([bounds.z_min, bounds.z_max], [Some(1e6), Some(1e6 + 2999.0)]);
+	assert_ne!(pc.guid, reader.guid());
+}
+
+#[test]
+fn verify_roundtrip_tolerance() {
+	let point = |position: [f64; 3], intensity: f32| {
+		let mut point = Point::default();
+		point.position = position;
+		point.intensity = Some(intensity);
+		point
+	};
+	let points = [
+		point([0.0, 1.0, -2.0], 0.0),
+		point([0.0004, 1.5, 2.25], 0.3),
+		point([-7.9996, 0.1, 3.0], 1.0),
+	];
+	let scaled = Record {
+		name:      RecordName::CartesianX,
+		data_type: RecordDataType::ScaledInteger { min: -10_000, max: 10_000, scale: 0.001 },
+	};
+	let integer = Record {
+		name:      RecordName::CartesianY,
+		data_type: RecordDataType::Integer { min: -10, max: 10 },
+	};
+	let prototype = [
+		scaled,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F32,
+		Record::INTENSITY_U16,
+	];
+	verify_roundtrip(&points, &prototype).expect("Values outside of the tolerance");
+
+	// Integer records are exact, 1.5 and 0.1 are not integral
+	let prototype = [scaled, integer, Record::CARTESIAN_Z_F32];
+	assert!(verify_roundtrip(&points, &prototype).is_err());
+	assert!(verify_roundtrip(&points[..1], &prototype).is_ok());
+	// Values outside of the record range cannot be written
+	assert!(verify_roundtrip(&[point([11.0, 0.0, 0.0], 0.0)], &prototype).is_err());
+}
+
+#[test]
+fn append_pointcloud() {
+	let prototype = [
+		Record::CARTESIAN_X_F64,
+		Record::CARTESIAN_Y_F64,
+		Record::CARTESIAN_Z_F64,
+	];
+	let values = |count: usize, z: f64| {
+		(0..count)
+			.map(|i| {
+				vec![
+					RecordValue::Double(i as f64),
+					RecordValue::Double(-(i as f64)),
+					RecordValue::Double(z),
+				]
+			})
+			.collect::<Vec<_>>()
+	};
+	let mut appended_prototype = prototype.to_vec();
+	appended_prototype.push(Record {
+		name:      RecordName::StdevX,
+		data_type: RecordDataType::F64,
+	});
+	let appended_values = values(3000, 2.0)
+		.into_iter()
+		.map(|mut v| {
+			v.push(RecordValue::Double(0.5));
+			v
+		})
+		.collect::<Vec<_>>();
+	let append = |bytes: Vec<u8>| {
+		let mut appender = E57Appender::new(Cursor::new(bytes)).expect("Failed to open file");
+		let pc = PointCloud::builder("{appended}")
+			.prototype(appended_prototype.clone())
+			.name("Appended".into())
+			.build()
+			.expect("Invalid point cloud");
+		appender
+			.add_pointcloud(pc, appended_values.clone().into_iter())
+			.expect("Failed to append point cloud");
+		appender.finalize().expect("Failed to finalize file").into_inner()
+	};
+	let read = |reader: &mut Reader, pc: &PointCloud| {
+		reader
+			.pointcloud_raw(pc)
+			.expect("Failed to create reader")
+			.collect::<Result<Vec<_>, _>>()
+			.expect("Failed to read points")
+	};
+
+	let original = write(&prototype, values(1000, 1.0));
+	let mut appender = E57Appender::new(Cursor::new(original.clone())).expect("Failed to open file");
+	let pc = PointCloud::builder("{pc}")
+		.prototype(prototype.to_vec())
+		.build()
+		.expect("Invalid point cloud");
+	assert!(appender.add_pointcloud(pc, std::iter::empty()).is_err());
+	let mut reader = open(&append(original.clone()));
+	reader.validate_crc().expect("Invalid checksums");
+	let pointclouds = reader.pointclouds();
+	assert_eq!(pointclouds.len(), 2);
+	assert_eq!(reader.guid(), open(&original).guid());
+	assert!(read(&mut reader, &pointclouds[0]) == values(1000, 1.0));
+	assert!(read(&mut reader, &pointclouds[1]) == appended_values);
+	assert_eq!(pointclouds[1].name.as_deref(), Some("Appended"));
+	assert!(reader.namespaces().iter().any(|(prefix, _)| prefix == "stdev"));
+
+	// Files without point clouds can have an empty element for the data3D vector
+	let empty = E57Writer::new(Cursor::new(Vec::new())).expect("Failed to create writer");
+	let empty = empty.finalize().expect("Failed to finalize file").into_inner();
+	let empty = rewrite_xml(&empty, |xml| xml.replace("></data3D>", "/>"));
+	let mut reader = open(&append(empty));
+	let pointclouds = reader.pointclouds();
+	assert_eq!(pointclouds.len(), 1);
+	assert!(read(&mut reader, &pointclouds[0]) == appended_values);
+}